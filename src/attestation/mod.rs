@@ -0,0 +1,102 @@
+//! Simulates the on-chain verification of a quote with an `eth_call` to the
+//! DCAP attestation entrypoint, to learn whether it would pass before paying gas.
+
+pub mod output;
+
+use alloy::{
+    eips::BlockId,
+    primitives::Bytes,
+    rpc::json_rpc::ErrorPayload,
+    sol,
+    sol_types::{Revert, SolError},
+    transports::RpcError,
+};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::quote::parse_quote;
+use crate::{MissingCollateral, PccsReader};
+
+pub use output::{TcbStatus, VerifiedOutput};
+
+sol! {
+    #[sol(rpc)]
+    interface IDcapAttestation {
+        #[derive(Debug)]
+        function verifyAndAttestOnChain(bytes calldata rawQuote) external payable returns (bool success, bytes memory output);
+    }
+}
+
+/// The outcome of verifying a quote on chain, with the block the call was made at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OnchainVerification {
+    pub block: BlockId,
+    pub verified: bool,
+    pub output: Option<VerifiedOutput>,
+    // the failure message or the revert reason
+    pub error: Option<String>,
+    // what the quote lacks on chain, None when it verified
+    pub missing: MissingCollateral,
+}
+
+pub async fn simulate_onchain_verification(raw_quote: &[u8]) -> Result<OnchainVerification> {
+    PccsReader::default().simulate_onchain_verification(raw_quote).await
+}
+
+impl PccsReader {
+    /// Calls `verifyAndAttestOnChain` without sending a transaction. When the quote does not
+    /// verify, the collateral it lacks is looked up at the same block.
+    pub async fn simulate_onchain_verification(&self, raw_quote: &[u8]) -> Result<OnchainVerification> {
+        parse_quote(raw_quote)?;
+        let reader = self.pin_block().await?;
+
+        let attestation = IDcapAttestation::new(reader.network().dcap_attestation, reader.provider());
+        let call = reader
+            .prepare_call(attestation.verifyAndAttestOnChain(raw_quote.to_vec().into()))
+            .call()
+            .await;
+
+        let (output, error) = match call {
+            Ok(ret) if ret.success => (Some(VerifiedOutput::from_bytes(&ret.output)?), None),
+            // the verifiers return the reason of the failure as a string
+            Ok(ret) => (None, Some(String::from_utf8_lossy(&ret.output).into_owned())),
+            // only a revert is a failed verification, other error responses are node errors
+            Err(alloy::contract::Error::TransportError(RpcError::ErrorResp(payload))) => {
+                match revert_reason(&payload) {
+                    Some(reason) => (None, Some(reason)),
+                    None => {
+                        let error = RpcError::ErrorResp(payload);
+                        return Err(alloy::contract::Error::TransportError(error).into());
+                    }
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let missing = match output {
+            Some(_) => MissingCollateral::None,
+            None => reader.missing_collaterals(raw_quote).await?,
+        };
+        Ok(OnchainVerification {
+            block: reader.block(),
+            verified: output.is_some(),
+            output,
+            error,
+            missing,
+        })
+    }
+}
+
+// The reason of a reverted call: the `Error(string)` message, or the raw revert data.
+// None when the error is not a revert, nodes report those with code 3 and the revert data.
+fn revert_reason(payload: &ErrorPayload) -> Option<String> {
+    let data = payload.try_data_as::<Bytes>().and_then(|data| data.ok());
+    match (payload.code, data) {
+        (_, Some(data)) if !data.is_empty() => Some(match Revert::abi_decode(&data, true) {
+            Ok(revert) => revert.reason,
+            Err(_) => format!("{}: {}", payload.message, data),
+        }),
+        (3, _) | (_, Some(_)) => Some(payload.message.clone()),
+        _ => None,
+    }
+}
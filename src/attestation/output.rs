@@ -0,0 +1,168 @@
+//! The output of a successful on-chain verification, packed by the V3 and V4 verifiers as
+//! `abi.encodePacked(quoteVersion, tee, tcbStatus, fmspcBytes, quoteBody)`, followed by
+//! `abi.encode(advisoryIDs)` on the verifiers that report advisories.
+
+use alloy::sol_types::{sol_data, SolType};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::constants::TDX_TEE_TYPE;
+use crate::quote::{QuoteBody, ENCLAVE_REPORT_LENGTH, TD10_REPORT_LENGTH};
+
+// uint16 quoteVersion, bytes4 tee, uint8 tcbStatus, bytes6 fmspcBytes
+const OUTPUT_HEADER_LENGTH: usize = 13;
+
+type AdvisoryIds = sol_data::Array<sol_data::String>;
+
+/// The TCB status the verifiers settle on, in the order of their `TCBStatus` enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TcbStatus {
+    UpToDate,
+    SwHardeningNeeded,
+    ConfigurationAndSwHardeningNeeded,
+    ConfigurationNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+    Unrecognized,
+}
+
+impl TryFrom<u8> for TcbStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => TcbStatus::UpToDate,
+            1 => TcbStatus::SwHardeningNeeded,
+            2 => TcbStatus::ConfigurationAndSwHardeningNeeded,
+            3 => TcbStatus::ConfigurationNeeded,
+            4 => TcbStatus::OutOfDate,
+            5 => TcbStatus::OutOfDateConfigurationNeeded,
+            6 => TcbStatus::Revoked,
+            7 => TcbStatus::Unrecognized,
+            _ => return Err(anyhow::anyhow!("Unknown TCB status {}", value)),
+        })
+    }
+}
+
+impl From<TcbStatus> for u8 {
+    fn from(status: TcbStatus) -> u8 {
+        status as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifiedOutput {
+    pub quote_version: u16,
+    // as in the quote header, 0x81 for TDX
+    pub tee_type: u32,
+    pub tcb_status: TcbStatus,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub fmspc: [u8; 6],
+    // the enclave report or TD report of the quote
+    pub quote_body: QuoteBody,
+    // None when the verifier does not report advisories at all
+    pub advisory_ids: Option<Vec<String>>,
+}
+
+impl VerifiedOutput {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self> {
+        if raw.len() < OUTPUT_HEADER_LENGTH {
+            return Err(anyhow::anyhow!("Verified output too short: {} bytes", raw.len()));
+        }
+        let quote_version = u16::from_be_bytes([raw[0], raw[1]]);
+        // copied from the quote header, little endian
+        let tee_type = u32::from_le_bytes([raw[2], raw[3], raw[4], raw[5]]);
+        let tcb_status = TcbStatus::try_from(raw[6])?;
+        let fmspc = raw[7..OUTPUT_HEADER_LENGTH].try_into().unwrap();
+
+        let body_end = OUTPUT_HEADER_LENGTH
+            + if tee_type == TDX_TEE_TYPE {
+                TD10_REPORT_LENGTH
+            } else {
+                ENCLAVE_REPORT_LENGTH
+            };
+        let quote_body = QuoteBody::from_bytes(tee_type, &raw[OUTPUT_HEADER_LENGTH..])?;
+        let advisory_ids = match raw.get(body_end..) {
+            Some([]) | None => None,
+            Some(encoded) => Some(AdvisoryIds::abi_decode(encoded, true)?),
+        };
+
+        Ok(VerifiedOutput {
+            quote_version,
+            tee_type,
+            tcb_status,
+            fmspc,
+            quote_body,
+            advisory_ids,
+        })
+    }
+
+    /// Packs the output the way the verifiers do.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(OUTPUT_HEADER_LENGTH + TD10_REPORT_LENGTH);
+        raw.extend_from_slice(&self.quote_version.to_be_bytes());
+        raw.extend_from_slice(&self.tee_type.to_le_bytes());
+        raw.push(self.tcb_status.into());
+        raw.extend_from_slice(&self.fmspc);
+        raw.extend_from_slice(&self.quote_body.to_bytes());
+        if let Some(advisory_ids) = &self.advisory_ids {
+            raw.extend_from_slice(&AdvisoryIds::abi_encode(advisory_ids));
+        }
+        raw
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constants::SGX_TEE_TYPE;
+    use crate::quote::{parse_quote, HEADER_LENGTH};
+
+    fn quote(hex_quote: &str) -> Vec<u8> {
+        hex::decode(hex_quote.trim()).unwrap()
+    }
+
+    // What a verifier emits for the quote
+    fn packed(raw_quote: &[u8], body_length: usize, tcb_status: u8) -> Vec<u8> {
+        let mut raw = raw_quote[0..2].iter().rev().copied().collect::<Vec<_>>();
+        raw.extend_from_slice(&raw_quote[4..8]);
+        raw.push(tcb_status);
+        raw.extend_from_slice(&[0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        raw.extend_from_slice(&raw_quote[HEADER_LENGTH..HEADER_LENGTH + body_length]);
+        raw
+    }
+
+    #[test]
+    fn test_sgx_output_round_trip() {
+        let raw_quote = quote(include_str!("../../tests/data/v3_sgx_quote.hex"));
+        let raw = packed(&raw_quote, ENCLAVE_REPORT_LENGTH, 1);
+
+        let output = VerifiedOutput::from_bytes(&raw).unwrap();
+        assert_eq!((output.quote_version, output.tee_type), (3, SGX_TEE_TYPE));
+        assert_eq!(output.tcb_status, TcbStatus::SwHardeningNeeded);
+        assert_eq!(output.quote_body, parse_quote(&raw_quote).unwrap().1);
+        assert_eq!(output.advisory_ids, None);
+        assert_eq!(output.to_bytes(), raw);
+    }
+
+    #[test]
+    fn test_tdx_output_round_trip() {
+        let raw_quote = quote(include_str!("../../tests/data/v4_tdx_quote.hex"));
+        let mut raw = packed(&raw_quote, TD10_REPORT_LENGTH, 4);
+        let advisory_ids = vec!["INTEL-SA-00837".to_string(), "INTEL-SA-00960".to_string()];
+        raw.extend_from_slice(&AdvisoryIds::abi_encode(&advisory_ids));
+
+        let output = VerifiedOutput::from_bytes(&raw).unwrap();
+        assert_eq!((output.quote_version, output.tee_type), (4, TDX_TEE_TYPE));
+        assert_eq!(output.tcb_status, TcbStatus::OutOfDate);
+        assert!(matches!(output.quote_body, QuoteBody::Td10Report(_)));
+        assert_eq!(output.advisory_ids.as_deref(), Some(&advisory_ids[..]));
+        assert_eq!(output.to_bytes(), raw);
+
+        // truncated body, unknown status
+        assert!(VerifiedOutput::from_bytes(&raw[..100]).is_err());
+        raw[6] = 8;
+        assert!(VerifiedOutput::from_bytes(&raw).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Missing collateral scanning over many quotes at once.
+//! Every distinct DAO lookup is issued once, however many quotes need it.
+
+use std::collections::HashMap;
+
+use alloy::eips::BlockId;
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::{Serialize, Serializer};
+
+use crate::parser::try_get_pck_fmspc_and_issuer;
+use crate::pccs::enclave_id::EnclaveIdType;
+use crate::pccs::is_not_stored;
+use crate::quote::QuoteHeader;
+use crate::{qe_identity_type, tcb_type_and_version, MissingCollateral, PccsReader, CA};
+
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The outcome of scanning a single quote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuoteScan {
+    // position of the quote in the batch
+    pub index: usize,
+    pub fmspc: Option<String>,
+    #[serde(serialize_with = "serialize_ca")]
+    pub pck_ca: Option<CA>,
+    // the first missing collateral, checked in the same order as
+    // find_missing_collaterals_from_quote
+    pub missing: MissingCollateral,
+    // why the quote could not be parsed, nothing is looked up for it then,
+    // or why a DAO read it needs failed, the missing collateral is unknown then
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchReport {
+    pub quotes: Vec<QuoteScan>,
+    // every distinct missing collateral across the batch
+    pub missing: Vec<MissingCollateral>,
+    // number of DAO queries issued
+    pub lookups: usize,
+    // the block every query was made at
+    pub block: BlockId,
+}
+
+impl BatchReport {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.quotes.iter().all(|quote| quote.error.is_none())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Lookup {
+    Pcs(CA),
+    // Id, Version
+    QeIdentity(EnclaveIdType, u32),
+    // TcbType, Fmspc, Version
+    TcbInfo(u8, String, u32),
+}
+
+pub async fn find_missing_collaterals_from_quotes<Q: AsRef<[u8]>>(
+    raw_quotes: &[Q],
+    concurrency: usize,
+) -> Result<BatchReport> {
+    PccsReader::default()
+        .find_missing_collaterals_from_quotes(raw_quotes, concurrency)
+        .await
+}
+
+impl PccsReader {
+    /// Scans every quote for missing collateral with at most `concurrency` DAO queries in flight.
+    /// Fails when the block to scan at cannot be resolved, failed DAO reads are reported per quote.
+    pub async fn find_missing_collaterals_from_quotes<Q: AsRef<[u8]>>(
+        &self,
+        raw_quotes: &[Q],
+        concurrency: usize,
+    ) -> Result<BatchReport> {
+        let reader = self.pin_block().await?;
+
+        let mut distinct: Vec<Lookup> = Vec::new();
+        let mut index_of: HashMap<Lookup, usize> = HashMap::new();
+
+        // Step 1: parse every quote and collect the lookups it needs, in check order
+        let parsed: Vec<Result<(String, CA, Vec<usize>)>> = raw_quotes
+            .iter()
+            .map(|raw_quote| {
+                let (fmspc, pck_ca, lookups) = quote_lookups(raw_quote.as_ref())?;
+                let indexes: Vec<usize> = lookups
+                    .into_iter()
+                    .map(|lookup| {
+                        *index_of.entry(lookup.clone()).or_insert_with(|| {
+                            distinct.push(lookup);
+                            distinct.len() - 1
+                        })
+                    })
+                    .collect();
+                Ok((fmspc, pck_ca, indexes))
+            })
+            .collect();
+
+        // Step 2: query each distinct lookup once
+        let reader = &reader;
+        let results: Vec<std::result::Result<Option<MissingCollateral>, String>> =
+            stream::iter(&distinct)
+                .map(|lookup| async move {
+                    reader
+                        .lookup(lookup)
+                        .await
+                        .map_err(|e| format!("{:?} could not be read: {}", lookup, e))
+                })
+                .buffered(concurrency.max(1))
+                .collect()
+                .await;
+
+        // Step 3: fan the results back out to the quotes
+        let quotes = parsed
+            .into_iter()
+            .enumerate()
+            .map(|(index, parsed)| match parsed {
+                Ok((fmspc, pck_ca, indexes)) => {
+                    // the first lookup in check order that is missing or failed decides
+                    let first = indexes.into_iter().find_map(|i| match &results[i] {
+                        Ok(missing) => missing.clone().map(Ok),
+                        Err(e) => Some(Err(e.clone())),
+                    });
+                    let (missing, error) = match first {
+                        Some(Ok(missing)) => (missing, None),
+                        Some(Err(e)) => (MissingCollateral::None, Some(e)),
+                        None => (MissingCollateral::None, None),
+                    };
+                    QuoteScan {
+                        index,
+                        fmspc: Some(fmspc),
+                        pck_ca: Some(pck_ca),
+                        missing,
+                        error,
+                    }
+                }
+                Err(e) => QuoteScan {
+                    index,
+                    fmspc: None,
+                    pck_ca: None,
+                    missing: MissingCollateral::None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect();
+
+        Ok(BatchReport {
+            quotes,
+            missing: results.into_iter().filter_map(|result| result.ok().flatten()).collect(),
+            lookups: distinct.len(),
+            block: reader.block(),
+        })
+    }
+
+    // The collateral missing for a lookup, only what the DAO does not store counts as missing
+    async fn lookup(&self, lookup: &Lookup) -> Result<Option<MissingCollateral>> {
+        let missing = match lookup {
+            // only the certificate of the TCB Signing CA is needed
+            Lookup::Pcs(CA::SIGNING) => match self.get_certificate_by_id(CA::SIGNING).await {
+                Ok((cert, _)) if !cert.is_empty() => None,
+                Ok(_) => Some(MissingCollateral::PCS(CA::SIGNING, true, false)),
+                Err(e) if is_not_stored(&e) => Some(MissingCollateral::PCS(CA::SIGNING, true, false)),
+                Err(e) => return Err(e),
+            },
+            Lookup::Pcs(ca) => match self.get_certificate_by_id(*ca).await {
+                Ok((cert, crl)) if !cert.is_empty() && !crl.is_empty() => None,
+                Ok((cert, _)) if !cert.is_empty() => Some(MissingCollateral::PCS(*ca, false, true)),
+                Ok(_) => Some(MissingCollateral::PCS(*ca, true, true)),
+                Err(e) if is_not_stored(&e) => Some(MissingCollateral::PCS(*ca, true, true)),
+                Err(e) => return Err(e),
+            },
+            Lookup::QeIdentity(id, version) => match self.get_enclave_identity(*id, *version).await {
+                Ok(_) => None,
+                Err(e) if is_not_stored(&e) => Some(MissingCollateral::QEIdentity(*id, *version)),
+                Err(e) => return Err(e),
+            },
+            Lookup::TcbInfo(tcb_type, fmspc, version) => {
+                match self.get_tcb_info(*tcb_type, fmspc, *version).await {
+                    Ok(_) => None,
+                    Err(e) if is_not_stored(&e) => {
+                        Some(MissingCollateral::FMSPCTCB(*tcb_type, fmspc.clone(), *version))
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        Ok(missing)
+    }
+}
+
+fn quote_lookups(raw_quote: &[u8]) -> Result<(String, CA, [Lookup; 5])> {
+    let header = QuoteHeader::from_bytes(raw_quote)?;
+
+    let (fmspc, pck_ca) = try_get_pck_fmspc_and_issuer(raw_quote, header.version, header.tee_type)?;
+
+    let (tcb_type, tcb_version) = tcb_type_and_version(header.tee_type, header.version);
+    let lookups = [
+        Lookup::Pcs(CA::ROOT),
+        Lookup::QeIdentity(qe_identity_type(header.tee_type), header.version as u32),
+        Lookup::TcbInfo(tcb_type, fmspc.clone(), tcb_version),
+        Lookup::Pcs(CA::SIGNING),
+        Lookup::Pcs(pck_ca),
+    ];
+
+    Ok((fmspc, pck_ca, lookups))
+}
+
+fn serialize_ca<S: Serializer>(ca: &Option<CA>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    ca.map(|ca| format!("{:?}", ca)).serialize(serializer)
+}
@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use clap::Parser;
+
+use pccs_reader_rs::cache::{CacheConfig, CollateralCache};
+use pccs_reader_rs::{server, NetworkProfile, PccsReader};
+
+/// Serves the Intel PCCS API from the Automata On-chain PCCS
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Network profile to read the collateral from
+    #[arg(long, default_value = "automata-testnet")]
+    network: String,
+
+    /// Overrides the RPC URL of the network profile
+    #[arg(long)]
+    rpc_url: Option<String>,
+
+    /// Reads the DAO addresses from the PCCS router instead of the network profile
+    #[arg(long)]
+    resolve_addresses: bool,
+
+    /// PCCS router the DAO addresses are read from, implies --resolve-addresses
+    #[arg(long)]
+    pccs_router: Option<Address>,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    listen: String,
+
+    /// Caches the collateral in memory until its nextUpdate
+    #[arg(long)]
+    cache: bool,
+
+    /// Also keeps the cache in this file across restarts, implies --cache
+    #[arg(long)]
+    cache_file: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut network = NetworkProfile::from_name(&args.network)?;
+    if let Some(rpc_url) = args.rpc_url.as_deref() {
+        network = network.with_rpc_url(rpc_url);
+    }
+    if let Some(pccs_router) = args.pccs_router {
+        network.pccs_router = pccs_router;
+    }
+    let mut reader = PccsReader::new(network)?;
+    if args.resolve_addresses || args.pccs_router.is_some() {
+        reader = reader.resolve_addresses().await?;
+        println!("Resolved addresses:\n{}", reader.resolved_addresses().unwrap());
+    }
+    if args.cache || args.cache_file.is_some() {
+        let cache = CollateralCache::new(CacheConfig {
+            path: args.cache_file,
+            ..Default::default()
+        })?;
+        reader = reader.with_cache(Arc::new(cache));
+    }
+
+    let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+    println!(
+        "Serving the {} PCCS on http://{}",
+        reader.network().name,
+        listener.local_addr()?
+    );
+
+    axum::serve(listener, server::router(reader)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::io::Read;
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuoteFormat {
+    Auto,
+    Raw,
+    Hex,
+    Base64,
+}
+
+/// Reads a quote from a file, or from stdin when the path is `-`.
+pub fn read_quote(path: &str, format: QuoteFormat) -> Result<Vec<u8>> {
+    let content = if path == "-" {
+        let mut content = Vec::new();
+        std::io::stdin().read_to_end(&mut content)?;
+        content
+    } else {
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?
+    };
+
+    decode_quote(&content, format)
+}
+
+pub fn decode_quote(content: &[u8], format: QuoteFormat) -> Result<Vec<u8>> {
+    let text = || {
+        std::str::from_utf8(content)
+            .map(|s| s.split_whitespace().collect::<String>())
+            .map_err(|_| anyhow::Error::msg("quote is not valid text"))
+    };
+
+    match format {
+        QuoteFormat::Raw => Ok(content.to_vec()),
+        QuoteFormat::Hex => {
+            let text = text()?;
+            Ok(hex::decode(text.strip_prefix("0x").unwrap_or(&text))?)
+        }
+        QuoteFormat::Base64 => Ok(STANDARD.decode(text()?)?),
+        // raw quotes start with a binary version number, so text can only be hex or base64
+        QuoteFormat::Auto => match text() {
+            Ok(text) if !text.is_empty() => decode_quote(content, QuoteFormat::Hex)
+                .or_else(|_| decode_quote(content, QuoteFormat::Base64))
+                .or_else(|_| Ok(content.to_vec())),
+            _ => Ok(content.to_vec()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_quote() {
+        let raw = vec![0x04, 0x00, 0x02, 0x00, 0x81, 0x00, 0x00, 0x00, 0xfb];
+
+        let hex = format!("0x{}\n", hex::encode(&raw));
+        let base64 = STANDARD.encode(&raw);
+
+        assert_eq!(decode_quote(&raw, QuoteFormat::Auto).unwrap(), raw);
+        assert_eq!(decode_quote(hex.as_bytes(), QuoteFormat::Auto).unwrap(), raw);
+        assert_eq!(decode_quote(base64.as_bytes(), QuoteFormat::Auto).unwrap(), raw);
+        assert_eq!(decode_quote(base64.as_bytes(), QuoteFormat::Base64).unwrap(), raw);
+        assert!(decode_quote(base64.as_bytes(), QuoteFormat::Hex).is_err());
+    }
+}
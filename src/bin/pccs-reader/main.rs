@@ -0,0 +1,866 @@
+mod input;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use alloy::eips::BlockId;
+use alloy::primitives::utils::{format_ether, format_units};
+use alloy::primitives::{Address, TxHash, U256};
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+
+use pccs_reader_rs::batch::DEFAULT_CONCURRENCY;
+use pccs_reader_rs::diff::{DiffStatus, NetworkDiff, Side};
+use pccs_reader_rs::freshness::{CollateralVersion, Freshness};
+use pccs_reader_rs::parser::try_get_pck_fmspc_and_issuer;
+use pccs_reader_rs::quote::{parse_quote, QuoteBody};
+use pccs_reader_rs::source::intel_pcs::INTEL_PCS_URL;
+use pccs_reader_rs::source::{
+    CollateralSource, DirectoryCollateralSource, IntelPcsCollateralSource, SnapshotCollateralSource,
+};
+use pccs_reader_rs::writer::{signer_from_keystore, signer_from_private_key, UpsertCall};
+use pccs_reader_rs::{find_missing_collaterals_from_source, MissingCollateral, NetworkProfile, PccsReader};
+
+use input::{read_quote, QuoteFormat};
+
+/// Checks and inspects quotes against the Automata On-chain PCCS
+#[derive(Debug, Parser)]
+#[command(name = "pccs-reader", version)]
+struct Cli {
+    /// Network profile to read the collateral from
+    #[arg(long, global = true, default_value = "automata-testnet")]
+    network: String,
+
+    /// Overrides the RPC URL of the network profile
+    #[arg(long, global = true)]
+    rpc_url: Option<String>,
+
+    /// RPC URLs tried in order when the main one fails, can be repeated
+    #[arg(long = "fallback-rpc-url", global = true)]
+    fallback_rpc_urls: Vec<String>,
+
+    /// Reads the chain at a block number, hash or tag instead of the latest block
+    #[arg(long, global = true)]
+    block: Option<BlockId>,
+
+    /// Reads the DAO addresses from the PCCS router instead of the network profile
+    #[arg(long, global = true)]
+    resolve_addresses: bool,
+
+    /// PCCS router the DAO addresses are read from, implies --resolve-addresses
+    #[arg(long, global = true, conflicts_with = "dcap_attestation")]
+    pccs_router: Option<Address>,
+
+    /// DCAP attestation contract whose PCCS router is used, implies --resolve-addresses
+    #[arg(long, global = true)]
+    dcap_attestation: Option<Address>,
+
+    /// Prints machine readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Reports the first collateral the quote needs that is missing on chain
+    Check {
+        #[command(flatten)]
+        quote: QuoteArgs,
+
+        /// Checks against a snapshot file instead of the chain, with no network
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Prints the header, the report body, the FMSPC and the PCK CA of the quote
+    Inspect(QuoteArgs),
+    /// Verifies the quote with an eth_call to the DCAP attestation contract, sending nothing
+    Simulate(QuoteArgs),
+    /// Parses the collateral of the quote with the helper contracts and off chain, and reports where they differ
+    ParseCheck(QuoteArgs),
+    /// Checks that the RPC URL serves the chain of the network profile and that the DAOs are deployed
+    Doctor {
+        /// JSON file of keccak256 hashes of the DAO runtime code, keyed by enclave_id_dao, fmspc_tcb_dao, pcs_dao and pck_dao
+        #[arg(long)]
+        code_hashes: Option<PathBuf>,
+    },
+    /// Reports the missing collateral of many quotes, querying each distinct collateral once
+    Scan {
+        /// Quote files, or directories whose files are all quotes
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Encoding of the quotes, detected from the content by default
+        #[arg(long, value_enum, default_value_t = QuoteFormat::Auto)]
+        format: QuoteFormat,
+
+        /// Maximum number of DAO queries in flight
+        #[arg(long, default_value_t = DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+    /// Reports the collateral of the quote that is older on chain than in the source
+    Freshness {
+        #[command(flatten)]
+        quote: QuoteArgs,
+
+        #[command(flatten)]
+        source: SourceArgs,
+    },
+    /// Estimates the gas and cost of filling the collateral the quote lacks and verifying it
+    Cost {
+        #[command(flatten)]
+        quote: QuoteArgs,
+
+        #[command(flatten)]
+        source: SourceArgs,
+    },
+    /// Writes every collateral needed by the quote to files
+    Collateral {
+        #[command(flatten)]
+        quote: QuoteArgs,
+
+        /// Directory the collateral is written to
+        #[arg(long, short, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Writes every certificate, CRL, identity and the TCB Info of the FMSPCs to one file
+    Snapshot {
+        /// FMSPC whose TCB Info is included, can be repeated
+        #[arg(long = "fmspc")]
+        fmspcs: Vec<String>,
+
+        /// File the snapshot is written to
+        #[arg(long, short, default_value = "snapshot.json")]
+        out: PathBuf,
+    },
+    /// Compares the certificates, CRLs, identities and TCB Infos of two networks
+    Diff {
+        #[command(flatten)]
+        networks: NetworkPair,
+    },
+    /// Upserts on the second network the collateral that is newer on the first one
+    Sync {
+        #[command(flatten)]
+        networks: NetworkPair,
+
+        #[command(flatten)]
+        signer: SignerArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+struct NetworkPair {
+    /// Network profile compared, or synced from
+    network_a: String,
+
+    /// Network profile compared with, or synced to
+    network_b: String,
+
+    /// Overrides the RPC URL of the first network
+    #[arg(long)]
+    rpc_url_a: Option<String>,
+
+    /// Overrides the RPC URL of the second network
+    #[arg(long)]
+    rpc_url_b: Option<String>,
+
+    /// FMSPC whose TCB Info is compared, can be repeated
+    #[arg(long = "fmspc")]
+    fmspcs: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct SignerArgs {
+    /// Reports what would be upserted without signing nor sending anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Environment variable holding the hex private key of the sender
+    #[arg(long, default_value = "PCCS_SYNC_PRIVATE_KEY")]
+    private_key_env: String,
+
+    /// Keystore file of the sender, used instead of the private key
+    #[arg(long)]
+    keystore: Option<PathBuf>,
+
+    /// Environment variable holding the keystore password
+    #[arg(long, default_value = "PCCS_SYNC_KEYSTORE_PASSWORD")]
+    keystore_password_env: String,
+}
+
+impl SignerArgs {
+    // None for a dry run
+    fn signer(&self) -> Result<Option<PrivateKeySigner>> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        let signer = match &self.keystore {
+            Some(keystore) => {
+                let password = std::env::var(&self.keystore_password_env)
+                    .map_err(|_| anyhow::anyhow!("{} is not set", self.keystore_password_env))?;
+                signer_from_keystore(keystore, &password)?
+            }
+            None => {
+                let private_key = std::env::var(&self.private_key_env)
+                    .map_err(|_| anyhow::anyhow!("{} is not set", self.private_key_env))?;
+                signer_from_private_key(&private_key)?
+            }
+        };
+        Ok(Some(signer))
+    }
+}
+
+impl NetworkPair {
+    // Each side resolves its addresses from its own router when asked to, the global
+    // flags that name a block, RPC URL or contract of a single chain are rejected
+    async fn readers(&self, cli: &Cli) -> Result<(PccsReader, PccsReader)> {
+        if cli.block.is_some()
+            || cli.rpc_url.is_some()
+            || !cli.fallback_rpc_urls.is_empty()
+            || cli.pccs_router.is_some()
+            || cli.dcap_attestation.is_some()
+        {
+            return Err(anyhow::anyhow!(
+                "--block, --rpc-url, --fallback-rpc-url, --pccs-router and --dcap-attestation name a single chain, they do not apply to two networks, use --rpc-url-a and --rpc-url-b"
+            ));
+        }
+        let network = |name: &str, rpc_url: Option<&str>| -> Result<NetworkProfile> {
+            let network = NetworkProfile::from_name(name)?;
+            Ok(match rpc_url {
+                Some(rpc_url) => network.with_rpc_url(rpc_url),
+                None => network,
+            })
+        };
+        Ok((
+            cli.network_reader(network(&self.network_a, self.rpc_url_a.as_deref())?)
+                .await?,
+            cli.network_reader(network(&self.network_b, self.rpc_url_b.as_deref())?)
+                .await?,
+        ))
+    }
+}
+
+#[derive(Debug, Args)]
+struct QuoteArgs {
+    /// Path to the quote, or - for stdin
+    quote: String,
+
+    /// Encoding of the quote, detected from the content by default
+    #[arg(long, value_enum, default_value_t = QuoteFormat::Auto)]
+    format: QuoteFormat,
+}
+
+#[derive(Debug, Args)]
+struct SourceArgs {
+    /// Base URL of the Intel PCS API, or of a server speaking it
+    #[arg(long, default_value = INTEL_PCS_URL)]
+    pcs_url: String,
+
+    /// Reads the collateral from a directory instead of the Intel PCS API
+    #[arg(long, conflicts_with = "pcs_url")]
+    source_dir: Option<PathBuf>,
+}
+
+impl SourceArgs {
+    fn source(&self) -> Box<dyn CollateralSource> {
+        match &self.source_dir {
+            Some(dir) => Box::new(DirectoryCollateralSource::new(dir)),
+            None => Box::new(IntelPcsCollateralSource::new(&self.pcs_url)),
+        }
+    }
+}
+
+impl QuoteArgs {
+    fn read(&self) -> Result<Vec<u8>> {
+        let raw_quote = read_quote(&self.quote, self.format)?;
+        // reject anything the reader would not understand before going further
+        parse_quote(&raw_quote)?;
+        Ok(raw_quote)
+    }
+}
+
+impl Cli {
+    fn network(&self) -> Result<NetworkProfile> {
+        let mut network = NetworkProfile::from_name(&self.network)?;
+        if let Some(rpc_url) = self.rpc_url.as_deref() {
+            network = network.with_rpc_url(rpc_url);
+        }
+        if !self.fallback_rpc_urls.is_empty() {
+            network.fallback_rpc_urls = self.fallback_rpc_urls.clone();
+        }
+        Ok(network)
+    }
+
+    async fn reader(&self) -> Result<PccsReader> {
+        self.network_reader(self.network()?).await
+    }
+
+    async fn network_reader(&self, network: NetworkProfile) -> Result<PccsReader> {
+        let reader = match (self.pccs_router, self.dcap_attestation) {
+            (Some(pccs_router), _) => PccsReader::from_router(network, pccs_router).await?,
+            (None, Some(dcap_attestation)) => {
+                PccsReader::from_dcap_attestation(network, dcap_attestation).await?
+            }
+            (None, None) if self.resolve_addresses => {
+                PccsReader::new(network)?.resolve_addresses().await?
+            }
+            (None, None) => PccsReader::new(network)?,
+        };
+        if let Some(resolved) = reader.resolved_addresses() {
+            eprintln!("Resolved addresses:\n{}", resolved);
+        }
+        Ok(match self.block {
+            Some(block) => reader.at_block(block),
+            None => reader,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn run(cli: &Cli) -> Result<ExitCode> {
+    match &cli.command {
+        Command::Check { quote, snapshot } => match snapshot {
+            Some(snapshot) => check_offline(cli, quote, snapshot).await,
+            None => check(cli, quote).await,
+        },
+        Command::Inspect(quote) => inspect(cli, quote),
+        Command::Simulate(quote) => simulate(cli, quote).await,
+        Command::ParseCheck(quote) => parse_check(cli, quote).await,
+        Command::Doctor { code_hashes } => doctor(cli, code_hashes.as_deref()).await,
+        Command::Scan {
+            paths,
+            format,
+            concurrency,
+        } => scan(cli, paths, *format, *concurrency).await,
+        Command::Freshness { quote, source } => freshness(cli, quote, source).await,
+        Command::Cost { quote, source } => cost(cli, quote, source).await,
+        Command::Collateral { quote, out_dir } => collateral(cli, quote, out_dir).await,
+        Command::Snapshot { fmspcs, out } => snapshot(cli, fmspcs, out).await,
+        Command::Diff { networks } => diff(cli, networks).await,
+        Command::Sync { networks, signer } => sync(cli, networks, signer.signer()?).await,
+    }
+}
+
+async fn check(cli: &Cli, quote: &QuoteArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let check = cli.reader().await?.check_quote_collaterals(&raw_quote).await?;
+
+    if cli.json {
+        println!(
+            "{}",
+            json!({ "network": cli.network, "block": check.block, "missing": check.missing })
+        );
+    } else {
+        println!("{}", check.missing);
+    }
+
+    match check.missing {
+        MissingCollateral::None => Ok(ExitCode::SUCCESS),
+        _ => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn simulate(cli: &Cli, quote: &QuoteArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let verification = cli.reader().await?.simulate_onchain_verification(&raw_quote).await?;
+
+    if cli.json {
+        let mut value = serde_json::to_value(&verification)?;
+        value["network"] = json!(cli.network);
+        println!("{}", value);
+    } else {
+        match &verification.output {
+            Some(output) => println!(
+                "verified: {:?} (fmspc {})",
+                output.tcb_status,
+                hex::encode(output.fmspc)
+            ),
+            None => {
+                println!("not verified: {}", verification.error.as_deref().unwrap_or("-"));
+                println!("{}", verification.missing);
+            }
+        }
+    }
+
+    if verification.verified {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+async fn parse_check(cli: &Cli, quote: &QuoteArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let reader = cli.reader().await?;
+    let collateral = reader.get_quote_collateral(&raw_quote).await?;
+    let reports = reader.check_collateral_parse(&collateral).await?;
+
+    if cli.json {
+        println!("{}", json!({ "network": cli.network, "reports": reports }));
+    } else {
+        for report in &reports {
+            if report.is_consistent() {
+                println!("{}: consistent", report.collateral);
+            }
+            if let Some(e) = &report.onchain_error {
+                println!("{}: the helper failed to parse it, {}", report.collateral, e);
+            }
+            if let Some(e) = &report.offchain_error {
+                println!("{}: failed to parse it off chain, {}", report.collateral, e);
+            }
+            for discrepancy in &report.discrepancies {
+                println!(
+                    "{}: {} is {} on chain, {} off chain",
+                    report.collateral, discrepancy.field, discrepancy.onchain, discrepancy.offchain
+                );
+            }
+        }
+    }
+
+    match reports.iter().all(|report| report.is_consistent()) {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn doctor(cli: &Cli, code_hashes: Option<&Path>) -> Result<ExitCode> {
+    let mut network = cli.network()?;
+    if let Some(path) = code_hashes {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        network.code_hashes = Some(serde_json::from_str(&json)?);
+    }
+    let report = cli.network_reader(network).await?.self_check().await?;
+    let problems = report.problems();
+
+    if cli.json {
+        println!("{}", json!({ "report": report, "problems": problems }));
+    } else {
+        println!("Network: {} ({})", report.network, report.rpc_url);
+        match report.chain_id {
+            Some(chain_id) => println!(
+                "Chain ID: {} (expected {})",
+                chain_id, report.expected_chain_id
+            ),
+            None => println!("Chain ID: unknown (expected {})", report.expected_chain_id),
+        }
+        for contract in &report.contracts {
+            let hash = match contract.code_hash_matches() {
+                _ if !contract.has_code() => "no code",
+                Some(true) => "pinned hash matches",
+                Some(false) => "pinned hash differs",
+                None => "not pinned",
+            };
+            println!(
+                "{}: {} {} bytes, code hash {} ({})",
+                contract.name, contract.address, contract.code_size, contract.code_hash, hash
+            );
+        }
+        match problems.is_empty() {
+            true => println!("No problems found"),
+            false => problems.iter().for_each(|problem| println!("Problem: {}", problem)),
+        }
+    }
+
+    match problems.is_empty() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn check_offline(cli: &Cli, quote: &QuoteArgs, path: &Path) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let source = SnapshotCollateralSource::load(path)?;
+    let missing = find_missing_collaterals_from_source(&source, &raw_quote).await;
+
+    if cli.json {
+        let snapshot = source.snapshot();
+        println!(
+            "{}",
+            json!({
+                "network": snapshot.network,
+                "block": snapshot.block_number,
+                "missing": missing,
+            })
+        );
+    } else {
+        println!("{}", missing);
+    }
+
+    match missing {
+        MissingCollateral::None => Ok(ExitCode::SUCCESS),
+        _ => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn snapshot(cli: &Cli, fmspcs: &[String], out: &Path) -> Result<ExitCode> {
+    let snapshot = cli.reader().await?.export_snapshot(fmspcs).await?;
+    snapshot.save(out)?;
+
+    if cli.json {
+        println!(
+            "{}",
+            json!({
+                "network": cli.network,
+                "block": snapshot.block_number,
+                "block_hash": snapshot.block_hash,
+                "path": out.display().to_string(),
+            })
+        );
+    } else {
+        println!(
+            "{} certificates, {} TCB Infos and {} identities at block {} written to {}",
+            snapshot.certificates.len(),
+            snapshot.tcb_infos.len(),
+            snapshot.enclave_identities.len(),
+            snapshot.block_number,
+            out.display()
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn diff(cli: &Cli, networks: &NetworkPair) -> Result<ExitCode> {
+    let (a, b) = networks.readers(cli).await?;
+    let diff = a.diff(&b, &networks.fmspcs).await?;
+
+    if cli.json {
+        println!("{}", serde_json::to_string(&diff)?);
+    } else {
+        let number = |version: Option<&CollateralVersion>| {
+            version
+                .and_then(|version| version.number)
+                .map_or("-".to_string(), |number| number.to_string())
+        };
+        for collateral in &diff.collaterals {
+            let status = match collateral.status {
+                DiffStatus::Identical => "identical".to_string(),
+                DiffStatus::Newer(side) => format!("newer on {}", network_name(&diff, side)),
+                DiffStatus::Conflicting => "conflicting".to_string(),
+                DiffStatus::OnlyOn(side) => format!("only on {}", network_name(&diff, side)),
+            };
+            println!(
+                "{}: {} ({} {}, {} {})",
+                collateral.collateral,
+                status,
+                diff.network_a,
+                number(collateral.a.as_ref()),
+                diff.network_b,
+                number(collateral.b.as_ref())
+            );
+        }
+    }
+
+    if diff.is_in_sync() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn network_name(diff: &NetworkDiff, side: Side) -> &str {
+    match side {
+        Side::A => &diff.network_a,
+        Side::B => &diff.network_b,
+    }
+}
+
+async fn sync(cli: &Cli, networks: &NetworkPair, signer: Option<PrivateKeySigner>) -> Result<ExitCode> {
+    let (from, to) = networks.readers(cli).await?;
+
+    let upserts: Vec<(UpsertCall, Option<TxHash>)> = match signer {
+        Some(signer) => to
+            .sync_from(&from, &networks.fmspcs, signer)
+            .await?
+            .into_iter()
+            .map(|(call, tx_hash)| (call, Some(tx_hash)))
+            .collect(),
+        None => to
+            .sync_calls(&from, &networks.fmspcs)
+            .await?
+            .into_iter()
+            .map(|call| (call, None))
+            .collect(),
+    };
+
+    if cli.json {
+        let upserts: Vec<_> = upserts
+            .iter()
+            .map(|(call, tx_hash)| json!({ "collateral": call.kind.to_string(), "tx_hash": tx_hash }))
+            .collect();
+        println!(
+            "{}",
+            json!({ "from": networks.network_a, "to": networks.network_b, "upserts": upserts })
+        );
+    } else {
+        for (call, tx_hash) in &upserts {
+            match tx_hash {
+                Some(tx_hash) => println!("Upserted {} in {}", call.kind, tx_hash),
+                None => println!("Needs upsert: {}", call.kind),
+            }
+        }
+        if upserts.is_empty() {
+            println!("{} is up to date with {}", networks.network_b, networks.network_a);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn scan(
+    cli: &Cli,
+    paths: &[PathBuf],
+    format: QuoteFormat,
+    concurrency: usize,
+) -> Result<ExitCode> {
+    let files = quote_files(paths)?;
+    let raw_quotes = files
+        .iter()
+        .map(|file| read_quote(&file.to_string_lossy(), format))
+        .collect::<Result<Vec<_>>>()?;
+
+    let report = cli
+        .reader()
+        .await?
+        .find_missing_collaterals_from_quotes(&raw_quotes, concurrency)
+        .await?;
+
+    if cli.json {
+        let mut value = serde_json::to_value(&report)?;
+        for (quote, file) in value["quotes"].as_array_mut().unwrap().iter_mut().zip(&files) {
+            quote["path"] = json!(file.display().to_string());
+        }
+        value["network"] = json!(cli.network);
+        println!("{}", value);
+    } else {
+        for (quote, file) in report.quotes.iter().zip(&files) {
+            match (&quote.error, &quote.fmspc) {
+                (Some(e), None) => println!("{}: invalid quote: {}", file.display(), e),
+                (Some(e), Some(_)) => println!("{}: {}", file.display(), e),
+                (None, _) => println!("{}: {}", file.display(), quote.missing),
+            }
+        }
+        println!(
+            "{} quotes, {} lookups, {} missing collateral",
+            report.quotes.len(),
+            report.lookups,
+            report.missing.len()
+        );
+        for missing in &report.missing {
+            println!("  {}", missing);
+        }
+    }
+
+    if report.is_complete() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+// Expands directories into the files they contain, sorted by name
+fn quote_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            entries.retain(|entry| entry.is_file());
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+async fn freshness(cli: &Cli, quote: &QuoteArgs, source: &SourceArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let reports = cli
+        .reader()
+        .await?
+        .compare_quote_with_source(&raw_quote, source.source().as_ref())
+        .await?;
+
+    if cli.json {
+        println!("{}", json!({ "network": cli.network, "collateral": reports }));
+    } else {
+        let number = |version: Option<&CollateralVersion>| {
+            version
+                .and_then(|version| version.number)
+                .map_or("-".to_string(), |number| number.to_string())
+        };
+        for report in &reports {
+            println!(
+                "{:?}: {} (on chain {}, source {})",
+                report.freshness,
+                report.collateral,
+                number(report.onchain.as_ref()),
+                number(Some(&report.source))
+            );
+        }
+    }
+
+    let stale = reports
+        .iter()
+        .any(|report| matches!(report.freshness, Freshness::Behind | Freshness::MissingOnChain));
+    if stale {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+async fn cost(cli: &Cli, quote: &QuoteArgs, source: &SourceArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let report = cli
+        .reader()
+        .await?
+        .quote_cost(&raw_quote, source.source().as_ref())
+        .await?;
+
+    if cli.json {
+        let mut value = serde_json::to_value(&report)?;
+        value["network"] = json!(cli.network);
+        println!("{}", value);
+    } else {
+        for estimate in &report.estimates {
+            println!(
+                "{}: {} gas, at most {} ETH",
+                estimate.action,
+                estimate.gas,
+                format_ether(U256::from(estimate.max_cost_wei))
+            );
+        }
+        for (action, e) in &report.unestimated {
+            println!("{}: not estimated, {}", action, e);
+        }
+        println!(
+            "total: {} gas, at most {} ETH at {} gwei per gas",
+            report.total_gas,
+            format_ether(U256::from(report.total_max_cost_wei)),
+            format_units(U256::from(report.max_fee_per_gas), "gwei")?
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn inspect(cli: &Cli, quote: &QuoteArgs) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let (header, body) = parse_quote(&raw_quote)?;
+    let (fmspc, pck_ca) = try_get_pck_fmspc_and_issuer(&raw_quote, header.version, header.tee_type)?;
+
+    if cli.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "header": header,
+                "body": body,
+                "fmspc": fmspc,
+                "pck_ca": format!("{:?}", pck_ca),
+            }))?
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!("Header");
+    println!("  version:              {}", header.version);
+    println!("  attestation key type: {}", header.attestation_key_type);
+    println!("  tee type:             {:#010x}", header.tee_type);
+    if header.version < 4 {
+        println!("  qe svn:               {}", header.qe_svn);
+        println!("  pce svn:              {}", header.pce_svn);
+    }
+    println!("  qe vendor id:         {}", hex::encode(header.qe_vendor_id));
+    println!("  user data:            {}", hex::encode(header.user_data));
+
+    match body {
+        QuoteBody::SgxEnclaveReport(report) => {
+            println!("SGX Enclave Report");
+            println!("  cpu svn:     {}", hex::encode(report.cpu_svn));
+            println!("  misc select: {:#010x}", report.misc_select);
+            println!("  attributes:  {}", hex::encode(report.attributes));
+            println!("  mr enclave:  {}", hex::encode(report.mr_enclave));
+            println!("  mr signer:   {}", hex::encode(report.mr_signer));
+            println!("  isv prod id: {}", report.isv_prod_id);
+            println!("  isv svn:     {}", report.isv_svn);
+            println!("  report data: {}", hex::encode(report.report_data));
+        }
+        QuoteBody::Td10Report(report) => {
+            println!("TD Report");
+            println!("  tee tcb svn:     {}", hex::encode(report.tee_tcb_svn));
+            println!("  mr seam:         {}", hex::encode(report.mr_seam));
+            println!("  mr signer seam:  {}", hex::encode(report.mr_signer_seam));
+            println!("  seam attributes: {}", hex::encode(report.seam_attributes));
+            println!("  td attributes:   {}", hex::encode(report.td_attributes));
+            println!("  xfam:            {}", hex::encode(report.xfam));
+            println!("  mr td:           {}", hex::encode(report.mr_td));
+            println!("  mr config id:    {}", hex::encode(report.mr_config_id));
+            println!("  mr owner:        {}", hex::encode(report.mr_owner));
+            println!("  mr owner config: {}", hex::encode(report.mr_owner_config));
+            println!("  rtmr0:           {}", hex::encode(report.rt_mr0));
+            println!("  rtmr1:           {}", hex::encode(report.rt_mr1));
+            println!("  rtmr2:           {}", hex::encode(report.rt_mr2));
+            println!("  rtmr3:           {}", hex::encode(report.rt_mr3));
+            println!("  report data:     {}", hex::encode(report.report_data));
+        }
+    }
+
+    println!("PCK");
+    println!("  fmspc: {}", fmspc);
+    println!("  ca:    {:?}", pck_ca);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn collateral(cli: &Cli, quote: &QuoteArgs, out_dir: &PathBuf) -> Result<ExitCode> {
+    let raw_quote = quote.read()?;
+    let collateral = cli.reader().await?.get_quote_collateral(&raw_quote).await?;
+
+    let files: [(&str, Vec<u8>); 9] = [
+        ("root_ca.der", collateral.root_ca.clone()),
+        ("root_ca_crl.der", collateral.root_ca_crl.clone()),
+        ("pck_ca.der", collateral.pck_ca.clone()),
+        ("pck_crl.der", collateral.pck_crl.clone()),
+        ("tcb_signing_ca.der", collateral.tcb_signing_ca.clone()),
+        ("tcb_info.json", collateral.tcb_info.to_pcs_json().into_bytes()),
+        ("qe_identity.json", collateral.qe_identity.to_pcs_json().into_bytes()),
+        ("collateral.qve.bin", collateral.to_qve_bytes()),
+        ("collateral.dcap-qvl.json", collateral.to_dcap_qvl_json()?.into_bytes()),
+    ];
+
+    std::fs::create_dir_all(out_dir)?;
+    let mut written = Vec::with_capacity(files.len());
+    for (name, content) in files {
+        let path = out_dir.join(name);
+        std::fs::write(&path, content)?;
+        written.push(path.display().to_string());
+    }
+
+    if cli.json {
+        println!("{}", json!({ "files": written }));
+    } else {
+        for path in written {
+            println!("{}", path);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+
+use pccs_reader_rs::relayer::{Relayer, RelayerConfig};
+use pccs_reader_rs::writer::{signer_from_keystore, signer_from_private_key};
+
+/// Keeps the collateral of a watch list fresh in the Automata On-chain PCCS
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// JSON configuration file
+    #[arg(long)]
+    config: PathBuf,
+
+    /// Reports what would be upserted without signing nor sending anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Runs a single round and exits
+    #[arg(long)]
+    once: bool,
+
+    /// Environment variable holding the hex private key of the relayer
+    #[arg(long, default_value = "PCCS_RELAYER_PRIVATE_KEY")]
+    private_key_env: String,
+
+    /// Keystore file of the relayer, used instead of the private key
+    #[arg(long)]
+    keystore: Option<PathBuf>,
+
+    /// Environment variable holding the keystore password
+    #[arg(long, default_value = "PCCS_RELAYER_KEYSTORE_PASSWORD")]
+    keystore_password_env: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = RelayerConfig::load(&args.config)?;
+
+    let signer = if args.dry_run {
+        None
+    } else if let Some(keystore) = &args.keystore {
+        let password = std::env::var(&args.keystore_password_env)
+            .map_err(|_| anyhow::anyhow!("{} is not set", args.keystore_password_env))?;
+        Some(signer_from_keystore(keystore, &password)?)
+    } else {
+        let private_key = std::env::var(&args.private_key_env)
+            .map_err(|_| anyhow::anyhow!("{} is not set", args.private_key_env))?;
+        Some(signer_from_private_key(&private_key)?)
+    };
+
+    let source = config.source();
+    let mut relayer = Relayer::new(config, source, signer)?;
+    relayer.resolve_addresses().await?;
+    if let Some(resolved) = relayer.reader().resolved_addresses() {
+        println!("Resolved addresses:\n{}", resolved);
+    }
+    let interval = Duration::from_secs(relayer.config().interval_secs);
+    println!(
+        "Relaying to the {} PCCS{}",
+        relayer.reader().network().name,
+        if relayer.is_dry_run() { " (dry run)" } else { "" }
+    );
+
+    // the handlers are installed right away, a signal during a round is honored after it
+    let (shutdown_tx, mut shutdown) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    loop {
+        // a round is never interrupted, so that every sent upsert is recorded
+        match relayer.run_once().await {
+            Ok(summary) => {
+                for (collateral, e) in &summary.errors {
+                    eprintln!("Skipped {}: {}", collateral, e);
+                }
+                for call in &summary.upserts {
+                    println!("Needs upsert: {}", call.kind);
+                }
+                for tx_hash in &summary.submitted {
+                    println!("Submitted {}", tx_hash);
+                }
+                if summary.skipped > 0 {
+                    println!("{} upserts already submitted", summary.skipped);
+                }
+            }
+            Err(e) => eprintln!("Round failed: {:#}", e),
+        }
+
+        if args.once || *shutdown.borrow() {
+            break;
+        }
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+
+    println!("Shutting down");
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Failed to install the SIGTERM handler");
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = ctrl_c.await;
+    }
+}
@@ -0,0 +1,548 @@
+//! A cache in front of the DAO reads, in memory and optionally persisted to disk.
+//! Collateral changes rarely, so an entry is kept until the `nextUpdate` of the
+//! collateral itself unless a TTL is configured.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use x509_parser::prelude::{CertificateRevocationList, FromDer};
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::{PccsReader, CA};
+
+/// What a cache entry holds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CachedCollateral {
+    Pcs(#[serde(with = "ca_index")] CA),
+    // TcbType, Fmspc, Version
+    TcbInfo(u8, String, u32),
+    // Id, Version
+    EnclaveIdentity(EnclaveIdType, u32),
+}
+
+/// The network part tells apart the same collateral read from different DAOs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    // "<chain id>:<DAO address>"
+    pub network: String,
+    pub collateral: CachedCollateral,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CachedValue {
+    // cert, crl
+    Certificate(Vec<u8>, Vec<u8>),
+    TcbInfo(TcbInfoJsonObj),
+    EnclaveIdentity(EnclaveIdentityJsonObj),
+}
+
+impl CachedValue {
+    /// The `nextUpdate` of a TCB Info, an Enclave Identity or a CRL.
+    /// Certificates without a CRL have none.
+    pub fn next_update(&self) -> Option<OffsetDateTime> {
+        match self {
+            CachedValue::Certificate(_, crl) => {
+                let (_, crl) = CertificateRevocationList::from_der(crl).ok()?;
+                crl.next_update().map(|next_update| next_update.to_datetime())
+            }
+            CachedValue::TcbInfo(obj) => next_update_of_body(&obj.tcbInfoStr),
+            CachedValue::EnclaveIdentity(obj) => next_update_of_body(&obj.identityStr),
+        }
+    }
+}
+
+fn next_update_of_body(body: &str) -> Option<OffsetDateTime> {
+    let body: serde_json::Value = serde_json::from_str(body).ok()?;
+    OffsetDateTime::parse(body["nextUpdate"].as_str()?, &Rfc3339).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    // overrides the nextUpdate of the collateral when set
+    pub ttl: Option<Duration>,
+    // for collateral without a nextUpdate
+    pub default_ttl: Duration,
+    // the entries are loaded from and saved to this file when set
+    pub path: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            ttl: None,
+            default_ttl: Duration::from_secs(60 * 60),
+            path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    // misses on entries that had expired
+    pub expired: u64,
+    pub invalidations: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: CachedValue,
+    expires: OffsetDateTime,
+    // the block the value was read at, None when read at the latest block
+    block: Option<u64>,
+}
+
+/// A collateral cache, meant to be shared between readers through an `Arc`.
+#[derive(Debug, Default)]
+pub struct CollateralCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<CacheKey, Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    expired: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl CollateralCache {
+    /// A cache with the given config, loaded from its file when there is one.
+    pub fn new(config: CacheConfig) -> Result<Self> {
+        let entries = match &config.path {
+            Some(path) if path.exists() => load(path)?,
+            _ => HashMap::new(),
+        };
+        Ok(CollateralCache {
+            config,
+            entries: Mutex::new(entries),
+            ..Default::default()
+        })
+    }
+
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<CachedValue> {
+        self.lookup(key, None)
+    }
+
+    /// Same as [`CollateralCache::get`] for a reader pinned to `block`: only an entry read
+    /// at that block or before it is served, so that a pinned check never sees collateral
+    /// from after its block.
+    pub fn get_at(&self, key: &CacheKey, block: u64) -> Option<CachedValue> {
+        self.lookup(key, Some(block))
+    }
+
+    fn lookup(&self, key: &CacheKey, block: Option<u64>) -> Option<CachedValue> {
+        let mut entries = self.entries.lock().unwrap();
+        let readable = |entry: &Entry| match (block, entry.block) {
+            (None, _) => true,
+            (Some(block), Some(read_at)) => read_at <= block,
+            // the block of a read at the latest block is not known
+            (Some(_), None) => false,
+        };
+        match entries.get(key) {
+            Some(entry) if !readable(entry) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Some(entry) if entry.expires > OffsetDateTime::now_utc() => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                self.expired.fetch_add(1, Ordering::Relaxed);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Caches a value until its expiry. Values that expired already are not cached,
+    /// so that a newer issue is picked up as soon as it is on chain.
+    pub fn insert(&self, key: CacheKey, value: CachedValue) {
+        self.insert_at(key, value, None)
+    }
+
+    /// Same as [`CollateralCache::insert`] for a value read at the given block.
+    pub fn insert_at(&self, key: CacheKey, value: CachedValue, block: Option<u64>) {
+        let expires = self.expiry(&value);
+        if expires <= OffsetDateTime::now_utc() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            key,
+            Entry {
+                value,
+                expires,
+                block,
+            },
+        );
+        self.persist(&entries);
+    }
+
+    /// Drops an entry, returns whether there was one.
+    pub fn invalidate(&self, key: &CacheKey) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        let removed = entries.remove(key).is_some();
+        if removed {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+            self.persist(&entries);
+        }
+        removed
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.invalidations
+            .fetch_add(entries.len() as u64, Ordering::Relaxed);
+        entries.clear();
+        self.persist(&entries);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+
+    /// Writes the entries to the cache file, if any.
+    pub fn save(&self) -> Result<()> {
+        match &self.config.path {
+            Some(path) => save(path, &self.entries.lock().unwrap()),
+            None => Ok(()),
+        }
+    }
+
+    fn expiry(&self, value: &CachedValue) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+        match (self.config.ttl, value.next_update()) {
+            (Some(ttl), _) => now + ttl,
+            (None, Some(next_update)) => next_update,
+            (None, None) => now + self.config.default_ttl,
+        }
+    }
+
+    // A failed write only costs the persistence, the entries stay in memory
+    fn persist(&self, entries: &HashMap<CacheKey, Entry>) {
+        if let Some(path) = &self.config.path {
+            let _ = save(path, entries);
+        }
+    }
+}
+
+// The on-disk form of an entry, collateral is kept in the Intel PCS format
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    key: CacheKey,
+    value: StoredValue,
+    #[serde(with = "time::serde::rfc3339")]
+    expires: OffsetDateTime,
+    #[serde(default)]
+    block: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum StoredValue {
+    Certificate {
+        #[serde(with = "hex")]
+        cert: Vec<u8>,
+        #[serde(with = "hex")]
+        crl: Vec<u8>,
+    },
+    TcbInfo(String),
+    EnclaveIdentity(String),
+}
+
+fn load(path: &Path) -> Result<HashMap<CacheKey, Entry>> {
+    let stored: Vec<StoredEntry> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let now = OffsetDateTime::now_utc();
+    stored
+        .into_iter()
+        .filter(|stored| stored.expires > now)
+        .map(|stored| {
+            let value = match stored.value {
+                StoredValue::Certificate { cert, crl } => CachedValue::Certificate(cert, crl),
+                StoredValue::TcbInfo(json) => {
+                    CachedValue::TcbInfo(TcbInfoJsonObj::from_pcs_json(&json)?)
+                }
+                StoredValue::EnclaveIdentity(json) => {
+                    CachedValue::EnclaveIdentity(EnclaveIdentityJsonObj::from_pcs_json(&json)?)
+                }
+            };
+            let entry = Entry {
+                value,
+                expires: stored.expires,
+                block: stored.block,
+            };
+            Ok((stored.key, entry))
+        })
+        .collect()
+}
+
+// Written aside then renamed, so that a crash never leaves a truncated file
+fn save(path: &Path, entries: &HashMap<CacheKey, Entry>) -> Result<()> {
+    let stored: Vec<StoredEntry> = entries
+        .iter()
+        .map(|(key, entry)| StoredEntry {
+            key: key.clone(),
+            value: match &entry.value {
+                CachedValue::Certificate(cert, crl) => StoredValue::Certificate {
+                    cert: cert.clone(),
+                    crl: crl.clone(),
+                },
+                CachedValue::TcbInfo(obj) => StoredValue::TcbInfo(obj.to_pcs_json()),
+                CachedValue::EnclaveIdentity(obj) => StoredValue::EnclaveIdentity(obj.to_pcs_json()),
+            },
+            expires: entry.expires,
+            block: entry.block,
+        })
+        .collect();
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(&stored)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) mod ca_index {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::CA;
+
+    pub fn serialize<S: Serializer>(ca: &CA, serializer: S) -> Result<S::Ok, S::Error> {
+        u8::from(*ca).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CA, D::Error> {
+        CA::try_from(u8::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl PccsReader {
+    fn cache_key(&self, collateral: CachedCollateral) -> CacheKey {
+        let network = self.network();
+        let dao = match collateral {
+            CachedCollateral::Pcs(_) => network.pcs_dao,
+            CachedCollateral::TcbInfo(..) => network.fmspc_tcb_dao,
+            CachedCollateral::EnclaveIdentity(..) => network.enclave_id_dao,
+        };
+        CacheKey {
+            network: format!("{}:{}", network.chain_id, dao),
+            collateral,
+        }
+    }
+
+    // The number of a reader pinned from the latest block, None when reading the latest block
+    fn pinned_number(&self) -> Option<u64> {
+        match self.block() {
+            BlockId::Number(BlockNumberOrTag::Number(number)) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn cached(&self, collateral: CachedCollateral) -> Option<CachedValue> {
+        let cache = self.live_cache()?;
+        let key = self.cache_key(collateral);
+        match self.pinned_number() {
+            Some(number) => cache.get_at(&key, number),
+            None => cache.get(&key),
+        }
+    }
+
+    fn store(&self, collateral: CachedCollateral, value: CachedValue) {
+        if let Some(cache) = self.live_cache() {
+            cache.insert_at(self.cache_key(collateral), value, self.pinned_number());
+        }
+    }
+
+    /// Drops the cached certificate and CRL of a CA, e.g. after upserting a new CRL.
+    pub fn invalidate_certificate(&self, ca: CA) -> bool {
+        self.invalidate(CachedCollateral::Pcs(ca))
+    }
+
+    pub fn invalidate_tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> bool {
+        self.invalidate(CachedCollateral::TcbInfo(tcb_type, fmspc.to_string(), version))
+    }
+
+    pub fn invalidate_enclave_identity(&self, id: EnclaveIdType, version: u32) -> bool {
+        self.invalidate(CachedCollateral::EnclaveIdentity(id, version))
+    }
+
+    fn invalidate(&self, collateral: CachedCollateral) -> bool {
+        match self.cache() {
+            Some(cache) => cache.invalidate(&self.cache_key(collateral)),
+            None => false,
+        }
+    }
+
+    pub(crate) fn cached_certificate(&self, ca: CA) -> Option<(Vec<u8>, Vec<u8>)> {
+        match self.cached(CachedCollateral::Pcs(ca))? {
+            CachedValue::Certificate(cert, crl) => Some((cert, crl)),
+            _ => None,
+        }
+    }
+
+    // A missing certificate is not cached, it may be upserted any time
+    pub(crate) fn cache_certificate(&self, ca: CA, (cert, crl): &(Vec<u8>, Vec<u8>)) {
+        if !cert.is_empty() {
+            self.store(
+                CachedCollateral::Pcs(ca),
+                CachedValue::Certificate(cert.clone(), crl.clone()),
+            );
+        }
+    }
+
+    pub(crate) fn cached_tcb_info(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+    ) -> Option<TcbInfoJsonObj> {
+        let collateral = CachedCollateral::TcbInfo(tcb_type, fmspc.to_string(), version);
+        match self.cached(collateral)? {
+            CachedValue::TcbInfo(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cache_tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32, obj: &TcbInfoJsonObj) {
+        let collateral = CachedCollateral::TcbInfo(tcb_type, fmspc.to_string(), version);
+        self.store(collateral, CachedValue::TcbInfo(obj.clone()));
+    }
+
+    pub(crate) fn cached_enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Option<EnclaveIdentityJsonObj> {
+        match self.cached(CachedCollateral::EnclaveIdentity(id, version))? {
+            CachedValue::EnclaveIdentity(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cache_enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+        obj: &EnclaveIdentityJsonObj,
+    ) {
+        let collateral = CachedCollateral::EnclaveIdentity(id, version);
+        self.store(collateral, CachedValue::EnclaveIdentity(obj.clone()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(collateral: CachedCollateral) -> CacheKey {
+        CacheKey {
+            network: "65536:0x0000000000000000000000000000000000000001".to_string(),
+            collateral,
+        }
+    }
+
+    fn tcb_info(next_update: &str) -> CachedValue {
+        CachedValue::TcbInfo(TcbInfoJsonObj {
+            tcbInfoStr: format!(r#"{{"id":"SGX","version":2,"nextUpdate":"{}"}}"#, next_update),
+            signature: vec![0xaa; 64].into(),
+        })
+    }
+
+    #[test]
+    fn test_expires_at_next_update() {
+        let cache = CollateralCache::default();
+        let next_update = (OffsetDateTime::now_utc() + Duration::from_secs(3600))
+            .format(&Rfc3339)
+            .unwrap();
+
+        let fresh = key(CachedCollateral::TcbInfo(0, "00606a000000".to_string(), 2));
+        cache.insert(fresh.clone(), tcb_info(&next_update));
+        assert_eq!(cache.get(&fresh), Some(tcb_info(&next_update)));
+
+        // past its nextUpdate already, so not worth caching
+        let stale = key(CachedCollateral::TcbInfo(0, "00906ed50000".to_string(), 2));
+        cache.insert(stale.clone(), tcb_info("2020-01-01T00:00:00Z"));
+        assert_eq!(cache.get(&stale), None);
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_ttl_overrides_next_update() {
+        let cache = CollateralCache::new(CacheConfig {
+            ttl: Some(Duration::ZERO),
+            ..Default::default()
+        })
+        .unwrap();
+        let tcb = key(CachedCollateral::TcbInfo(0, "00606a000000".to_string(), 2));
+        cache.insert(tcb.clone(), tcb_info("2999-01-01T00:00:00Z"));
+        assert_eq!(cache.get(&tcb), None);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let cache = CollateralCache::default();
+        let root = key(CachedCollateral::Pcs(CA::ROOT));
+        let signing = key(CachedCollateral::Pcs(CA::SIGNING));
+        for key in [&root, &signing] {
+            cache.insert(key.clone(), CachedValue::Certificate(vec![0x30], vec![]));
+        }
+
+        assert!(cache.invalidate(&root));
+        assert!(!cache.invalidate(&root));
+        assert_eq!(cache.get(&root), None);
+        assert!(cache.get(&signing).is_some());
+
+        cache.clear();
+        assert_eq!(cache.stats().invalidations, 2);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_serves_pinned_reads_up_to_their_block() {
+        let cache = CollateralCache::default();
+        let pinned = key(CachedCollateral::Pcs(CA::ROOT));
+        let cert = CachedValue::Certificate(vec![0x30], vec![]);
+        cache.insert_at(pinned.clone(), cert.clone(), Some(10));
+        assert!(cache.get_at(&pinned, 10).is_some());
+        assert!(cache.get_at(&pinned, 11).is_some());
+        assert!(cache.get(&pinned).is_some());
+        // read after the block of the check
+        assert_eq!(cache.get_at(&pinned, 9), None);
+
+        // read at the latest block, whose number is not known
+        let latest = key(CachedCollateral::Pcs(CA::SIGNING));
+        cache.insert(latest.clone(), cert);
+        assert_eq!(cache.get_at(&latest, 10), None);
+        assert!(cache.get(&latest).is_some());
+    }
+}
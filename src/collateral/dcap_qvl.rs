@@ -0,0 +1,130 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{pem_to_der, QuoteCollateral};
+use crate::pccs::enclave_id::EnclaveIdentityJsonObj;
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+
+/// The collateral layout consumed by the `dcap-qvl` crate.
+/// Binary fields are hex encoded in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteCollateralV3 {
+    pub pck_crl_issuer_chain: String,
+    #[serde(with = "hex_bytes")]
+    pub root_ca_crl: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub pck_crl: Vec<u8>,
+    pub tcb_info_issuer_chain: String,
+    pub tcb_info: String,
+    #[serde(with = "hex_bytes")]
+    pub tcb_info_signature: Vec<u8>,
+    pub qe_identity_issuer_chain: String,
+    pub qe_identity: String,
+    #[serde(with = "hex_bytes")]
+    pub qe_identity_signature: Vec<u8>,
+}
+
+impl From<&QuoteCollateral> for QuoteCollateralV3 {
+    fn from(collateral: &QuoteCollateral) -> Self {
+        QuoteCollateralV3 {
+            pck_crl_issuer_chain: collateral.pck_crl_issuer_chain(),
+            root_ca_crl: collateral.root_ca_crl.clone(),
+            pck_crl: collateral.pck_crl.clone(),
+            tcb_info_issuer_chain: collateral.tcb_signing_issuer_chain(),
+            tcb_info: collateral.tcb_info.tcbInfoStr.clone(),
+            tcb_info_signature: collateral.tcb_info.signature.to_vec(),
+            qe_identity_issuer_chain: collateral.tcb_signing_issuer_chain(),
+            qe_identity: collateral.qe_identity.identityStr.clone(),
+            qe_identity_signature: collateral.qe_identity.signature.to_vec(),
+        }
+    }
+}
+
+impl QuoteCollateralV3 {
+    /// `dcap-qvl` does not carry the tee type, so it has to be supplied by the caller.
+    pub fn into_collateral(self, tee_type: u32) -> Result<QuoteCollateral> {
+        let pck_crl_issuer_chain = pem_to_der(&self.pck_crl_issuer_chain)?;
+        let tcb_info_issuer_chain = pem_to_der(&self.tcb_info_issuer_chain)?;
+        let (pck_ca, root_ca) = match pck_crl_issuer_chain.as_slice() {
+            [pck_ca, root_ca] => (pck_ca.clone(), root_ca.clone()),
+            _ => return Err(anyhow::Error::msg("invalid pck crl issuer chain")),
+        };
+        let tcb_signing_ca = match tcb_info_issuer_chain.as_slice() {
+            [tcb_signing_ca, _] => tcb_signing_ca.clone(),
+            _ => return Err(anyhow::Error::msg("invalid tcb info issuer chain")),
+        };
+
+        Ok(QuoteCollateral {
+            tee_type,
+            root_ca,
+            root_ca_crl: self.root_ca_crl,
+            pck_ca,
+            pck_crl: self.pck_crl,
+            tcb_signing_ca,
+            tcb_info: TcbInfoJsonObj {
+                tcbInfoStr: self.tcb_info,
+                signature: self.tcb_info_signature.into(),
+            },
+            qe_identity: EnclaveIdentityJsonObj {
+                identityStr: self.qe_identity,
+                signature: self.qe_identity_signature.into(),
+            },
+        })
+    }
+}
+
+impl QuoteCollateral {
+    pub fn to_dcap_qvl_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&QuoteCollateralV3::from(self))?)
+    }
+
+    pub fn from_dcap_qvl_json(json: &str, tee_type: u32) -> Result<Self> {
+        let collateral: QuoteCollateralV3 = serde_json::from_str(json)?;
+        collateral.into_collateral(tee_type)
+    }
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s.strip_prefix("0x").unwrap_or(&s)).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collateral::sample_collateral;
+    use crate::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
+
+    #[test]
+    fn test_dcap_qvl_json_round_trip() {
+        for tee_type in [SGX_TEE_TYPE, TDX_TEE_TYPE] {
+            let collateral = sample_collateral(tee_type);
+            let json = collateral.to_dcap_qvl_json().unwrap();
+
+            assert_eq!(QuoteCollateral::from_dcap_qvl_json(&json, tee_type).unwrap(), collateral);
+        }
+    }
+
+    #[test]
+    fn test_dcap_qvl_json_fields() {
+        let collateral = sample_collateral(SGX_TEE_TYPE);
+        let json: serde_json::Value =
+            serde_json::from_str(&collateral.to_dcap_qvl_json().unwrap()).unwrap();
+
+        assert_eq!(json["tcb_info"], collateral.tcb_info.tcbInfoStr.as_str());
+        assert_eq!(json["pck_crl"], hex::encode(&collateral.pck_crl).as_str());
+        assert_eq!(json["qe_identity_signature"], "22".repeat(64).as_str());
+        assert!(json["pck_crl_issuer_chain"]
+            .as_str()
+            .unwrap()
+            .starts_with("-----BEGIN CERTIFICATE-----\n"));
+    }
+}
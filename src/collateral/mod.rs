@@ -0,0 +1,190 @@
+pub mod dcap_qvl;
+pub mod qve;
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use x509_parser::pem::Pem;
+
+use crate::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
+use crate::parser::try_get_pck_fmspc_and_issuer;
+use crate::pccs::enclave_id::EnclaveIdentityJsonObj;
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::pccs::multicall::QuoteCollateralReads;
+use crate::source::CollateralSource;
+use crate::{qe_identity_type, tcb_type_and_version, PccsReader, CA};
+
+/// Every collateral needed to verify a quote, as read from the on-chain PCCS.
+/// Certificates and CRLs are DER encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteCollateral {
+    pub tee_type: u32,
+    pub root_ca: Vec<u8>,
+    pub root_ca_crl: Vec<u8>,
+    pub pck_ca: Vec<u8>,
+    pub pck_crl: Vec<u8>,
+    pub tcb_signing_ca: Vec<u8>,
+    pub tcb_info: TcbInfoJsonObj,
+    pub qe_identity: EnclaveIdentityJsonObj,
+}
+
+impl QuoteCollateral {
+    // PCK CA -> Root CA
+    pub fn pck_crl_issuer_chain(&self) -> String {
+        der_to_pem("CERTIFICATE", &self.pck_ca) + &der_to_pem("CERTIFICATE", &self.root_ca)
+    }
+
+    // TCB Signing CA -> Root CA, shared by the TCB Info and the QE Identity
+    pub fn tcb_signing_issuer_chain(&self) -> String {
+        der_to_pem("CERTIFICATE", &self.tcb_signing_ca) + &der_to_pem("CERTIFICATE", &self.root_ca)
+    }
+}
+
+pub async fn get_quote_collateral(raw_quote: &[u8]) -> Result<QuoteCollateral> {
+    PccsReader::default().get_quote_collateral(raw_quote).await
+}
+
+pub async fn get_collateral(
+    tee_type: u32,
+    quote_version: u16,
+    fmspc: &str,
+    pck_ca: CA,
+) -> Result<QuoteCollateral> {
+    PccsReader::default()
+        .get_collateral(tee_type, quote_version, fmspc, pck_ca)
+        .await
+}
+
+/// Same as [`get_quote_collateral`], reading the collateral from a source instead of
+/// the chain, e.g. a [`crate::source::SnapshotCollateralSource`] for offline verification.
+pub async fn get_quote_collateral_from_source<S: CollateralSource + ?Sized>(
+    source: &S,
+    raw_quote: &[u8],
+) -> Result<QuoteCollateral> {
+    let (tee_type, quote_version, fmspc, pck_ca) = collateral_ids(raw_quote)?;
+    let (tcb_type, tcb_version) = tcb_type_and_version(tee_type, quote_version);
+    let reads = QuoteCollateralReads::from_source(
+        source,
+        qe_identity_type(tee_type),
+        quote_version as u32,
+        (tcb_type, &fmspc, tcb_version),
+        pck_ca,
+    )
+    .await;
+    QuoteCollateral::from_reads(tee_type, reads)
+}
+
+// TeeType, quote version, FMSPC and PCK CA of a quote
+fn collateral_ids(raw_quote: &[u8]) -> Result<(u32, u16, String, CA)> {
+    if raw_quote.len() < 8 {
+        return Err(anyhow::Error::msg("quote too short"));
+    }
+
+    let quote_version = u16::from_le_bytes([raw_quote[0], raw_quote[1]]);
+    let tee_type = u32::from_le_bytes([raw_quote[4], raw_quote[5], raw_quote[6], raw_quote[7]]);
+
+    if !(3..=4).contains(&quote_version) {
+        return Err(anyhow::Error::msg("Unsupported quote version"));
+    }
+
+    if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
+        return Err(anyhow::Error::msg("Unsupported tee type"));
+    }
+
+    let (fmspc, pck_ca) = try_get_pck_fmspc_and_issuer(raw_quote, quote_version, tee_type)?;
+    Ok((tee_type, quote_version, fmspc, pck_ca))
+}
+
+impl PccsReader {
+    pub async fn get_quote_collateral(&self, raw_quote: &[u8]) -> Result<QuoteCollateral> {
+        let (tee_type, quote_version, fmspc, pck_ca) = collateral_ids(raw_quote)?;
+
+        self.get_collateral(tee_type, quote_version, &fmspc, pck_ca).await
+    }
+
+    pub async fn get_collateral(
+        &self,
+        tee_type: u32,
+        quote_version: u16,
+        fmspc: &str,
+        pck_ca: CA,
+    ) -> Result<QuoteCollateral> {
+        // read everything from the same block, in a single batch when possible
+        let reader = self.pin_block().await?;
+        let (tcb_type, tcb_version) = tcb_type_and_version(tee_type, quote_version);
+        let reads = reader
+            .read_quote_collateral(
+                qe_identity_type(tee_type),
+                quote_version as u32,
+                (tcb_type, fmspc, tcb_version),
+                pck_ca,
+            )
+            .await;
+
+        QuoteCollateral::from_reads(tee_type, reads)
+    }
+}
+
+impl QuoteCollateral {
+    fn from_reads(tee_type: u32, reads: QuoteCollateralReads) -> Result<Self> {
+        let (root_ca, root_ca_crl) = reads.root?;
+        let (pck_ca, pck_crl) = reads.pck?;
+        let (tcb_signing_ca, _) = reads.signing?;
+
+        if root_ca.is_empty() || root_ca_crl.is_empty() || tcb_signing_ca.is_empty() {
+            return Err(anyhow::Error::msg("missing"));
+        }
+        if pck_ca.is_empty() || pck_crl.is_empty() {
+            return Err(anyhow::Error::msg("missing"));
+        }
+
+        let tcb_info = reads.tcb_info?;
+        let qe_identity = reads.qe_identity?;
+
+        Ok(QuoteCollateral {
+            tee_type,
+            root_ca,
+            root_ca_crl,
+            pck_ca,
+            pck_crl,
+            tcb_signing_ca,
+            tcb_info,
+            qe_identity,
+        })
+    }
+}
+
+pub(crate) fn der_to_pem(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+pub(crate) fn pem_to_der(pem: &str) -> Result<Vec<Vec<u8>>> {
+    let pems: Vec<Pem> = Pem::iter_from_buffer(pem.as_bytes()).collect::<Result<_, _>>()?;
+    Ok(pems.into_iter().map(|pem| pem.contents).collect())
+}
+
+#[cfg(test)]
+pub(crate) fn sample_collateral(tee_type: u32) -> QuoteCollateral {
+    QuoteCollateral {
+        tee_type,
+        root_ca: vec![0x30, 0x82, 0x01, 0x00, 0xaa],
+        root_ca_crl: vec![0x30, 0x81, 0x02, 0xbb, 0xcc],
+        pck_ca: (0..=255).collect(),
+        pck_crl: vec![0x30, 0x03, 0x01, 0x02, 0x03],
+        tcb_signing_ca: vec![0x30, 0x82, 0x02, 0x00, 0xdd, 0xee],
+        tcb_info: TcbInfoJsonObj {
+            tcbInfoStr: "{\"id\":\"SGX\",\"version\":3,\"fmspc\":\"00906ed50000\"}".to_string(),
+            signature: vec![0x11; 64].into(),
+        },
+        qe_identity: EnclaveIdentityJsonObj {
+            identityStr: "{\"id\":\"QE\",\"version\":2,\"isvprodid\":1}".to_string(),
+            signature: vec![0x22; 64].into(),
+        },
+    }
+}
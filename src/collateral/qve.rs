@@ -0,0 +1,216 @@
+use std::mem::{offset_of, size_of};
+use std::os::raw::c_char;
+
+use anyhow::Result;
+
+use super::{der_to_pem, pem_to_der, QuoteCollateral};
+use crate::constants::TDX_TEE_TYPE;
+use crate::pccs::enclave_id::EnclaveIdentityJsonObj;
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+
+// Collateral versions reported by the Intel QPL, CRLs are PEM encoded
+pub const SGX_QVE_COLLATERAL_VERSION: (u16, u16) = (3, 0);
+pub const TDX_QVE_COLLATERAL_VERSION: (u16, u16) = (4, 0);
+
+/// Mirrors `sgx_ql_qve_collateral_t` from `sgx_ql_lib_common.h`.
+/// Every string is null-terminated and its size includes the terminator.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SgxQlQveCollateral {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub tee_type: u32,
+    pub pck_crl_issuer_chain: *mut c_char,
+    pub pck_crl_issuer_chain_size: u32,
+    pub root_ca_crl: *mut c_char,
+    pub root_ca_crl_size: u32,
+    pub pck_crl: *mut c_char,
+    pub pck_crl_size: u32,
+    pub tcb_info_issuer_chain: *mut c_char,
+    pub tcb_info_issuer_chain_size: u32,
+    pub tcb_info: *mut c_char,
+    pub tcb_info_size: u32,
+    pub qe_identity_issuer_chain: *mut c_char,
+    pub qe_identity_issuer_chain_size: u32,
+    pub qe_identity: *mut c_char,
+    pub qe_identity_size: u32,
+}
+
+pub const QVE_COLLATERAL_HEADER_SIZE: usize = size_of::<SgxQlQveCollateral>();
+
+// (pointer offset, size offset) of every string, in struct order
+pub(crate) const QVE_COLLATERAL_FIELDS: [(usize, usize); 7] = [
+    (
+        offset_of!(SgxQlQveCollateral, pck_crl_issuer_chain),
+        offset_of!(SgxQlQveCollateral, pck_crl_issuer_chain_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, root_ca_crl),
+        offset_of!(SgxQlQveCollateral, root_ca_crl_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, pck_crl),
+        offset_of!(SgxQlQveCollateral, pck_crl_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, tcb_info_issuer_chain),
+        offset_of!(SgxQlQveCollateral, tcb_info_issuer_chain_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, tcb_info),
+        offset_of!(SgxQlQveCollateral, tcb_info_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, qe_identity_issuer_chain),
+        offset_of!(SgxQlQveCollateral, qe_identity_issuer_chain_size),
+    ),
+    (
+        offset_of!(SgxQlQveCollateral, qe_identity),
+        offset_of!(SgxQlQveCollateral, qe_identity_size),
+    ),
+];
+
+impl QuoteCollateral {
+    /// Serializes the collateral as a single `sgx_ql_qve_collateral_t` buffer:
+    /// the struct itself followed by the strings it points to.
+    /// Pointers are written as offsets from the start of the buffer,
+    /// so the caller has to rebase them before handing the struct to C.
+    pub fn to_qve_bytes(&self) -> Vec<u8> {
+        let (major_version, minor_version) = if self.tee_type == TDX_TEE_TYPE {
+            TDX_QVE_COLLATERAL_VERSION
+        } else {
+            SGX_QVE_COLLATERAL_VERSION
+        };
+
+        let fields = [
+            self.pck_crl_issuer_chain(),
+            der_to_pem("X509 CRL", &self.root_ca_crl),
+            der_to_pem("X509 CRL", &self.pck_crl),
+            self.tcb_signing_issuer_chain(),
+            self.tcb_info.to_pcs_json(),
+            self.tcb_signing_issuer_chain(),
+            self.qe_identity.to_pcs_json(),
+        ];
+
+        let mut buf = vec![0u8; QVE_COLLATERAL_HEADER_SIZE];
+        write_bytes(
+            &mut buf,
+            offset_of!(SgxQlQveCollateral, major_version),
+            &major_version.to_ne_bytes(),
+        );
+        write_bytes(
+            &mut buf,
+            offset_of!(SgxQlQveCollateral, minor_version),
+            &minor_version.to_ne_bytes(),
+        );
+        write_bytes(
+            &mut buf,
+            offset_of!(SgxQlQveCollateral, tee_type),
+            &self.tee_type.to_ne_bytes(),
+        );
+
+        for (field, (ptr_offset, size_offset)) in fields.iter().zip(QVE_COLLATERAL_FIELDS) {
+            let data_offset = buf.len();
+            buf.extend_from_slice(field.as_bytes());
+            buf.push(0);
+
+            write_bytes(&mut buf, ptr_offset, &data_offset.to_ne_bytes());
+            write_bytes(&mut buf, size_offset, &(field.len() as u32 + 1).to_ne_bytes());
+        }
+
+        buf
+    }
+
+    /// Parses a buffer produced by [`QuoteCollateral::to_qve_bytes`].
+    pub fn from_qve_bytes(buf: &[u8]) -> Result<Self> {
+        if buf.len() < QVE_COLLATERAL_HEADER_SIZE {
+            return Err(anyhow::Error::msg("collateral buffer too short"));
+        }
+
+        let tee_type = u32::from_ne_bytes(
+            read_bytes(buf, offset_of!(SgxQlQveCollateral, tee_type))?,
+        );
+
+        let mut fields = Vec::with_capacity(QVE_COLLATERAL_FIELDS.len());
+        for (ptr_offset, size_offset) in QVE_COLLATERAL_FIELDS {
+            let data_offset = usize::from_ne_bytes(read_bytes(buf, ptr_offset)?);
+            let size = u32::from_ne_bytes(read_bytes(buf, size_offset)?) as usize;
+
+            let data = buf
+                .get(data_offset..data_offset + size)
+                .ok_or_else(|| anyhow::Error::msg("collateral field out of bounds"))?;
+            match data.split_last() {
+                Some((0, s)) => fields.push(std::str::from_utf8(s)?),
+                _ => return Err(anyhow::Error::msg("collateral field is not null-terminated")),
+            }
+        }
+
+        let pck_crl_issuer_chain = pem_to_der(fields[0])?;
+        let tcb_info_issuer_chain = pem_to_der(fields[3])?;
+        let (pck_ca, root_ca) = match pck_crl_issuer_chain.as_slice() {
+            [pck_ca, root_ca] => (pck_ca.clone(), root_ca.clone()),
+            _ => return Err(anyhow::Error::msg("invalid pck crl issuer chain")),
+        };
+        let tcb_signing_ca = match tcb_info_issuer_chain.as_slice() {
+            [tcb_signing_ca, _] => tcb_signing_ca.clone(),
+            _ => return Err(anyhow::Error::msg("invalid tcb info issuer chain")),
+        };
+
+        Ok(QuoteCollateral {
+            tee_type,
+            root_ca,
+            root_ca_crl: pem_to_der(fields[1])?.concat(),
+            pck_ca,
+            pck_crl: pem_to_der(fields[2])?.concat(),
+            tcb_signing_ca,
+            tcb_info: TcbInfoJsonObj::from_pcs_json(fields[4])?,
+            qe_identity: EnclaveIdentityJsonObj::from_pcs_json(fields[6])?,
+        })
+    }
+}
+
+fn write_bytes(buf: &mut [u8], offset: usize, bytes: &[u8]) {
+    buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn read_bytes<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N]> {
+    buf.get(offset..offset + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow::Error::msg("collateral buffer too short"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collateral::sample_collateral;
+    use crate::constants::SGX_TEE_TYPE;
+
+    #[test]
+    fn test_qve_round_trip() {
+        for tee_type in [SGX_TEE_TYPE, TDX_TEE_TYPE] {
+            let collateral = sample_collateral(tee_type);
+            let buf = collateral.to_qve_bytes();
+
+            assert_eq!(QuoteCollateral::from_qve_bytes(&buf).unwrap(), collateral);
+        }
+    }
+
+    #[test]
+    fn test_qve_layout() {
+        let collateral = sample_collateral(TDX_TEE_TYPE);
+        let buf = collateral.to_qve_bytes();
+
+        assert_eq!(u16::from_ne_bytes([buf[0], buf[1]]), TDX_QVE_COLLATERAL_VERSION.0);
+        assert_eq!(u16::from_ne_bytes([buf[2], buf[3]]), TDX_QVE_COLLATERAL_VERSION.1);
+        assert_eq!(u32::from_ne_bytes([buf[4], buf[5], buf[6], buf[7]]), TDX_TEE_TYPE);
+
+        // the tcb info is served with its size including the null terminator
+        let (ptr_offset, size_offset) = QVE_COLLATERAL_FIELDS[4];
+        let data_offset = usize::from_ne_bytes(read_bytes(&buf, ptr_offset).unwrap());
+        let size = u32::from_ne_bytes(read_bytes(&buf, size_offset).unwrap()) as usize;
+        let tcb_info = collateral.tcb_info.to_pcs_json();
+        assert_eq!(size, tcb_info.len() + 1);
+        assert_eq!(&buf[data_offset..data_offset + size - 1], tcb_info.as_bytes());
+        assert_eq!(buf[data_offset + size - 1], 0);
+    }
+}
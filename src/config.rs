@@ -0,0 +1,78 @@
+//! Pieces shared by the JSON configuration files of the QPL and the relayer.
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::rpc::RpcPolicy;
+use crate::network::{CodeHashes, HelperAddresses};
+use crate::{NetworkProfile, PccsReader};
+
+/// A network profile name with optional overrides, as written in configuration files.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub network: Option<String>,
+    pub rpc_url: Option<String>,
+    pub fallback_rpc_urls: Option<Vec<String>>,
+    pub rpc: Option<RpcPolicy>,
+    pub enclave_id_dao: Option<Address>,
+    pub fmspc_tcb_dao: Option<Address>,
+    pub pcs_dao: Option<Address>,
+    pub pck_dao: Option<Address>,
+    pub multicall: Option<Address>,
+    pub dcap_attestation: Option<Address>,
+    pub helpers: Option<HelperAddresses>,
+    pub pccs_router: Option<Address>,
+    pub code_hashes: Option<CodeHashes>,
+    // read the DAO addresses from the PCCS router at startup
+    pub resolve_addresses: bool,
+}
+
+impl NetworkConfig {
+    pub fn profile(&self) -> Result<NetworkProfile> {
+        let mut network = match self.network.as_deref() {
+            Some(name) => NetworkProfile::from_name(name)?,
+            None => NetworkProfile::default(),
+        };
+        if let Some(rpc_url) = self.rpc_url.as_deref() {
+            network = network.with_rpc_url(rpc_url);
+        }
+        if let Some(urls) = &self.fallback_rpc_urls {
+            network.fallback_rpc_urls = urls.clone();
+        }
+        if let Some(rpc) = &self.rpc {
+            network.rpc = rpc.clone();
+        }
+        network.enclave_id_dao = self.enclave_id_dao.unwrap_or(network.enclave_id_dao);
+        network.fmspc_tcb_dao = self.fmspc_tcb_dao.unwrap_or(network.fmspc_tcb_dao);
+        network.pcs_dao = self.pcs_dao.unwrap_or(network.pcs_dao);
+        network.pck_dao = self.pck_dao.unwrap_or(network.pck_dao);
+        network.multicall = self.multicall.unwrap_or(network.multicall);
+        network.dcap_attestation = self.dcap_attestation.unwrap_or(network.dcap_attestation);
+        network.helpers = self.helpers.or(network.helpers);
+        network.pccs_router = self.pccs_router.unwrap_or(network.pccs_router);
+        network.code_hashes = self.code_hashes.or(network.code_hashes);
+        Ok(network)
+    }
+
+    /// A reader for the profile, with the DAO addresses resolved from the PCCS router
+    /// when `resolve_addresses` is set.
+    pub async fn reader(&self) -> Result<PccsReader> {
+        let reader = PccsReader::new(self.profile()?)?;
+        match self.resolve_addresses {
+            true => reader.resolve_addresses().await,
+            false => Ok(reader),
+        }
+    }
+}
+
+/// Parses JSON where lines starting with `//` are comments, like `sgx_default_qcnl.conf`.
+pub fn from_commented_json<T: DeserializeOwned>(json: &str) -> Result<T> {
+    let json: String = json
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(serde_json::from_str(&json)?)
+}
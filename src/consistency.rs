@@ -0,0 +1,545 @@
+//! Parses collateral off chain into the same shapes the helper contracts return, and
+//! flags every field where the contracts and this crate read the collateral differently.
+
+use alloy::{primitives::U256, transports::RpcError};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use x509_parser::oid_registry::asn1_rs::{oid, FromDer, OctetString, Oid, Sequence};
+use x509_parser::prelude::{CertificateRevocationList, ParsedExtension, X509Certificate, X509Name};
+
+use crate::attestation::TcbStatus;
+use crate::collateral::QuoteCollateral;
+use crate::pccs::helpers::{
+    EnclaveTcbStatus, IdentityTcbLevel, ParsedCertificate, ParsedCrl, ParsedIdentity,
+    ParsedTcbInfo, PckExtension, TcbLevel, PCK_CERT_SUBJECT,
+};
+use crate::PccsReader;
+
+/// A field the two parses disagree on, with its value on each side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    // e.g. `tcb_levels[2].status`
+    pub field: String,
+    pub onchain: Value,
+    pub offchain: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsistencyReport {
+    pub collateral: String,
+    // a side that could not parse the collateral at all
+    pub onchain_error: Option<String>,
+    pub offchain_error: Option<String>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.onchain_error.is_none() && self.offchain_error.is_none() && self.discrepancies.is_empty()
+    }
+}
+
+impl PccsReader {
+    pub async fn check_identity_parse(&self, identity_str: &str) -> Result<ConsistencyReport> {
+        consistency(
+            "enclave identity",
+            self.parse_identity_onchain(identity_str).await,
+            ParsedIdentity::from_json_body(identity_str),
+        )
+    }
+
+    pub async fn check_tcb_info_parse(&self, tcb_info_str: &str) -> Result<ConsistencyReport> {
+        consistency(
+            "TCB info",
+            self.parse_tcb_info_onchain(tcb_info_str).await,
+            ParsedTcbInfo::from_json_body(tcb_info_str),
+        )
+    }
+
+    pub async fn check_certificate_parse(&self, der: &[u8]) -> Result<ConsistencyReport> {
+        consistency(
+            "certificate",
+            self.parse_certificate_onchain(der).await,
+            ParsedCertificate::from_der(der),
+        )
+    }
+
+    pub async fn check_crl_parse(&self, der: &[u8]) -> Result<ConsistencyReport> {
+        consistency(
+            "CRL",
+            self.parse_crl_onchain(der).await,
+            ParsedCrl::from_der(der),
+        )
+    }
+
+    /// Checks every certificate, CRL, identity and TCB Info of the collateral of a quote.
+    pub async fn check_collateral_parse(
+        &self,
+        collateral: &QuoteCollateral,
+    ) -> Result<Vec<ConsistencyReport>> {
+        let named = |name: &str, mut report: ConsistencyReport| {
+            report.collateral = name.to_string();
+            report
+        };
+        let mut reports = vec![
+            named("Root CA certificate", self.check_certificate_parse(&collateral.root_ca).await?),
+            named("Root CA CRL", self.check_crl_parse(&collateral.root_ca_crl).await?),
+            named("PCK CA certificate", self.check_certificate_parse(&collateral.pck_ca).await?),
+            named("PCK CA CRL", self.check_crl_parse(&collateral.pck_crl).await?),
+            named(
+                "TCB Signing certificate",
+                self.check_certificate_parse(&collateral.tcb_signing_ca).await?,
+            ),
+        ];
+        reports.push(self.check_tcb_info_parse(&collateral.tcb_info.tcbInfoStr).await?);
+        reports.push(self.check_identity_parse(&collateral.qe_identity.identityStr).await?);
+        Ok(reports)
+    }
+}
+
+// Compares the two parses field by field. A revert of the helper is a parse error,
+// any other failure of the call is returned.
+fn consistency<T: Serialize>(
+    collateral: &str,
+    onchain: Result<T>,
+    offchain: Result<T>,
+) -> Result<ConsistencyReport> {
+    let mut report = ConsistencyReport {
+        collateral: collateral.to_string(),
+        onchain_error: None,
+        offchain_error: None,
+        discrepancies: Vec::new(),
+    };
+    let onchain = match onchain {
+        Ok(parsed) => Some(serde_json::to_value(parsed)?),
+        Err(e) => match e.downcast_ref::<alloy::contract::Error>() {
+            Some(alloy::contract::Error::TransportError(RpcError::ErrorResp(payload))) => {
+                report.onchain_error = Some(payload.message.clone());
+                None
+            }
+            _ => return Err(e),
+        },
+    };
+    let offchain = match offchain {
+        Ok(parsed) => Some(serde_json::to_value(parsed)?),
+        Err(e) => {
+            report.offchain_error = Some(e.to_string());
+            None
+        }
+    };
+
+    if let (Some(onchain), Some(offchain)) = (onchain, offchain) {
+        compare("", &onchain, &offchain, &mut report.discrepancies);
+    }
+    Ok(report)
+}
+
+fn compare(field: &str, onchain: &Value, offchain: &Value, out: &mut Vec<Discrepancy>) {
+    match (onchain, offchain) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                let field = match field {
+                    "" => key.clone(),
+                    _ => format!("{}.{}", field, key),
+                };
+                compare(&field, value, b.get(key).unwrap_or(&Value::Null), out);
+            }
+        }
+        // levels are compared one by one, unless there are not as many on each side
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                compare(&format!("{}[{}]", field, i), a, b, out);
+            }
+        }
+        (a, b) if a != b => out.push(Discrepancy {
+            field: field.to_string(),
+            onchain: a.clone(),
+            offchain: b.clone(),
+        }),
+        _ => {}
+    }
+}
+
+impl ParsedIdentity {
+    /// Parses an Enclave Identity body the way `EnclaveIdentityHelper` does.
+    pub fn from_json_body(body: &str) -> Result<Self> {
+        let body: Value = serde_json::from_str(body)?;
+        let id = match body["id"].as_str() {
+            Some("QE") => 0,
+            Some("QVE") => 1,
+            Some("TD_QE") => 2,
+            id => return Err(anyhow::anyhow!("Unknown enclave identity id {:?}", id)),
+        };
+        let tcb_levels = array(&body, "tcbLevels")?
+            .iter()
+            .map(|level| {
+                let status = match level["tcbStatus"].as_str() {
+                    Some("UpToDate") => EnclaveTcbStatus::UpToDate,
+                    Some("Revoked") => EnclaveTcbStatus::Revoked,
+                    Some("OutOfDate") => EnclaveTcbStatus::OutOfDate,
+                    _ => EnclaveTcbStatus::NotSupported,
+                };
+                Ok(IdentityTcbLevel {
+                    isvsvn: number(&level["tcb"], "isvsvn")?,
+                    tcb_date: timestamp(level, "tcbDate")?,
+                    status,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ParsedIdentity {
+            id,
+            version: number(&body, "version")?,
+            issue_date: timestamp(&body, "issueDate")?,
+            next_update: timestamp(&body, "nextUpdate")?,
+            tcb_evaluation_data_number: number(&body, "tcbEvaluationDataNumber")?,
+            miscselect: hex_field(&body, "miscselect")?,
+            miscselect_mask: hex_field(&body, "miscselectMask")?,
+            attributes: hex_field(&body, "attributes")?,
+            attributes_mask: hex_field(&body, "attributesMask")?,
+            mrsigner: hex_field(&body, "mrsigner")?,
+            isvprodid: number(&body, "isvprodid")?,
+            tcb_levels,
+        })
+    }
+}
+
+impl ParsedTcbInfo {
+    /// Parses a TCB Info body the way `FmspcTcbHelper` does, for versions 2 and 3.
+    pub fn from_json_body(body: &str) -> Result<Self> {
+        let body: Value = serde_json::from_str(body)?;
+        // version 2 has no id, it is SGX only
+        let id = match body["id"].as_str() {
+            None | Some("SGX") => 0,
+            Some("TDX") => 1,
+            Some(id) => return Err(anyhow::anyhow!("Unknown TCB info id {}", id)),
+        };
+        let version = number(&body, "version")?;
+        let tcb_levels = array(&body, "tcbLevels")?
+            .iter()
+            .map(|level| tcb_level(level, version))
+            .collect::<Result<_>>()?;
+
+        Ok(ParsedTcbInfo {
+            tcb_type: body["tcbType"].as_u64().unwrap_or_default() as u8,
+            id,
+            version,
+            issue_date: timestamp(&body, "issueDate")?,
+            next_update: timestamp(&body, "nextUpdate")?,
+            tcb_evaluation_data_number: number(&body, "tcbEvaluationDataNumber")?,
+            fmspc: hex_field(&body, "fmspc")?,
+            pceid: hex_field(&body, "pceId")?,
+            tcb_levels,
+        })
+    }
+}
+
+// Version 2 names each SVN, version 3 lists the components
+fn tcb_level(level: &Value, version: u32) -> Result<TcbLevel> {
+    let tcb = &level["tcb"];
+    let svns = |components: &Value| -> Result<Vec<u8>> {
+        match components.as_array() {
+            Some(components) => components.iter().map(|c| number(c, "svn")).collect(),
+            None => Ok(Vec::new()),
+        }
+    };
+    let (sgx_cpu_svns, tdx_svns) = match version {
+        2 => (
+            (1..=16)
+                .map(|i| number(tcb, &format!("sgxtcbcomp{:02}svn", i)))
+                .collect::<Result<_>>()?,
+            Vec::new(),
+        ),
+        _ => (svns(&tcb["sgxtcbcomponents"])?, svns(&tcb["tdxtcbcomponents"])?),
+    };
+    let status = match level["tcbStatus"].as_str() {
+        Some("UpToDate") => TcbStatus::UpToDate,
+        Some("SWHardeningNeeded") => TcbStatus::SwHardeningNeeded,
+        Some("ConfigurationAndSWHardeningNeeded") => TcbStatus::ConfigurationAndSwHardeningNeeded,
+        Some("ConfigurationNeeded") => TcbStatus::ConfigurationNeeded,
+        Some("OutOfDate") => TcbStatus::OutOfDate,
+        Some("OutOfDateConfigurationNeeded") => TcbStatus::OutOfDateConfigurationNeeded,
+        Some("Revoked") => TcbStatus::Revoked,
+        _ => TcbStatus::Unrecognized,
+    };
+    let advisory_ids = match level["advisoryIDs"].as_array() {
+        Some(ids) => ids.iter().filter_map(|id| id.as_str().map(String::from)).collect(),
+        None => Vec::new(),
+    };
+
+    Ok(TcbLevel {
+        pcesvn: number(tcb, "pcesvn")?,
+        sgx_cpu_svns,
+        tdx_svns,
+        tcb_date: timestamp(level, "tcbDate")?,
+        status,
+        advisory_ids,
+    })
+}
+
+impl ParsedCertificate {
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let (_, cert) = X509Certificate::from_der(der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+
+        let mut authority_key_identifier = Vec::new();
+        let mut subject_key_identifier = Vec::new();
+        for extension in cert.extensions() {
+            match extension.parsed_extension() {
+                ParsedExtension::AuthorityKeyIdentifier(aki) => {
+                    if let Some(key_identifier) = &aki.key_identifier {
+                        authority_key_identifier = key_identifier.0.to_vec();
+                    }
+                }
+                ParsedExtension::SubjectKeyIdentifier(ski) => {
+                    subject_key_identifier = ski.0.to_vec();
+                }
+                _ => {}
+            }
+        }
+        let public_key = cert.public_key().subject_public_key.data.as_ref();
+        let subject_public_key = match public_key {
+            [0x04, point @ ..] if point.len() == 64 => point.to_vec(),
+            _ => public_key.to_vec(),
+        };
+        let subject_common_name = common_name(cert.subject());
+
+        Ok(ParsedCertificate {
+            serial_number: U256::try_from_be_slice(cert.raw_serial())
+                .ok_or_else(|| anyhow::Error::msg("Serial number over 256 bits"))?,
+            issuer_common_name: common_name(cert.issuer()),
+            not_before: cert.validity().not_before.timestamp() as u64,
+            not_after: cert.validity().not_after.timestamp() as u64,
+            subject_public_key,
+            authority_key_identifier,
+            subject_key_identifier,
+            pck_extension: match subject_common_name == PCK_CERT_SUBJECT {
+                true => Some(pck_extension(&cert)?),
+                false => None,
+            },
+            subject_common_name,
+        })
+    }
+}
+
+impl ParsedCrl {
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let (_, crl) = CertificateRevocationList::from_der(der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse CRL: {}", e))?;
+        let revoked_serial_numbers = crl
+            .iter_revoked_certificates()
+            .map(|revoked| {
+                U256::try_from_be_slice(revoked.raw_serial())
+                    .ok_or_else(|| anyhow::Error::msg("Serial number over 256 bits"))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ParsedCrl {
+            serial_number: crl
+                .crl_number()
+                .and_then(|number| U256::try_from_be_slice(&number.to_bytes_be()))
+                .unwrap_or_default(),
+            issuer_common_name: common_name(crl.issuer()),
+            this_update: crl.last_update().timestamp() as u64,
+            next_update: crl
+                .next_update()
+                .map(|next_update| next_update.timestamp() as u64)
+                .unwrap_or_default(),
+            revoked_serial_numbers,
+        })
+    }
+}
+
+// The TCB (1.2.840.113741.1.13.1.2), PCE ID (.3) and FMSPC (.4) of the SGX extension
+fn pck_extension(cert: &X509Certificate) -> Result<PckExtension> {
+    let sgx_extension = cert
+        .get_extension_unique(&oid!(1.2.840 .113741 .1 .13 .1))
+        .map_err(|e| anyhow::anyhow!("Failed to read the SGX extension: {}", e))?
+        .ok_or_else(|| anyhow::Error::msg("No SGX extension"))?;
+
+    let mut extension = PckExtension {
+        pcesvn: 0,
+        cpu_svns: Vec::new(),
+        fmspc: Vec::new(),
+        pceid: Vec::new(),
+    };
+    let (_, pairs) = Sequence::from_der(sgx_extension.value).map_err(asn1_error)?;
+    for (id, value) in oid_values(pairs.content.as_ref())? {
+        match id.as_str() {
+            "1.2.840.113741.1.13.1.2" => {
+                let (_, tcb) = Sequence::from_der(&value).map_err(asn1_error)?;
+                for (id, value) in oid_values(tcb.content.as_ref())? {
+                    let component: u16 = id
+                        .rsplit('.')
+                        .next()
+                        .and_then(|component| component.parse().ok())
+                        .unwrap_or_default();
+                    match component {
+                        1..=16 => extension.cpu_svns.push(u8::from_der(&value).map_err(asn1_error)?.1),
+                        17 => extension.pcesvn = u16::from_der(&value).map_err(asn1_error)?.1,
+                        _ => {}
+                    }
+                }
+            }
+            "1.2.840.113741.1.13.1.3" => {
+                extension.pceid = OctetString::from_der(&value).map_err(asn1_error)?.1.as_ref().to_vec();
+            }
+            "1.2.840.113741.1.13.1.4" => {
+                extension.fmspc = OctetString::from_der(&value).map_err(asn1_error)?.1.as_ref().to_vec();
+            }
+            _ => {}
+        }
+    }
+    Ok(extension)
+}
+
+// The (OID, DER value) pairs of a sequence of `SEQUENCE { OID, value }`
+fn oid_values(mut der: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut pairs = Vec::new();
+    while !der.is_empty() {
+        let (rest, pair) = Sequence::from_der(der).map_err(asn1_error)?;
+        der = rest;
+        let (value, id) = Oid::from_der(pair.content.as_ref()).map_err(asn1_error)?;
+        pairs.push((id.to_id_string(), value.to_vec()));
+    }
+    Ok(pairs)
+}
+
+fn asn1_error<E: std::fmt::Display>(e: E) -> anyhow::Error {
+    anyhow::anyhow!("Failed to parse the SGX extension: {}", e)
+}
+
+fn common_name(name: &X509Name) -> String {
+    name.iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn number<T: TryFrom<u64>>(value: &Value, key: &str) -> Result<T> {
+    value[key]
+        .as_u64()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| anyhow::anyhow!("Missing or invalid {}", key))
+}
+
+fn timestamp(value: &Value, key: &str) -> Result<u64> {
+    let date = value[key]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing {}", key))?;
+    Ok(OffsetDateTime::parse(date, &Rfc3339)?.unix_timestamp() as u64)
+}
+
+fn hex_field<const N: usize>(value: &Value, key: &str) -> Result<[u8; N]> {
+    let hex_value = value[key]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing {}", key))?;
+    hex::decode(hex_value)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("{} must be {} bytes", key, N))
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>> {
+    value[key]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("Missing {}", key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use x509_parser::pem::Pem;
+
+    const IDENTITY: &str = r#"{"id":"QE","version":2,"issueDate":"2024-08-01T00:00:00Z","nextUpdate":"2024-08-31T00:00:00Z","tcbEvaluationDataNumber":17,"miscselect":"00000000","miscselectMask":"FFFFFFFF","attributes":"11000000000000000000000000000000","attributesMask":"FBFFFFFFFFFFFFFF0000000000000000","mrsigner":"8C4F5775D796503E96137F77C68A829A0056AC8DED70140B081B094490C57BFF","isvprodid":1,"tcbLevels":[{"tcb":{"isvsvn":8},"tcbDate":"2024-03-13T00:00:00Z","tcbStatus":"UpToDate"},{"tcb":{"isvsvn":6},"tcbDate":"2021-11-10T00:00:00Z","tcbStatus":"OutOfDate"}]}"#;
+
+    // Every certificate of the PCK chain embedded in the quote
+    fn quote_certificates() -> Vec<Vec<u8>> {
+        let raw_quote = hex::decode(include_str!("../tests/data/v3_sgx_quote.hex").trim()).unwrap();
+        let start = raw_quote.windows(10).position(|w| w == b"-----BEGIN").unwrap();
+        Pem::iter_from_buffer(&raw_quote[start..])
+            .map_while(|pem| pem.ok())
+            .map(|pem| pem.contents)
+            .collect()
+    }
+
+    #[test]
+    fn test_parses_identity() {
+        let identity = ParsedIdentity::from_json_body(IDENTITY).unwrap();
+        assert_eq!((identity.id, identity.version, identity.isvprodid), (0, 2, 1));
+        assert_eq!(identity.issue_date, 1722470400);
+        assert_eq!(identity.miscselect_mask, [0xff; 4]);
+        assert_eq!(identity.tcb_levels.len(), 2);
+        assert_eq!(identity.tcb_levels[1].isvsvn, 6);
+        assert_eq!(identity.tcb_levels[1].status, EnclaveTcbStatus::OutOfDate);
+    }
+
+    #[test]
+    fn test_parses_tcb_info_versions() {
+        let mut components: Vec<String> =
+            (1..=16).map(|i| format!(r#""sgxtcbcomp{:02}svn":{}"#, i, i)).collect();
+        components.push(r#""pcesvn":13"#.to_string());
+        let v2 = format!(
+            r#"{{"version":2,"issueDate":"2024-08-01T00:00:00Z","nextUpdate":"2024-08-31T00:00:00Z","fmspc":"00606a000000","pceId":"0000","tcbType":0,"tcbEvaluationDataNumber":17,"tcbLevels":[{{"tcb":{{{}}},"tcbDate":"2024-03-13T00:00:00Z","tcbStatus":"SWHardeningNeeded"}}]}}"#,
+            components.join(",")
+        );
+        let tcb_info = ParsedTcbInfo::from_json_body(&v2).unwrap();
+        assert_eq!((tcb_info.id, tcb_info.version), (0, 2));
+        assert_eq!(tcb_info.fmspc, [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00]);
+        let level = &tcb_info.tcb_levels[0];
+        assert_eq!(level.sgx_cpu_svns, (1..=16).collect::<Vec<u8>>());
+        assert_eq!((level.pcesvn, level.status), (13, TcbStatus::SwHardeningNeeded));
+
+        let v3 = r#"{"id":"TDX","version":3,"issueDate":"2024-08-01T00:00:00Z","nextUpdate":"2024-08-31T00:00:00Z","fmspc":"90c06f000000","pceId":"0000","tcbType":0,"tcbEvaluationDataNumber":17,"tcbLevels":[{"tcb":{"sgxtcbcomponents":[{"svn":2},{"svn":2}],"pcesvn":11,"tdxtcbcomponents":[{"svn":5,"category":"OS/VMM"}]},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"OutOfDate","advisoryIDs":["INTEL-SA-00837"]}]}"#;
+        let tcb_info = ParsedTcbInfo::from_json_body(v3).unwrap();
+        assert_eq!((tcb_info.id, tcb_info.version), (1, 3));
+        let level = &tcb_info.tcb_levels[0];
+        assert_eq!((level.sgx_cpu_svns.as_slice(), level.tdx_svns.as_slice()), (&[2, 2][..], &[5][..]));
+        assert_eq!(level.advisory_ids, ["INTEL-SA-00837"]);
+    }
+
+    #[test]
+    fn test_parses_certificates() {
+        let certificates = quote_certificates();
+        assert_eq!(certificates.len(), 3);
+
+        let pck = ParsedCertificate::from_der(&certificates[0]).unwrap();
+        assert_eq!(pck.subject_common_name, PCK_CERT_SUBJECT);
+        assert_eq!(pck.subject_public_key.len(), 64);
+        let extension = pck.pck_extension.unwrap();
+        assert_eq!((extension.cpu_svns.len(), extension.pceid.len()), (16, 2));
+        assert_eq!(extension.fmspc, [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00]);
+
+        let root = ParsedCertificate::from_der(&certificates[2]).unwrap();
+        assert_eq!(root.issuer_common_name, "Intel SGX Root CA");
+        assert_eq!(root.subject_common_name, root.issuer_common_name);
+        assert_eq!(root.pck_extension, None);
+        assert!(root.not_before < root.not_after);
+    }
+
+    #[test]
+    fn test_parses_crl() {
+        let crl = ParsedCrl::from_der(include_bytes!("../tests/data/crl_number_2.der")).unwrap();
+        assert_eq!(crl.serial_number, U256::from(2));
+        assert!(crl.this_update < crl.next_update);
+    }
+
+    #[test]
+    fn test_reports_discrepancies() {
+        let offchain = ParsedIdentity::from_json_body(IDENTITY).unwrap();
+        let mut onchain = offchain.clone();
+        onchain.tcb_levels[1].status = EnclaveTcbStatus::Revoked;
+        onchain.isvprodid = 2;
+
+        let report = consistency("QE identity", Ok(onchain), Ok(offchain.clone())).unwrap();
+        let fields: Vec<&str> = report.discrepancies.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["isvprodid", "tcb_levels[1].status"]);
+        assert_eq!(report.discrepancies[1].onchain, "Revoked");
+        assert!(!report.is_consistent());
+
+        let report = consistency("QE identity", Ok(offchain.clone()), Ok(offchain)).unwrap();
+        assert!(report.is_consistent());
+    }
+}
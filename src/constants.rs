@@ -5,8 +5,61 @@ pub const DEFAULT_RPC_URL: &str = "https://rpc-testnet.ata.network";
 pub const SGX_TEE_TYPE: u32 = 0x00000000;
 pub const TDX_TEE_TYPE: u32 = 0x00000081;
 
+// Multicall3, deployed at the same address on most chains
+pub const MULTICALL3_ADDRESS: &str = "cA11bde05977b3631167028862bE2a173976CA11";
+
 // PCCS addresses
 pub const ENCLAVE_ID_DAO_ADDRESS: &str = "413272890ab9F155a47A5F90a404Fb51aa259087";
 pub const FMSPC_TCB_DAO_ADDRESS: &str = "7c04B466DebA13D48116b1339C62b35B9805E5A0";
 pub const PCS_DAO_ADDRESS: &str = "D0335cbC73CA2f8EDd98a2BE3909f55642F414D7";
-pub const PCK_DAO_ADDRESS: &str = "6D4cA6AE5315EBBcb4331c82531db0ad8853Eb31";
\ No newline at end of file
+pub const PCK_DAO_ADDRESS: &str = "6D4cA6AE5315EBBcb4331c82531db0ad8853Eb31";
+pub const DCAP_ATTESTATION_ADDRESS: &str = "efE368b17D137E86298eec8EbC5502fb56d27832";
+pub const PCCS_ROUTER_ADDRESS: &str = "bFDeE7A1f1bFA2267cD0DA50BE76D8c4a3864543";
+pub const PCCS_STORAGE_ADDRESS: &str = "e8599DD2366230B7EfDD526985c64C7325b27569";
+
+// Declaration slot of the `mapping(bytes32 => bytes)` of PCCS_Storage, see `proof::DaoStorageLayout`
+pub const PCCS_STORAGE_SLOT: u64 = 3;
+
+// Parsers the DAOs run collateral through
+pub const ENCLAVE_IDENTITY_HELPER_ADDRESS: &str = "fd4a34b578B352FE1896CDafaEb0f45f993352Bf";
+pub const FMSPC_TCB_HELPER_ADDRESS: &str = "C2A662e08A35513596E22D0aC236Ce72e59125EE";
+pub const PCK_HELPER_ADDRESS: &str = "5213c0e3Ab478dbc83E8afFF8909717332E4f8E1";
+pub const CRL_HELPER_ADDRESS: &str = "12C1E13Aa2a238EAb15c2e2b6AC670266bc3C814";
+
+// Automata Testnet
+pub const AUTOMATA_TESTNET_CHAIN_ID: u64 = 1398243;
+
+// Automata Mainnet
+pub const AUTOMATA_MAINNET_CHAIN_ID: u64 = 65536;
+pub const AUTOMATA_MAINNET_RPC_URL: &str = "https://rpc.ata.network";
+pub const AUTOMATA_MAINNET_ENCLAVE_ID_DAO_ADDRESS: &str = "28111536292b34f37120861A46B39BF39187d73a";
+pub const AUTOMATA_MAINNET_FMSPC_TCB_DAO_ADDRESS: &str = "868c18869f68E0E0b0b7B2B4439f7fDDd0421e6b";
+pub const AUTOMATA_MAINNET_PCS_DAO_ADDRESS: &str = "86f8865BCe8BE62CB8096b5B94fA3fB3a6ED330c";
+pub const AUTOMATA_MAINNET_PCK_DAO_ADDRESS: &str = "eCc198936FcA3Ca1fDc97B8612B32185908917B0";
+pub const AUTOMATA_MAINNET_DCAP_ATTESTATION_ADDRESS: &str = "E26E11B257856B0bEBc4C759aaBDdea72B64351F";
+pub const AUTOMATA_MAINNET_PCCS_ROUTER_ADDRESS: &str = "b76834729717868fa203b9D90fc88F859A4E594D";
+pub const AUTOMATA_MAINNET_PCCS_STORAGE_ADDRESS: &str = "E2636fdbd053da8E798D959304e20fADa934E8c0";
+pub const AUTOMATA_MAINNET_ENCLAVE_IDENTITY_HELPER_ADDRESS: &str = "13BECaa512713Ac7C2d7a04ba221aD5E02D43DFE";
+pub const AUTOMATA_MAINNET_FMSPC_TCB_HELPER_ADDRESS: &str = "c99bF04C31bF3d026B5B47b2574FC19C1459B732";
+pub const AUTOMATA_MAINNET_X509_HELPER_ADDRESS: &str = "3e2fe733E444313A93Fa3f9AEd3bB203048dDE70";
+pub const AUTOMATA_MAINNET_X509_CRL_HELPER_ADDRESS: &str = "2567245dE6E349C8B7AA82fD6FF854b844A0aEF9";
+
+// Ethereum Holesky Testnet
+pub const HOLESKY_CHAIN_ID: u64 = 17000;
+pub const HOLESKY_RPC_URL: &str = "https://1rpc.io/holesky";
+pub const HOLESKY_ENCLAVE_ID_DAO_ADDRESS: &str = "9f4b0fB3A95072bD133082e9683A3536669EFE07";
+pub const HOLESKY_FMSPC_TCB_DAO_ADDRESS: &str = "aB5074445E5ae3C650553d5a7560B3A7121635B9";
+pub const HOLESKY_PCS_DAO_ADDRESS: &str = "66FdB4E72d2F4a7e2081bf83F1FfACC9bbCb384b";
+pub const HOLESKY_PCK_DAO_ADDRESS: &str = "5B2d7781E3c44966769484daBCdc435EFD281c34";
+pub const HOLESKY_DCAP_ATTESTATION_ADDRESS: &str = "133303659F51d75ED216FD98a0B70CbCD75339b2";
+pub const HOLESKY_PCCS_ROUTER_ADDRESS: &str = "dE5e69A2ca2556fe46883d754d987703bF28Cc51";
+
+// Ethereum Sepolia Testnet
+pub const SEPOLIA_CHAIN_ID: u64 = 11155111;
+pub const SEPOLIA_RPC_URL: &str = "https://1rpc.io/sepolia";
+pub const SEPOLIA_ENCLAVE_ID_DAO_ADDRESS: &str = "4bb680A5e6Ad6228E7d334903B0Ce10EF60c961C";
+pub const SEPOLIA_FMSPC_TCB_DAO_ADDRESS: &str = "F790b1C23e6508A6135Ce88450eC0A59Af0B9896";
+pub const SEPOLIA_PCS_DAO_ADDRESS: &str = "348DA46aA11188f641f01dbe247b25FFA5FFB9c4";
+pub const SEPOLIA_PCK_DAO_ADDRESS: &str = "3eA9D905Cb79586C2184f329e6a651D97F2ebee3";
+pub const SEPOLIA_DCAP_ATTESTATION_ADDRESS: &str = "76A3657F2d6c5C66733e9b69ACaDadCd0B68788b";
+pub const SEPOLIA_PCCS_ROUTER_ADDRESS: &str = "dc7dcF60b9580980128539Ed805D03BC60F84fd4";
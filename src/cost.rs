@@ -0,0 +1,136 @@
+//! Gas and cost estimates for verifying a quote on chain and for the upserts that fill
+//! its missing collateral, priced at the current EIP-1559 fees.
+
+use alloy::{
+    network::TransactionBuilder, providers::Provider, rpc::types::TransactionRequest,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::attestation::IDcapAttestation;
+use crate::hypothetical::state_overrides;
+use crate::quote::parse_quote;
+use crate::source::CollateralSource;
+use crate::writer::UpsertCall;
+use crate::{MissingCollateral, PccsReader};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasEstimate {
+    // the upserted collateral, or the verification
+    pub action: String,
+    pub gas: u64,
+    // at the max fee per gas, so what the transaction costs at most
+    pub max_cost_wei: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CostReport {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub estimates: Vec<GasEstimate>,
+    // actions the node could not estimate, with its error, e.g. a verification
+    // that reverts until the missing collateral is filled
+    pub unestimated: Vec<(String, String)>,
+    pub total_gas: u64,
+    pub total_max_cost_wei: u128,
+}
+
+const VERIFICATION: &str = "verifyAndAttestOnChain";
+
+impl PccsReader {
+    pub async fn estimate_verification_gas(&self, raw_quote: &[u8]) -> Result<u64> {
+        let call = IDcapAttestation::verifyAndAttestOnChainCall {
+            rawQuote: raw_quote.to_vec().into(),
+        };
+        let request = TransactionRequest::default()
+            .with_to(self.network().dcap_attestation)
+            .with_input(call.abi_encode());
+        self.estimate_gas(&request).await
+    }
+
+    pub async fn estimate_upsert_gas(&self, call: &UpsertCall) -> Result<u64> {
+        self.estimate_gas(&call.to_request()).await
+    }
+
+    /// What filling the missing collateral from `source` costs, one upsert at a time.
+    pub async fn missing_collateral_cost<S: CollateralSource + ?Sized>(
+        &self,
+        missing: &[MissingCollateral],
+        source: &S,
+    ) -> Result<CostReport> {
+        let calls = self.upsert_calls(missing, source).await?;
+        let mut actions = Vec::with_capacity(calls.len());
+        for call in &calls {
+            actions.push((call.kind.to_string(), self.estimate_upsert_gas(call).await));
+        }
+        self.cost_report(actions).await
+    }
+
+    /// What verifying the quote on chain costs, with the upserts of the collateral
+    /// it lacks filled from `source` first. The verification is estimated over state
+    /// overrides holding that collateral where the storage layout of the network keeps
+    /// it; without a layout, a quote that lacks collateral is left unestimated.
+    pub async fn quote_cost<S: CollateralSource + ?Sized>(
+        &self,
+        raw_quote: &[u8],
+        source: &S,
+    ) -> Result<CostReport> {
+        parse_quote(raw_quote)?;
+        let reader = self.pin_block().await?;
+        let missing = reader.all_missing_collaterals(raw_quote).await?;
+
+        let calls = reader.upsert_calls(&missing, source).await?;
+        let mut actions = Vec::with_capacity(calls.len() + 1);
+        for call in &calls {
+            actions.push((call.kind.to_string(), reader.estimate_upsert_gas(call).await));
+        }
+        let verifier = match &reader.network().storage {
+            Some(layout) if !calls.is_empty() => reader
+                .clone()
+                .with_state_overrides(state_overrides(&calls, layout)?),
+            _ => reader.clone(),
+        };
+        actions.push((
+            VERIFICATION.to_string(),
+            verifier.estimate_verification_gas(raw_quote).await,
+        ));
+        reader.cost_report(actions).await
+    }
+
+    async fn estimate_gas(&self, request: &TransactionRequest) -> Result<u64> {
+        let estimate = self.provider().estimate_gas(request).block(self.block());
+        let gas = match self.state_overrides() {
+            Some(overrides) => estimate.overrides(overrides).await?,
+            None => estimate.await?,
+        };
+        Ok(u64::try_from(gas)?)
+    }
+
+    // Prices the estimates at the current fees
+    async fn cost_report(&self, actions: Vec<(String, Result<u64>)>) -> Result<CostReport> {
+        let fees = self.provider().estimate_eip1559_fees(None).await?;
+
+        let mut estimates = Vec::new();
+        let mut unestimated = Vec::new();
+        for (action, gas) in actions {
+            match gas {
+                Ok(gas) => estimates.push(GasEstimate {
+                    action,
+                    gas,
+                    max_cost_wei: gas as u128 * fees.max_fee_per_gas,
+                }),
+                Err(e) => unestimated.push((action, e.to_string())),
+            }
+        }
+
+        Ok(CostReport {
+            max_fee_per_gas: fees.max_fee_per_gas,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+            total_gas: estimates.iter().map(|estimate| estimate.gas).sum(),
+            total_max_cost_wei: estimates.iter().map(|estimate| estimate.max_cost_wei).sum(),
+            estimates,
+            unestimated,
+        })
+    }
+}
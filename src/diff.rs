@@ -0,0 +1,241 @@
+//! Compares the collateral of two networks, e.g. Automata mainnet and Sepolia, which drift
+//! apart as they are filled independently, and copies the newer signed collateral of one
+//! to the other through the DAO upserts.
+
+use std::cmp::Ordering;
+
+use alloy::{primitives::TxHash, signers::local::PrivateKeySigner};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::freshness::{serialize_display, CollateralVersion};
+use crate::pccs::is_not_stored;
+use crate::source::snapshot::{SNAPSHOT_CAS, SNAPSHOT_IDENTITIES, SNAPSHOT_TCB_INFOS};
+use crate::writer::{UpsertCall, UpsertKind};
+use crate::{MissingCollateral, NetworkProfile, PccsReader, CA};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Side {
+    A,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DiffStatus {
+    Identical,
+    Newer(Side),
+    // both sides have an issue, and their versions do not tell which is newer
+    Conflicting,
+    OnlyOn(Side),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CollateralDiff {
+    #[serde(serialize_with = "serialize_display")]
+    pub collateral: UpsertKind,
+    pub status: DiffStatus,
+    // None for certificates, and for collateral missing on that side
+    pub a: Option<CollateralVersion>,
+    pub b: Option<CollateralVersion>,
+}
+
+/// Every collateral stored on either network, collateral missing on both is left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkDiff {
+    pub network_a: String,
+    pub network_b: String,
+    pub collaterals: Vec<CollateralDiff>,
+}
+
+impl NetworkDiff {
+    pub fn is_in_sync(&self) -> bool {
+        self.collaterals
+            .iter()
+            .all(|diff| diff.status == DiffStatus::Identical)
+    }
+
+    /// The collateral one side lacks, or has an older issue of, as the other side has it.
+    pub fn missing_on(&self, side: Side) -> Vec<MissingCollateral> {
+        let other = match side {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        };
+        self.collaterals
+            .iter()
+            .filter(|diff| {
+                matches!(diff.status, DiffStatus::Newer(s) | DiffStatus::OnlyOn(s) if s == other)
+            })
+            .map(|diff| match &diff.collateral {
+                UpsertKind::PcsCertificate(ca) => MissingCollateral::PCS(*ca, true, false),
+                UpsertKind::PcsCrl(ca) => MissingCollateral::PCS(*ca, false, true),
+                UpsertKind::FmspcTcb(tcb_type, fmspc, version) => {
+                    MissingCollateral::FMSPCTCB(*tcb_type, fmspc.clone(), *version)
+                }
+                UpsertKind::EnclaveIdentity(id, version) => {
+                    MissingCollateral::QEIdentity(*id, *version)
+                }
+            })
+            .collect()
+    }
+}
+
+pub async fn diff(
+    network_a: NetworkProfile,
+    network_b: NetworkProfile,
+    fmspcs: &[String],
+) -> Result<NetworkDiff> {
+    PccsReader::new(network_a)?
+        .diff(&PccsReader::new(network_b)?, fmspcs)
+        .await
+}
+
+pub async fn sync(
+    from: NetworkProfile,
+    to: NetworkProfile,
+    fmspcs: &[String],
+    signer: PrivateKeySigner,
+) -> Result<Vec<(UpsertCall, TxHash)>> {
+    PccsReader::new(to)?
+        .sync_from(&PccsReader::new(from)?, fmspcs, signer)
+        .await
+}
+
+// What a network stores for a collateral, and its version when it has one
+struct Stored {
+    content: Vec<u8>,
+    version: Option<CollateralVersion>,
+}
+
+impl PccsReader {
+    /// Compares the CA certificates and CRLs, the QE, QVE and TDQE identities and the
+    /// TCB Info of the FMSPCs stored on this network (A) and on `other` (B).
+    pub async fn diff(&self, other: &PccsReader, fmspcs: &[String]) -> Result<NetworkDiff> {
+        let (a, b) = futures::join!(
+            self.stored_collaterals(fmspcs),
+            other.stored_collaterals(fmspcs)
+        );
+
+        let collaterals = a?
+            .into_iter()
+            .zip(b?)
+            .filter_map(|((collateral, a), (_, b))| {
+                let status = compare(a.as_ref(), b.as_ref())?;
+                Some(CollateralDiff {
+                    collateral,
+                    status,
+                    a: a.and_then(|stored| stored.version),
+                    b: b.and_then(|stored| stored.version),
+                })
+            })
+            .collect();
+
+        Ok(NetworkDiff {
+            network_a: self.network().name.clone(),
+            network_b: other.network().name.clone(),
+            collaterals,
+        })
+    }
+
+    /// The upserts that bring this network up to `from`, issuers first.
+    /// Certificates that differ on both sides are left alone.
+    pub async fn sync_calls(
+        &self,
+        from: &PccsReader,
+        fmspcs: &[String],
+    ) -> Result<Vec<UpsertCall>> {
+        let diff = from.diff(self, fmspcs).await?;
+        self.upsert_calls(&diff.missing_on(Side::B), from).await
+    }
+
+    /// Upserts on this network whatever is missing here or older than on `from`,
+    /// with the Intel signatures `from` stores.
+    pub async fn sync_from(
+        &self,
+        from: &PccsReader,
+        fmspcs: &[String],
+        signer: PrivateKeySigner,
+    ) -> Result<Vec<(UpsertCall, TxHash)>> {
+        let diff = from.diff(self, fmspcs).await?;
+        self.fill_missing(&diff.missing_on(Side::B), from, signer)
+            .await
+    }
+
+    // Always in the same order, so that both sides line up
+    async fn stored_collaterals(
+        &self,
+        fmspcs: &[String],
+    ) -> Result<Vec<(UpsertKind, Option<Stored>)>> {
+        let mut stored = Vec::new();
+
+        for ca in SNAPSHOT_CAS {
+            let (cert, crl) = self.get_certificate_by_id(ca).await?;
+            let cert = (!cert.is_empty()).then_some(Stored {
+                content: cert,
+                version: None,
+            });
+            stored.push((UpsertKind::PcsCertificate(ca), cert));
+            // the TCB Signing CA has no CRL
+            if ca != CA::SIGNING {
+                let crl = (!crl.is_empty()).then(|| Stored {
+                    version: CollateralVersion::from_crl(&crl).ok(),
+                    content: crl,
+                });
+                stored.push((UpsertKind::PcsCrl(ca), crl));
+            }
+        }
+
+        for (id, version) in SNAPSHOT_IDENTITIES {
+            let read = self.get_enclave_identity_obj(id, version).await;
+            let identity = stored_or_none(read)?.map(|obj| Stored {
+                version: CollateralVersion::from_json_body(&obj.identityStr).ok(),
+                content: obj.to_pcs_json().into_bytes(),
+            });
+            stored.push((UpsertKind::EnclaveIdentity(id, version), identity));
+        }
+
+        for fmspc in fmspcs {
+            let fmspc = fmspc.to_ascii_lowercase();
+            for (tcb_type, version) in SNAPSHOT_TCB_INFOS {
+                let read = self.get_tcb_info_obj(tcb_type, &fmspc, version).await;
+                let tcb_info = stored_or_none(read)?.map(|obj| Stored {
+                    version: CollateralVersion::from_json_body(&obj.tcbInfoStr).ok(),
+                    content: obj.to_pcs_json().into_bytes(),
+                });
+                stored.push((
+                    UpsertKind::FmspcTcb(tcb_type, fmspc.clone(), version),
+                    tcb_info,
+                ));
+            }
+        }
+
+        Ok(stored)
+    }
+}
+
+// None when the DAO does not store the collateral, a failed read fails the diff
+fn stored_or_none<T>(read: Result<T>) -> Result<Option<T>> {
+    match read {
+        Ok(stored) => Ok(Some(stored)),
+        Err(e) if is_not_stored(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// None when neither side stores the collateral
+fn compare(a: Option<&Stored>, b: Option<&Stored>) -> Option<DiffStatus> {
+    let status = match (a, b) {
+        (None, None) => return None,
+        (Some(_), None) => DiffStatus::OnlyOn(Side::A),
+        (None, Some(_)) => DiffStatus::OnlyOn(Side::B),
+        (Some(a), Some(b)) if a.content == b.content => DiffStatus::Identical,
+        (Some(a), Some(b)) => match (&a.version, &b.version) {
+            (Some(version_a), Some(version_b)) => match version_a.compare(version_b) {
+                Ordering::Greater => DiffStatus::Newer(Side::A),
+                Ordering::Less => DiffStatus::Newer(Side::B),
+                Ordering::Equal => DiffStatus::Conflicting,
+            },
+            _ => DiffStatus::Conflicting,
+        },
+    };
+    Some(status)
+}
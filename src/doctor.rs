@@ -0,0 +1,171 @@
+//! Checks that the reader talks to the chain its network profile describes: a wrong RPC URL
+//! otherwise shows up as every collateral being missing.
+
+use alloy::{
+    primitives::{keccak256, Address, B256},
+    providers::Provider,
+};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::PccsReader;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContractCheck {
+    pub name: &'static str,
+    pub address: Address,
+    // 0 when there is no contract at the address
+    pub code_size: usize,
+    pub code_hash: B256,
+    pub pinned_code_hash: Option<B256>,
+}
+
+impl ContractCheck {
+    pub fn has_code(&self) -> bool {
+        self.code_size > 0
+    }
+
+    /// None when no hash is pinned for the contract.
+    pub fn code_hash_matches(&self) -> Option<bool> {
+        self.pinned_code_hash.map(|pinned| pinned == self.code_hash)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SelfCheckReport {
+    pub network: String,
+    pub rpc_url: String,
+    pub expected_chain_id: u64,
+    // None when the node could not be reached, see `rpc_error`
+    pub chain_id: Option<u64>,
+    pub rpc_error: Option<String>,
+    pub contracts: Vec<ContractCheck>,
+}
+
+impl SelfCheckReport {
+    pub fn chain_id_matches(&self) -> bool {
+        self.chain_id == Some(self.expected_chain_id)
+    }
+
+    /// What is wrong, empty when the reader can be relied upon.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match (&self.rpc_error, self.chain_id) {
+            (Some(e), _) => problems.push(format!("{} is unreachable: {}", self.rpc_url, e)),
+            (None, Some(chain_id)) if chain_id != self.expected_chain_id => problems.push(format!(
+                "{} serves chain {}, the {} profile expects {}",
+                self.rpc_url, chain_id, self.network, self.expected_chain_id
+            )),
+            _ => {}
+        }
+        for contract in &self.contracts {
+            if !contract.has_code() {
+                problems.push(format!("{} has no code at {}", contract.name, contract.address));
+            } else if contract.code_hash_matches() == Some(false) {
+                problems.push(format!(
+                    "{} at {} has code hash {}, {} is pinned",
+                    contract.name,
+                    contract.address,
+                    contract.code_hash,
+                    contract.pinned_code_hash.unwrap_or_default()
+                ));
+            }
+        }
+        problems
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+pub async fn self_check() -> Result<SelfCheckReport> {
+    PccsReader::default().self_check().await
+}
+
+impl PccsReader {
+    /// Checks the chain ID of the node against the network profile, that each DAO has
+    /// code, and that the code hashes pinned in the profile match. An unreachable node
+    /// is reported rather than returned as an error.
+    pub async fn self_check(&self) -> Result<SelfCheckReport> {
+        let network = self.network();
+        let mut report = SelfCheckReport {
+            network: network.name.clone(),
+            rpc_url: network.rpc_url.clone(),
+            expected_chain_id: network.chain_id,
+            chain_id: None,
+            rpc_error: None,
+            contracts: Vec::new(),
+        };
+        match self.provider().get_chain_id().await {
+            Ok(chain_id) => report.chain_id = Some(chain_id),
+            Err(e) => {
+                report.rpc_error = Some(e.to_string());
+                return Ok(report);
+            }
+        }
+
+        let pinned = network.code_hashes.unwrap_or_default();
+        let contracts = [
+            ("ENCLAVE_ID_DAO", network.enclave_id_dao, pinned.enclave_id_dao),
+            ("FMSPC_TCB_DAO", network.fmspc_tcb_dao, pinned.fmspc_tcb_dao),
+            ("PCS_DAO", network.pcs_dao, pinned.pcs_dao),
+            ("PCK_DAO", network.pck_dao, pinned.pck_dao),
+            ("DCAP_ATTESTATION", network.dcap_attestation, None),
+            ("PCCS_ROUTER", network.pccs_router, None),
+        ];
+        for (name, address, pinned_code_hash) in contracts {
+            let code = self
+                .provider()
+                .get_code_at(address)
+                .block_id(self.block())
+                .await?;
+            report.contracts.push(ContractCheck {
+                name,
+                address,
+                code_size: code.len(),
+                code_hash: keccak256(&code),
+                pinned_code_hash,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn report(chain_id: u64, code_size: usize, pinned_code_hash: Option<B256>) -> SelfCheckReport {
+        SelfCheckReport {
+            network: "automata-testnet".to_string(),
+            rpc_url: "http://localhost:8545".to_string(),
+            expected_chain_id: 1398243,
+            chain_id: Some(chain_id),
+            rpc_error: None,
+            contracts: vec![ContractCheck {
+                name: "PCS_DAO",
+                address: Address::repeat_byte(0x11),
+                code_size,
+                code_hash: B256::repeat_byte(0xaa),
+                pinned_code_hash,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_problems() {
+        assert!(report(1398243, 100, None).is_healthy());
+        assert!(report(1398243, 100, Some(B256::repeat_byte(0xaa))).is_healthy());
+
+        let problems = report(1, 0, Some(B256::repeat_byte(0xbb))).problems();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("serves chain 1"));
+        // the hash of missing code is not compared
+        assert!(problems[1].starts_with("PCS_DAO has no code"));
+
+        let problems = report(1398243, 100, Some(B256::repeat_byte(0xbb))).problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("code hash"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Compares the collateral on chain with what a [`CollateralSource`] currently publishes,
+//! to find items that are present but outdated.
+
+use std::cmp::Ordering;
+
+use alloy::eips::BlockId;
+use anyhow::Result;
+use serde::Serialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use x509_parser::prelude::{CertificateRevocationList, FromDer};
+
+use crate::pccs::enclave_id::EnclaveIdType;
+use crate::pccs::{is_not_stored, NotStored};
+use crate::parser::try_get_pck_fmspc_and_issuer;
+use crate::quote::QuoteHeader;
+use crate::source::CollateralSource;
+use crate::{qe_identity_type, tcb_type_and_version, PccsReader, CA};
+
+/// A versioned collateral, i.e. one that Intel reissues over time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VersionedCollateral {
+    Crl(CA),
+    // TcbType, Fmspc, Version
+    TcbInfo(u8, String, u32),
+    // Id, Version
+    EnclaveIdentity(EnclaveIdType, u32),
+}
+
+impl std::fmt::Display for VersionedCollateral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionedCollateral::Crl(ca) => write!(f, "{:?} CA CRL", ca),
+            VersionedCollateral::TcbInfo(tcb_type, fmspc, version) => write!(
+                f,
+                "TCB info for fmspc {} (tcb type {}, version {})",
+                fmspc, tcb_type, version
+            ),
+            VersionedCollateral::EnclaveIdentity(id, version) => {
+                write!(f, "{:?} identity (version {})", id, version)
+            }
+        }
+    }
+}
+
+/// What orders two issues of the same collateral.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CollateralVersion {
+    // tcbEvaluationDataNumber, or the CRL number
+    pub number: Option<u64>,
+    // issueDate, or the CRL thisUpdate
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub issue_date: Option<OffsetDateTime>,
+}
+
+impl CollateralVersion {
+    /// Reads `tcbEvaluationDataNumber` and `issueDate` from a TCB Info or Enclave Identity body.
+    pub fn from_json_body(body: &str) -> Result<Self> {
+        let body: serde_json::Value = serde_json::from_str(body)?;
+        let issue_date = match body["issueDate"].as_str() {
+            Some(date) => Some(OffsetDateTime::parse(date, &Rfc3339)?),
+            None => None,
+        };
+        Ok(CollateralVersion {
+            number: body["tcbEvaluationDataNumber"].as_u64(),
+            issue_date,
+        })
+    }
+
+    /// Reads the CRL number and thisUpdate from a DER encoded CRL.
+    pub fn from_crl(der: &[u8]) -> Result<Self> {
+        let (_, crl) = CertificateRevocationList::from_der(der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse CRL: {}", e))?;
+        Ok(CollateralVersion {
+            number: crl.crl_number().and_then(|number| u64::try_from(number).ok()),
+            issue_date: Some(crl.last_update().to_datetime()),
+        })
+    }
+
+    /// Orders two issues of the same collateral: the number decides, the issue date breaks ties.
+    /// Whatever is unknown on either side is not compared.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        let by_number = match (self.number, other.number) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => Ordering::Equal,
+        };
+        let by_date = match (self.issue_date, other.issue_date) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => Ordering::Equal,
+        };
+        by_number.then(by_date)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Freshness {
+    UpToDate,
+    // the source has a newer issue
+    Behind,
+    // on chain is newer than the source, e.g. a stale mirror
+    Ahead,
+    MissingOnChain,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FreshnessReport {
+    #[serde(serialize_with = "serialize_display")]
+    pub collateral: VersionedCollateral,
+    pub onchain: Option<CollateralVersion>,
+    pub source: CollateralVersion,
+    pub freshness: Freshness,
+    // the block the on-chain version was read at
+    pub block: BlockId,
+}
+
+impl PccsReader {
+    /// Compares every collateral with its current issue in `source`.
+    /// Fails if the source cannot serve one of them, or if the chain cannot be read.
+    pub async fn compare_with_source<S: CollateralSource + ?Sized>(
+        &self,
+        collaterals: &[VersionedCollateral],
+        source: &S,
+    ) -> Result<Vec<FreshnessReport>> {
+        let reader = self.pin_block().await?;
+        let mut reports = Vec::with_capacity(collaterals.len());
+
+        for collateral in collaterals {
+            let source_version = source_version(collateral, source).await?;
+            // only what the DAO does not store counts as missing, a failed read is no answer
+            let onchain = match reader.onchain_version(collateral).await {
+                Ok(version) => Some(version),
+                Err(e) if is_not_stored(&e) => None,
+                Err(e) => return Err(e),
+            };
+
+            let freshness = match onchain.as_ref().map(|v| v.compare(&source_version)) {
+                None => Freshness::MissingOnChain,
+                Some(Ordering::Less) => Freshness::Behind,
+                Some(Ordering::Greater) => Freshness::Ahead,
+                Some(Ordering::Equal) => Freshness::UpToDate,
+            };
+
+            reports.push(FreshnessReport {
+                collateral: collateral.clone(),
+                onchain,
+                source: source_version,
+                freshness,
+                block: reader.block(),
+            });
+        }
+
+        Ok(reports)
+    }
+
+    /// Compares the CRLs, TCB Info and QE Identity needed to verify the quote.
+    pub async fn compare_quote_with_source<S: CollateralSource + ?Sized>(
+        &self,
+        raw_quote: &[u8],
+        source: &S,
+    ) -> Result<Vec<FreshnessReport>> {
+        let header = QuoteHeader::from_bytes(raw_quote)?;
+        let (fmspc, pck_ca) =
+            try_get_pck_fmspc_and_issuer(raw_quote, header.version, header.tee_type)?;
+        let (tcb_type, tcb_version) = tcb_type_and_version(header.tee_type, header.version);
+
+        let collaterals = [
+            VersionedCollateral::Crl(CA::ROOT),
+            VersionedCollateral::Crl(pck_ca),
+            VersionedCollateral::TcbInfo(tcb_type, fmspc, tcb_version),
+            VersionedCollateral::EnclaveIdentity(
+                qe_identity_type(header.tee_type),
+                header.version as u32,
+            ),
+        ];
+
+        self.compare_with_source(&collaterals, source).await
+    }
+
+    async fn onchain_version(&self, collateral: &VersionedCollateral) -> Result<CollateralVersion> {
+        match collateral {
+            VersionedCollateral::Crl(ca) => {
+                let (_, crl) = self.get_certificate_by_id(*ca).await?;
+                if crl.is_empty() {
+                    return Err(NotStored.into());
+                }
+                CollateralVersion::from_crl(&crl)
+            }
+            VersionedCollateral::TcbInfo(tcb_type, fmspc, version) => {
+                let tcb_info = self.get_tcb_info_obj(*tcb_type, fmspc, *version).await?;
+                CollateralVersion::from_json_body(&tcb_info.tcbInfoStr)
+            }
+            VersionedCollateral::EnclaveIdentity(id, version) => {
+                let identity = self.get_enclave_identity_obj(*id, *version).await?;
+                CollateralVersion::from_json_body(&identity.identityStr)
+            }
+        }
+    }
+}
+
+async fn source_version<S: CollateralSource + ?Sized>(
+    collateral: &VersionedCollateral,
+    source: &S,
+) -> Result<CollateralVersion> {
+    match collateral {
+        VersionedCollateral::Crl(ca) => {
+            let (_, crl) = source.certificate(*ca).await?;
+            CollateralVersion::from_crl(&crl)
+        }
+        VersionedCollateral::TcbInfo(tcb_type, fmspc, version) => {
+            let tcb_info = source.tcb_info(*tcb_type, fmspc, *version).await?;
+            CollateralVersion::from_json_body(&tcb_info.tcbInfoStr)
+        }
+        VersionedCollateral::EnclaveIdentity(id, version) => {
+            let identity = source.enclave_identity(*id, *version).await?;
+            CollateralVersion::from_json_body(&identity.identityStr)
+        }
+    }
+}
+
+pub(crate) fn serialize_display<T: std::fmt::Display, S: serde::Serializer>(
+    value: &T,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_ordering() {
+        let version = |number, date: &str| CollateralVersion {
+            number: Some(number),
+            issue_date: Some(OffsetDateTime::parse(date, &Rfc3339).unwrap()),
+        };
+
+        let parsed = CollateralVersion::from_json_body(
+            r#"{"id":"QE","version":2,"issueDate":"2024-05-20T12:00:00Z","tcbEvaluationDataNumber":16}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed, version(16, "2024-05-20T12:00:00Z"));
+
+        assert_eq!(
+            version(16, "2024-05-20T12:00:00Z").compare(&version(17, "2024-01-01T00:00:00Z")),
+            Ordering::Less
+        );
+        assert_eq!(
+            version(17, "2024-05-20T12:00:00Z").compare(&version(17, "2024-05-19T12:00:00Z")),
+            Ordering::Greater
+        );
+        let undated = CollateralVersion { number: Some(17), issue_date: None };
+        assert_eq!(undated.compare(&version(17, "2024-05-19T12:00:00Z")), Ordering::Equal);
+    }
+}
@@ -0,0 +1,272 @@
+//! "Would this quote verify if we uploaded this collateral?" answered without sending
+//! transactions: the candidate collateral is written into the DAO storage with `eth_call`
+//! state overrides, or upserted on a local anvil fork when the node does not take overrides.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    network::TransactionBuilder,
+    providers::{Provider, ProviderBuilder},
+    rpc::types::{
+        state::StateOverride,
+        BlockTransactionsKind,
+    },
+    sol_types::{SolCall, SolValue},
+    transports::RpcError,
+};
+use anyhow::Result;
+use serde::Serialize;
+use tokio::process::{Child, Command};
+
+use crate::attestation::OnchainVerification;
+use crate::pccs::enclave_id::IEnclaveIdentityDao;
+use crate::pccs::fmspc_tcb::IFmspcTcbDao;
+use crate::pccs::pcs::IPCSDao;
+use crate::proof::{bytes_storage, StorageLayout};
+use crate::writer::{UpsertCall, UpsertKind};
+use crate::{PccsReader, CA};
+
+// The JSON-RPC errors of nodes without state overrides: invalid params, from those that
+// take no third `eth_call` parameter, and method not found, from gateways that filter it
+const OVERRIDES_UNSUPPORTED: [i64; 2] = [-32602, -32601];
+
+/// How the hypothetical state was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HypotheticalState {
+    // the collateral was written straight into storage, the DAOs did not check it
+    StateOverrides,
+    // the upserts were sent to a fork, so the DAOs checked the collateral
+    AnvilFork,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HypotheticalVerification {
+    pub state: HypotheticalState,
+    // what was written, in the order of the calls
+    pub applied: Vec<String>,
+    // the missing collateral is looked up over the same state
+    pub verification: OnchainVerification,
+}
+
+pub async fn simulate_with_collateral<L: StorageLayout>(
+    raw_quote: &[u8],
+    calls: &[UpsertCall],
+    layout: &L,
+) -> Result<HypotheticalVerification> {
+    PccsReader::default()
+        .simulate_with_collateral(raw_quote, calls, layout)
+        .await
+}
+
+/// The storage writes that leave each upserted collateral where `layout` says it is stored.
+pub fn state_overrides<L: StorageLayout>(calls: &[UpsertCall], layout: &L) -> Result<StateOverride> {
+    let mut overrides = StateOverride::new();
+    for call in calls {
+        let (location, value) = match &call.kind {
+            UpsertKind::PcsCertificate(ca) => {
+                let call = IPCSDao::upsertPcsCertificatesCall::abi_decode(&call.input, true)?;
+                (layout.certificate(*ca), call.cert.to_vec())
+            }
+            UpsertKind::PcsCrl(CA::ROOT) => {
+                let call = IPCSDao::upsertRootCACrlCall::abi_decode(&call.input, true)?;
+                (layout.crl(CA::ROOT), call.rootcacrl.to_vec())
+            }
+            UpsertKind::PcsCrl(ca) => {
+                let call = IPCSDao::upsertPckCrlCall::abi_decode(&call.input, true)?;
+                (layout.crl(*ca), call.crl.to_vec())
+            }
+            UpsertKind::FmspcTcb(tcb_type, fmspc, version) => {
+                let call = IFmspcTcbDao::upsertFmspcTcbCall::abi_decode(&call.input, true)?;
+                let fmspc: [u8; 6] = hex::decode(fmspc)?
+                    .try_into()
+                    .map_err(|_| anyhow::Error::msg("FMSPC must be 6 bytes"))?;
+                (
+                    layout.tcb_info(*tcb_type, fmspc, *version),
+                    call.tcbInfoObj.abi_encode(),
+                )
+            }
+            UpsertKind::EnclaveIdentity(id, version) => {
+                let call =
+                    IEnclaveIdentityDao::upsertEnclaveIdentityCall::abi_decode(&call.input, true)?;
+                (
+                    layout.enclave_identity(*id, *version),
+                    call.enclaveIdentityObj.abi_encode(),
+                )
+            }
+        };
+
+        overrides
+            .entry(location.address)
+            .or_default()
+            .state_diff
+            .get_or_insert_with(Default::default)
+            .extend(bytes_storage(location.slot, &value));
+    }
+    Ok(overrides)
+}
+
+impl PccsReader {
+    /// Runs the missing collateral check and the on-chain verification as if `calls` had
+    /// been sent. The collateral is written over the current state with `eth_call` state
+    /// overrides, or upserted on an anvil fork when the node rejects overrides.
+    pub async fn simulate_with_collateral<L: StorageLayout>(
+        &self,
+        raw_quote: &[u8],
+        calls: &[UpsertCall],
+        layout: &L,
+    ) -> Result<HypotheticalVerification> {
+        let pinned = self.pin_block().await?;
+        let reader = pinned
+            .clone()
+            .with_state_overrides(state_overrides(calls, layout)?);
+        if !reader.takes_state_overrides().await? {
+            // forked at the block the overrides were tried at
+            return pinned.simulate_on_fork(raw_quote, calls).await;
+        }
+
+        Ok(HypotheticalVerification {
+            state: HypotheticalState::StateOverrides,
+            applied: calls.iter().map(|call| call.kind.to_string()).collect(),
+            verification: reader.simulate_onchain_verification(raw_quote).await?,
+        })
+    }
+
+    /// Same as [`PccsReader::simulate_with_collateral`], on a local anvil fork of the
+    /// network at the block of the reader. Needs `anvil` on the `PATH`.
+    pub async fn simulate_on_fork(
+        &self,
+        raw_quote: &[u8],
+        calls: &[UpsertCall],
+    ) -> Result<HypotheticalVerification> {
+        let fork = AnvilFork::spawn(&self.network().rpc_url, self.block_number().await?).await?;
+
+        let provider = ProviderBuilder::new().on_http(fork.url.parse()?);
+        let sender = *provider
+            .get_accounts()
+            .await?
+            .first()
+            .ok_or_else(|| anyhow::Error::msg("The fork has no unlocked account"))?;
+        for call in calls {
+            let receipt = provider
+                .send_transaction(call.to_request().with_from(sender))
+                .await?
+                .get_receipt()
+                .await?;
+            if !receipt.status() {
+                return Err(anyhow::anyhow!("The upsert of the {} reverted on the fork", call.kind));
+            }
+        }
+
+        let mut network = self.network().clone().with_rpc_url(&fork.url);
+        network.fallback_rpc_urls.clear();
+        Ok(HypotheticalVerification {
+            state: HypotheticalState::AnvilFork,
+            applied: calls.iter().map(|call| call.kind.to_string()).collect(),
+            verification: PccsReader::new(network)?
+                .simulate_onchain_verification(raw_quote)
+                .await?,
+        })
+    }
+
+    // Whether the node applies state overrides, those that do not reject the extra parameter.
+    // Any other error, a revert or a rate limit, is not a reason to fork.
+    async fn takes_state_overrides(&self) -> Result<bool> {
+        let pcs_dao_contract = IPCSDao::new(self.network().pcs_dao, self.provider());
+        let call_builder = pcs_dao_contract.getCertificateById(IPCSDao::CA::ROOT);
+        match self.prepare_call(call_builder).call_raw().await {
+            Ok(_) => Ok(true),
+            Err(alloy::contract::Error::TransportError(RpcError::ErrorResp(payload)))
+                if OVERRIDES_UNSUPPORTED.contains(&payload.code) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        match self.pin_block().await?.block() {
+            BlockId::Number(BlockNumberOrTag::Number(number)) => Ok(number),
+            block => self
+                .provider()
+                .get_block(block, BlockTransactionsKind::Hashes)
+                .await?
+                .and_then(|block| block.header.number)
+                .ok_or_else(|| anyhow::anyhow!("No block for {:?}", block)),
+        }
+    }
+}
+
+// A local anvil node forking the network, killed when dropped
+struct AnvilFork {
+    _node: Child,
+    url: String,
+}
+
+impl AnvilFork {
+    async fn spawn(fork_url: &str, block: u64) -> Result<Self> {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let node = Command::new("anvil")
+            .args(["--fork-url", fork_url])
+            .args(["--fork-block-number", &block.to_string()])
+            .args(["--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start anvil: {}", e))?;
+        let fork = AnvilFork {
+            _node: node,
+            url: format!("http://127.0.0.1:{}", port),
+        };
+
+        let provider = ProviderBuilder::new().on_http(fork.url.parse()?);
+        for _ in 0..100 {
+            if provider.get_chain_id().await.is_ok() {
+                return Ok(fork);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Err(anyhow::Error::msg("anvil did not start"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::NetworkProfile;
+    use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+    use crate::proof::DaoStorageLayout;
+    use alloy::primitives::{Address, B256, U256};
+
+    #[test]
+    fn test_state_overrides() {
+        let network = NetworkProfile::default();
+        let layout = DaoStorageLayout::new(Address::repeat_byte(0x44), U256::from(3));
+        let identity = EnclaveIdentityJsonObj {
+            identityStr: r#"{"id":"QE","version":2}"#.to_string(),
+            signature: vec![0xbb; 64].into(),
+        };
+        let calls = [
+            UpsertCall::pcs_crl(&network, CA::ROOT, vec![0x30, 0x01, 0x00]),
+            UpsertCall::enclave_identity(&network, EnclaveIdType::QE, 3, identity.clone()),
+        ];
+
+        let overrides = state_overrides(&calls, &layout).unwrap();
+        assert_eq!(overrides.len(), 1);
+        let state_diff = overrides[&layout.storage].state_diff.as_ref().unwrap();
+
+        // a short value, in its slot
+        let crl = state_diff[&layout.crl(CA::ROOT).slot];
+        assert_eq!(&crl[..3], &[0x30, 0x01, 0x00]);
+        assert_eq!(crl[31], 3 * 2);
+
+        // a long value: the length slot, then the data slots
+        let encoded = identity.abi_encode();
+        let head = layout.enclave_identity(EnclaveIdType::QE, 3).slot;
+        assert_eq!(state_diff[&head], B256::from(U256::from(encoded.len() * 2 + 1)));
+        assert_eq!(state_diff.len(), 2 + encoded.len().div_ceil(32));
+    }
+}
@@ -1,16 +1,43 @@
 pub mod pccs;
+pub mod attestation;
 pub mod constants;
 pub mod parser;
+pub mod proof;
+pub mod collateral;
+pub mod config;
+pub mod consistency;
+pub mod cost;
+pub mod batch;
+pub mod cache;
+pub mod diff;
+pub mod doctor;
+pub mod freshness;
+pub mod hypothetical;
+pub mod network;
+pub mod qpl;
+pub mod quote;
+pub mod reader;
+pub mod relayer;
+pub mod resolve;
+pub mod rpc;
+pub mod server;
+pub mod source;
+pub mod watch;
+pub mod writer;
 
+use alloy::eips::BlockId;
+use anyhow::Result;
 use constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
-use parser::get_pck_fmspc_and_issuer;
-use pccs::pcs::get_certificate_by_id;
-use pccs::enclave_id::{EnclaveIdType, get_enclave_identity};
-use pccs::fmspc_tcb::get_tcb_info;
+use parser::try_get_pck_fmspc_and_issuer;
+use pccs::enclave_id::EnclaveIdType;
+use pccs::multicall::QuoteCollateralReads;
+use source::CollateralSource;
 
+pub use network::NetworkProfile;
 pub use pccs::pcs::IPCSDao::CA;
+pub use reader::PccsReader;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MissingCollateral {
     None,
     // Id, Version
@@ -21,101 +48,252 @@ pub enum MissingCollateral {
     PCS(CA, bool, bool)
 }
 
-pub async fn find_missing_collaterals_from_quote(raw_quote: &[u8]) -> MissingCollateral {
-    // Step 0: read the version and tee type
-    let quote_version = u16::from_le_bytes([raw_quote[0], raw_quote[1]]);
-    let tee_type = u32::from_le_bytes([raw_quote[4], raw_quote[5], raw_quote[6], raw_quote[7]]);
-
-    if quote_version < 3 || quote_version > 4 {
-        panic!("Unsupported quote version");
+impl std::fmt::Display for MissingCollateral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingCollateral::None => write!(f, "no missing collateral"),
+            MissingCollateral::QEIdentity(id, version) => {
+                write!(f, "{:?} identity (version {}) is missing", id, version)
+            }
+            MissingCollateral::FMSPCTCB(tcb_type, fmspc, version) => write!(
+                f,
+                "TCB info for fmspc {} (tcb type {}, version {}) is missing",
+                fmspc, tcb_type, version
+            ),
+            MissingCollateral::PCS(ca, cert_missing, crl_missing) => {
+                let missing = match (cert_missing, crl_missing) {
+                    (true, true) => "certificate and CRL are",
+                    (true, false) => "certificate is",
+                    _ => "CRL is",
+                };
+                write!(f, "{:?} CA {} missing", ca, missing)
+            }
+        }
     }
+}
 
-    if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
-        panic!("Unsupported tee type");
+impl serde::Serialize for MissingCollateral {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = match self {
+            MissingCollateral::None => serde_json::Value::Null,
+            MissingCollateral::QEIdentity(id, version) => serde_json::json!({
+                "type": "QEIdentity",
+                "id": format!("{:?}", id),
+                "version": version,
+            }),
+            MissingCollateral::FMSPCTCB(tcb_type, fmspc, version) => serde_json::json!({
+                "type": "FMSPCTCB",
+                "tcb_type": tcb_type,
+                "fmspc": fmspc,
+                "version": version,
+            }),
+            MissingCollateral::PCS(ca, cert_missing, crl_missing) => serde_json::json!({
+                "type": "PCS",
+                "ca": format!("{:?}", ca),
+                "cert_missing": cert_missing,
+                "crl_missing": crl_missing,
+            }),
+        };
+        value.serialize(serializer)
     }
+}
+
+pub async fn find_missing_collaterals_from_quote(raw_quote: &[u8]) -> MissingCollateral {
+    PccsReader::default().find_missing_collaterals_from_quote(raw_quote).await
+}
+
+/// The outcome of checking a quote, with the block every DAO call was made at.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CollateralCheck {
+    pub block: BlockId,
+    pub missing: MissingCollateral,
+}
+
+pub async fn check_quote_collaterals(raw_quote: &[u8]) -> Result<CollateralCheck> {
+    PccsReader::default().check_quote_collaterals(raw_quote).await
+}
 
-    // Step 1: Check ROOT CRLs
-    match get_certificate_by_id(CA::ROOT).await {
-        Ok((root, crl)) => {
-            if root.len() == 0 {
-                return MissingCollateral::PCS(CA::ROOT, true, true);
-            } else if crl.len() == 0 {
-                return MissingCollateral::PCS(CA::ROOT, false, true);
+impl PccsReader {
+    pub async fn find_missing_collaterals_from_quote(&self, raw_quote: &[u8]) -> MissingCollateral {
+        let needs = QuoteNeeds::of_quote(raw_quote);
+        match self.pin_block().await {
+            Ok(reader) => {
+                let reads = reader.quote_collateral_reads(&needs).await;
+                needs.first_missing(reads)
             }
-        },
-        _ => {
-            return MissingCollateral::PCS(CA::ROOT, true, true); 
+            // the block could not be resolved, so nothing can be read
+            Err(_) => MissingCollateral::PCS(CA::ROOT, true, true),
         }
     }
 
-    // Step 2: Check QE Identity
-    let qe_id_type: EnclaveIdType;
-    if tee_type == TDX_TEE_TYPE {
-        qe_id_type = EnclaveIdType::TDQE
-    } else {
-        qe_id_type = EnclaveIdType::QE
+    /// Same as [`PccsReader::find_missing_collaterals_from_quote`], with every call
+    /// pinned to a single block so that the result reflects one state of the chain.
+    pub async fn check_quote_collaterals(&self, raw_quote: &[u8]) -> Result<CollateralCheck> {
+        let reader = self.pin_block().await?;
+        let missing = reader.missing_collaterals(raw_quote).await?;
+        Ok(CollateralCheck {
+            block: reader.block(),
+            missing,
+        })
     }
-    match get_enclave_identity(qe_id_type, quote_version as u32).await {
-        Ok(_) => {
-            // do nothing
-        },
-        _ => {
-            return MissingCollateral::QEIdentity(qe_id_type, quote_version as u32);
-        }
+
+    // Fails on a quote whose certification data cannot be parsed
+    async fn missing_collaterals(&self, raw_quote: &[u8]) -> Result<MissingCollateral> {
+        let needs = QuoteNeeds::try_of_quote(raw_quote)?;
+        let reads = self.quote_collateral_reads(&needs).await;
+        Ok(needs.first_missing(reads))
     }
 
-    // Step 3: get the fmspc value and the pck ca
-    let (fmspc, pck_type) = get_pck_fmspc_and_issuer(raw_quote, quote_version, tee_type);
+    // Every collateral the quote lacks, not only the first one
+    async fn all_missing_collaterals(&self, raw_quote: &[u8]) -> Result<Vec<MissingCollateral>> {
+        let needs = QuoteNeeds::try_of_quote(raw_quote)?;
+        let reads = self.quote_collateral_reads(&needs).await;
+        Ok(needs.all_missing(reads))
+    }
 
-    // Step 4: Check TCBInfo
-    let tcb_type: u8;
-    if tee_type == TDX_TEE_TYPE {
-        tcb_type = 1;
-    } else {
-        tcb_type = 0;
+    async fn quote_collateral_reads(&self, needs: &QuoteNeeds) -> QuoteCollateralReads {
+        self.read_quote_collateral(
+            needs.qe_id_type,
+            needs.qe_id_version,
+            (needs.tcb_type, needs.fmspc.as_str(), needs.tcb_version),
+            needs.pck_ca,
+        )
+        .await
     }
-    let tcb_version: u32;
-    if quote_version < 4 {
-        tcb_version = 2
-    } else {
-        tcb_version = 3
+}
+
+/// Same as [`find_missing_collaterals_from_quote`], reading the collateral from a source
+/// instead of the chain, e.g. a [`source::SnapshotCollateralSource`] with no network at all.
+pub async fn find_missing_collaterals_from_source<S: CollateralSource + ?Sized>(
+    source: &S,
+    raw_quote: &[u8],
+) -> MissingCollateral {
+    let needs = QuoteNeeds::of_quote(raw_quote);
+    let reads = QuoteCollateralReads::from_source(
+        source,
+        needs.qe_id_type,
+        needs.qe_id_version,
+        (needs.tcb_type, needs.fmspc.as_str(), needs.tcb_version),
+        needs.pck_ca,
+    )
+    .await;
+    needs.first_missing(reads)
+}
+
+// The collateral a quote is checked against
+struct QuoteNeeds {
+    qe_id_type: EnclaveIdType,
+    qe_id_version: u32,
+    tcb_type: u8,
+    fmspc: String,
+    tcb_version: u32,
+    pck_ca: CA,
+}
+
+impl QuoteNeeds {
+    // The entry points that return no Result panic on a malformed quote, as they always have
+    fn of_quote(raw_quote: &[u8]) -> Self {
+        QuoteNeeds::try_of_quote(raw_quote).unwrap_or_else(|e| panic!("{}", e))
     }
-    match get_tcb_info(tcb_type, fmspc.as_str(), tcb_version).await {
-        Ok(_) => {
-            // do nothing
-        },
-        _ => {
-            return MissingCollateral::FMSPCTCB(tcb_type, fmspc, tcb_version);
+
+    fn try_of_quote(raw_quote: &[u8]) -> Result<Self> {
+        // Step 0: read the version and tee type
+        let header = raw_quote
+            .get(..8)
+            .ok_or_else(|| anyhow::Error::msg("The quote is too short"))?;
+        let quote_version = u16::from_le_bytes([header[0], header[1]]);
+        let tee_type = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        if !(3..=4).contains(&quote_version) {
+            return Err(anyhow::Error::msg("Unsupported quote version"));
         }
-    }
 
-    // Step 5: Check TCB Signing CA is present
-    match get_certificate_by_id(CA::SIGNING).await {
-        Ok((root, _)) => {
-            if root.len() == 0 {
-                return MissingCollateral::PCS(CA::SIGNING, true, false);
-            }
-        },
-        _ => {
-            return MissingCollateral::PCS(CA::SIGNING, true, false); 
+        if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
+            return Err(anyhow::Error::msg("Unsupported tee type"));
         }
+
+        // Step 1: get the fmspc value and the pck ca, everything the quote needs is read at once
+        let (fmspc, pck_ca) = try_get_pck_fmspc_and_issuer(raw_quote, quote_version, tee_type)?;
+        let (tcb_type, tcb_version) = tcb_type_and_version(tee_type, quote_version);
+        Ok(QuoteNeeds {
+            qe_id_type: qe_identity_type(tee_type),
+            qe_id_version: quote_version as u32,
+            tcb_type,
+            fmspc,
+            tcb_version,
+            pck_ca,
+        })
     }
 
-    // Step 6: Check PCK CA CRLs
-    match get_certificate_by_id(pck_type).await {
-        Ok((cert, crl)) => {
-            if cert.len() == 0 {
-                return MissingCollateral::PCS(pck_type, true, true);
-            } else if crl.len() == 0 {
-                return MissingCollateral::PCS(pck_type, false, true);
-            }
-        },
-        _ => {
-            return MissingCollateral::PCS(pck_type, true, true);
+    fn first_missing(self, reads: QuoteCollateralReads) -> MissingCollateral {
+        self.all_missing(reads)
+            .into_iter()
+            .next()
+            .unwrap_or(MissingCollateral::None)
+    }
+
+    // Every missing collateral, in the order the checks are made
+    fn all_missing(self, reads: QuoteCollateralReads) -> Vec<MissingCollateral> {
+        let QuoteNeeds {
+            qe_id_type,
+            qe_id_version,
+            tcb_type,
+            fmspc,
+            tcb_version,
+            pck_ca: pck_type,
+        } = self;
+        let mut missing = Vec::new();
+
+        // Step 2: Check ROOT CRLs
+        match reads.root {
+            Ok((root, _)) if root.is_empty() => missing.push(MissingCollateral::PCS(CA::ROOT, true, true)),
+            Ok((_, crl)) if crl.is_empty() => missing.push(MissingCollateral::PCS(CA::ROOT, false, true)),
+            Ok(_) => {}
+            Err(_) => missing.push(MissingCollateral::PCS(CA::ROOT, true, true)),
+        }
+
+        // Step 3: Check QE Identity
+        if reads.qe_identity.is_err() {
+            missing.push(MissingCollateral::QEIdentity(qe_id_type, qe_id_version));
         }
+
+        // Step 4: Check TCBInfo
+        if reads.tcb_info.is_err() {
+            missing.push(MissingCollateral::FMSPCTCB(tcb_type, fmspc, tcb_version));
+        }
+
+        // Step 5: Check TCB Signing CA is present
+        match reads.signing {
+            Ok((signing, _)) if !signing.is_empty() => {}
+            _ => missing.push(MissingCollateral::PCS(CA::SIGNING, true, false)),
+        }
+
+        // Step 6: Check PCK CA CRLs
+        match reads.pck {
+            Ok((cert, _)) if cert.is_empty() => missing.push(MissingCollateral::PCS(pck_type, true, true)),
+            Ok((_, crl)) if crl.is_empty() => missing.push(MissingCollateral::PCS(pck_type, false, true)),
+            Ok(_) => {}
+            Err(_) => missing.push(MissingCollateral::PCS(pck_type, true, true)),
+        }
+
+        missing
     }
+}
+
+// The QE Identity that signs quotes for the given tee type
+pub(crate) fn qe_identity_type(tee_type: u32) -> EnclaveIdType {
+    if tee_type == TDX_TEE_TYPE {
+        EnclaveIdType::TDQE
+    } else {
+        EnclaveIdType::QE
+    }
+}
 
-    MissingCollateral::None
+// TcbType, TcbInfo version
+pub(crate) fn tcb_type_and_version(tee_type: u32, quote_version: u16) -> (u8, u32) {
+    let tcb_type = if tee_type == TDX_TEE_TYPE { 1 } else { 0 };
+    let tcb_version = if quote_version < 4 { 2 } else { 3 };
+    (tcb_type, tcb_version)
 }
 
 #[cfg(test)]
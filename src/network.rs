@@ -0,0 +1,220 @@
+use anyhow::Result;
+
+use alloy::primitives::{Address, B256, U256};
+use serde::Deserialize;
+
+use crate::constants::*;
+use crate::proof::DaoStorageLayout;
+use crate::rpc::RpcPolicy;
+
+/// The chain and the PCCS DAO deployment a reader talks to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkProfile {
+    pub name: String,
+    pub chain_id: u64,
+    pub rpc_url: String,
+    // tried in order when `rpc_url` fails, or queried together for a quorum
+    pub fallback_rpc_urls: Vec<String>,
+    pub rpc: RpcPolicy,
+    pub enclave_id_dao: Address,
+    pub fmspc_tcb_dao: Address,
+    pub pcs_dao: Address,
+    pub pck_dao: Address,
+    // Multicall3, used to batch the DAO reads of a check
+    pub multicall: Address,
+    // the entrypoint of on-chain quote verification
+    pub dcap_attestation: Address,
+    // points to the current DAOs, see `PccsReader::resolve_addresses`
+    pub pccs_router: Address,
+    // None where the helper addresses are not published
+    pub helpers: Option<HelperAddresses>,
+    // keccak256 of the DAO runtime code, compared by `PccsReader::self_check`
+    pub code_hashes: Option<CodeHashes>,
+    // where PCCS_Storage keeps the collateral, for `PccsReader::verified`;
+    // None where the storage contract is not published
+    pub storage: Option<DaoStorageLayout>,
+}
+
+/// Pinned keccak256 hashes of the runtime code of the DAOs, each one optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CodeHashes {
+    pub enclave_id_dao: Option<B256>,
+    pub fmspc_tcb_dao: Option<B256>,
+    pub pcs_dao: Option<B256>,
+    pub pck_dao: Option<B256>,
+}
+
+/// The parser contracts behind the DAOs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct HelperAddresses {
+    pub enclave_identity: Address,
+    pub fmspc_tcb: Address,
+    // PCK_HELPER, or X509_HELPER
+    pub x509: Address,
+    pub x509_crl: Address,
+}
+
+impl NetworkProfile {
+    pub const NAMES: [&'static str; 4] = [
+        "automata-testnet",
+        "automata-mainnet",
+        "holesky",
+        "sepolia",
+    ];
+
+    pub fn automata_testnet() -> Self {
+        Self::from_constants(
+            "automata-testnet",
+            AUTOMATA_TESTNET_CHAIN_ID,
+            DEFAULT_RPC_URL,
+            [
+                ENCLAVE_ID_DAO_ADDRESS,
+                FMSPC_TCB_DAO_ADDRESS,
+                PCS_DAO_ADDRESS,
+                PCK_DAO_ADDRESS,
+            ],
+            DCAP_ATTESTATION_ADDRESS,
+            PCCS_ROUTER_ADDRESS,
+            Some(PCCS_STORAGE_ADDRESS),
+            Some([
+                ENCLAVE_IDENTITY_HELPER_ADDRESS,
+                FMSPC_TCB_HELPER_ADDRESS,
+                PCK_HELPER_ADDRESS,
+                CRL_HELPER_ADDRESS,
+            ]),
+        )
+    }
+
+    pub fn automata_mainnet() -> Self {
+        Self::from_constants(
+            "automata-mainnet",
+            AUTOMATA_MAINNET_CHAIN_ID,
+            AUTOMATA_MAINNET_RPC_URL,
+            [
+                AUTOMATA_MAINNET_ENCLAVE_ID_DAO_ADDRESS,
+                AUTOMATA_MAINNET_FMSPC_TCB_DAO_ADDRESS,
+                AUTOMATA_MAINNET_PCS_DAO_ADDRESS,
+                AUTOMATA_MAINNET_PCK_DAO_ADDRESS,
+            ],
+            AUTOMATA_MAINNET_DCAP_ATTESTATION_ADDRESS,
+            AUTOMATA_MAINNET_PCCS_ROUTER_ADDRESS,
+            Some(AUTOMATA_MAINNET_PCCS_STORAGE_ADDRESS),
+            Some([
+                AUTOMATA_MAINNET_ENCLAVE_IDENTITY_HELPER_ADDRESS,
+                AUTOMATA_MAINNET_FMSPC_TCB_HELPER_ADDRESS,
+                AUTOMATA_MAINNET_X509_HELPER_ADDRESS,
+                AUTOMATA_MAINNET_X509_CRL_HELPER_ADDRESS,
+            ]),
+        )
+    }
+
+    pub fn holesky() -> Self {
+        Self::from_constants(
+            "holesky",
+            HOLESKY_CHAIN_ID,
+            HOLESKY_RPC_URL,
+            [
+                HOLESKY_ENCLAVE_ID_DAO_ADDRESS,
+                HOLESKY_FMSPC_TCB_DAO_ADDRESS,
+                HOLESKY_PCS_DAO_ADDRESS,
+                HOLESKY_PCK_DAO_ADDRESS,
+            ],
+            HOLESKY_DCAP_ATTESTATION_ADDRESS,
+            HOLESKY_PCCS_ROUTER_ADDRESS,
+            None,
+            None,
+        )
+    }
+
+    pub fn sepolia() -> Self {
+        Self::from_constants(
+            "sepolia",
+            SEPOLIA_CHAIN_ID,
+            SEPOLIA_RPC_URL,
+            [
+                SEPOLIA_ENCLAVE_ID_DAO_ADDRESS,
+                SEPOLIA_FMSPC_TCB_DAO_ADDRESS,
+                SEPOLIA_PCS_DAO_ADDRESS,
+                SEPOLIA_PCK_DAO_ADDRESS,
+            ],
+            SEPOLIA_DCAP_ATTESTATION_ADDRESS,
+            SEPOLIA_PCCS_ROUTER_ADDRESS,
+            None,
+            None,
+        )
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "automata-testnet" => Ok(Self::automata_testnet()),
+            "automata-mainnet" => Ok(Self::automata_mainnet()),
+            "holesky" => Ok(Self::holesky()),
+            "sepolia" => Ok(Self::sepolia()),
+            _ => Err(anyhow::anyhow!(
+                "Unknown network {}, expected one of {}",
+                name,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn with_rpc_url(mut self, rpc_url: &str) -> Self {
+        self.rpc_url = rpc_url.to_string();
+        self
+    }
+
+    // [ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO, PCK_DAO]
+    // and [ENCLAVE_IDENTITY_HELPER, FMSPC_TCB_HELPER, X509_HELPER, X509_CRL_HELPER]
+    #[allow(clippy::too_many_arguments)]
+    fn from_constants(
+        name: &str,
+        chain_id: u64,
+        rpc_url: &str,
+        daos: [&str; 4],
+        dcap_attestation: &str,
+        pccs_router: &str,
+        storage: Option<&str>,
+        helpers: Option<[&str; 4]>,
+    ) -> Self {
+        let address = |hex_address: &str| {
+            Address::from_slice(&hex::decode(hex_address).expect("Invalid address hex"))
+        };
+        let [enclave_id_dao, fmspc_tcb_dao, pcs_dao, pck_dao] = daos.map(address);
+        let helpers = helpers.map(|helpers| {
+            let [enclave_identity, fmspc_tcb, x509, x509_crl] = helpers.map(address);
+            HelperAddresses {
+                enclave_identity,
+                fmspc_tcb,
+                x509,
+                x509_crl,
+            }
+        });
+
+        NetworkProfile {
+            name: name.to_string(),
+            chain_id,
+            rpc_url: rpc_url.to_string(),
+            fallback_rpc_urls: Vec::new(),
+            rpc: RpcPolicy::default(),
+            enclave_id_dao,
+            fmspc_tcb_dao,
+            pcs_dao,
+            pck_dao,
+            multicall: address(MULTICALL3_ADDRESS),
+            dcap_attestation: address(dcap_attestation),
+            pccs_router: address(pccs_router),
+            helpers,
+            code_hashes: None,
+            storage: storage.map(|storage| {
+                DaoStorageLayout::new(address(storage), U256::from(PCCS_STORAGE_SLOT))
+            }),
+        }
+    }
+}
+
+impl Default for NetworkProfile {
+    fn default() -> Self {
+        Self::automata_testnet()
+    }
+}
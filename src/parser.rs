@@ -4,6 +4,7 @@ use x509_parser::oid_registry::asn1_rs::{
 
 use super::pccs::pcs::IPCSDao::CA;
 use super::constants::SGX_TEE_TYPE;
+use anyhow::Result;
 use x509_parser::prelude::*;
 
 // 48 + 384 + 4 + 64 + 64 + 384 + 64
@@ -14,92 +15,134 @@ const V4_SGX_QE_AUTH_DATA_SIZE_OFFSET: usize = 1018;
 const V4_TDX_QE_AUTH_DATA_SIZE_OFFSET: usize = 1218;
 
 pub fn get_pck_fmspc_and_issuer(quote: &[u8], version: u16, tee_type: u32) -> (String, CA) {
-    
-    let offset: usize;
-    if version < 4 {
-        offset = V3_SGX_QE_AUTH_DATA_SIZE_OFFSET;
+    try_get_pck_fmspc_and_issuer(quote, version, tee_type).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as [`get_pck_fmspc_and_issuer`], with an error instead of a panic when the
+/// certification data of the quote is malformed.
+pub fn try_get_pck_fmspc_and_issuer(
+    quote: &[u8],
+    version: u16,
+    tee_type: u32,
+) -> Result<(String, CA)> {
+    let offset = if version < 4 {
+        V3_SGX_QE_AUTH_DATA_SIZE_OFFSET
+    } else if tee_type == SGX_TEE_TYPE {
+        V4_SGX_QE_AUTH_DATA_SIZE_OFFSET
     } else {
-        if tee_type == SGX_TEE_TYPE {
-            offset = V4_SGX_QE_AUTH_DATA_SIZE_OFFSET;
-        } else {
-            offset = V4_TDX_QE_AUTH_DATA_SIZE_OFFSET;
-        }
-    }
-    
-    let cert_data_offset = get_cert_data_offset(quote, offset);
-    let cert_data: Vec<u8> = (quote[cert_data_offset..]).to_vec();
+        V4_TDX_QE_AUTH_DATA_SIZE_OFFSET
+    };
+
+    let cert_data_offset = get_cert_data_offset(quote, offset)?;
+    let cert_data = &quote[cert_data_offset..];
 
-    let pem = parse_pem(&cert_data).expect("Failed to parse cert data");
-    let cert_chain = parse_certchain(&pem);
-    let pck = &cert_chain[0];
+    let pem = parse_pem(cert_data)
+        .map_err(|e| anyhow::anyhow!("Failed to parse cert data: {}", e))?;
+    let cert_chain = parse_certchain(&pem)?;
+    let pck = cert_chain
+        .first()
+        .ok_or_else(|| anyhow::Error::msg("The quote has no PCK certificate"))?;
 
-    let pck_issuer = get_x509_issuer_cn(pck);
+    let pck_issuer = get_x509_issuer_cn(pck)?;
 
     let pck_ca = match pck_issuer.as_str() {
         "Intel SGX PCK Platform CA" => CA::PLATFORM,
         "Intel SGX PCK Processor CA" => CA::PROCESSOR,
-        _ => panic!("Unknown PCK Issuer"),
+        _ => return Err(anyhow::Error::msg("Unknown PCK Issuer")),
     };
 
-    let fmspc_slice = extract_fmspc_from_extension(pck);
+    let fmspc_slice = extract_fmspc_from_extension(pck)?;
     let fmspc = hex::encode(fmspc_slice);
 
-    (fmspc, pck_ca)
+    Ok((fmspc, pck_ca))
 }
 
-fn get_cert_data_offset(quote: &[u8], offset: usize) -> usize {
-    let auth_data_size = u16::from_le_bytes([
-        quote[offset],
-        quote[offset + 1],
-    ]);
+fn get_cert_data_offset(quote: &[u8], offset: usize) -> Result<usize> {
+    let size_bytes = quote
+        .get(offset..offset + 2)
+        .ok_or_else(|| anyhow::Error::msg("The quote is too short"))?;
+    let auth_data_size = u16::from_le_bytes([size_bytes[0], size_bytes[1]]);
 
-    offset + 2 + auth_data_size as usize + 2 + 4
+    let cert_data_offset = offset + 2 + auth_data_size as usize + 2 + 4;
+    if cert_data_offset > quote.len() {
+        return Err(anyhow::Error::msg("The quote is too short"));
+    }
+    Ok(cert_data_offset)
 }
 
 fn parse_pem(raw_bytes: &[u8]) -> Result<Vec<Pem>, PEMError> {
     Pem::iter_from_buffer(raw_bytes).collect()
 }
 
-fn parse_certchain<'a>(pem_certs: &'a [Pem]) -> Vec<X509Certificate<'a>> {
+fn parse_certchain<'a>(pem_certs: &'a [Pem]) -> Result<Vec<X509Certificate<'a>>> {
     pem_certs
         .iter()
-        .map(|pem| pem.parse_x509().unwrap())
+        .map(|pem| {
+            pem.parse_x509()
+                .map_err(|e| anyhow::anyhow!("Failed to parse the PCK certificate chain: {}", e))
+        })
         .collect()
 }
 
-fn get_x509_issuer_cn(cert: &X509Certificate) -> String {
+fn get_x509_issuer_cn(cert: &X509Certificate) -> Result<String> {
     let issuer = cert.issuer();
-    let cn = issuer.iter_common_name().next().unwrap();
-    cn.as_str().unwrap().to_string()
+    let cn = issuer
+        .iter_common_name()
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("The PCK issuer has no common name"))?;
+    Ok(cn.as_str()?.to_string())
 }
 
-fn extract_fmspc_from_extension<'a>(cert: &'a X509Certificate<'a>) -> [u8; 6] {
+fn extract_fmspc_from_extension<'a>(cert: &'a X509Certificate<'a>) -> Result<[u8; 6]> {
+    let malformed = |_| anyhow::Error::msg("Malformed SGX extension");
     let sgx_extensions_bytes = cert
-        .get_extension_unique(&oid!(1.2.840 .113741 .1 .13 .1))
-        .unwrap()
-        .unwrap()
+        .get_extension_unique(&oid!(1.2.840 .113741 .1 .13 .1))?
+        .ok_or_else(|| anyhow::Error::msg("The PCK certificate has no SGX extension"))?
         .value;
 
-    let (_, sgx_extensions) = Sequence::from_der(sgx_extensions_bytes).unwrap();
-
-    let mut fmspc = [0; 6];
+    let (_, sgx_extensions) = Sequence::from_der(sgx_extensions_bytes).map_err(malformed)?;
 
     let mut i = sgx_extensions.content.as_ref();
 
-    while i.len() > 0 {
-        let (j, current_sequence) = Sequence::from_der(i).unwrap();
+    while !i.is_empty() {
+        let (j, current_sequence) = Sequence::from_der(i).map_err(malformed)?;
         i = j;
-        let (j, current_oid) = Oid::from_der(current_sequence.content.as_ref()).unwrap();
-        match current_oid.to_id_string().as_str() {
-            "1.2.840.113741.1.13.1.4" => {
-                let (k, fmspc_bytes) = OctetString::from_der(j).unwrap();
-                assert_eq!(k.len(), 0);
-                fmspc.copy_from_slice(fmspc_bytes.as_ref());
-                break;
+        let (j, current_oid) =
+            Oid::from_der(current_sequence.content.as_ref()).map_err(malformed)?;
+        if current_oid.to_id_string() == "1.2.840.113741.1.13.1.4" {
+            let (k, fmspc_bytes) = OctetString::from_der(j).map_err(malformed)?;
+            if !k.is_empty() {
+                return Err(anyhow::Error::msg("Malformed SGX extension"));
             }
-            _ => continue,
+            return fmspc_bytes
+                .as_ref()
+                .try_into()
+                .map_err(|_| anyhow::Error::msg("The FMSPC must be 6 bytes"));
         }
     }
 
-    fmspc
+    Err(anyhow::Error::msg("The SGX extension has no FMSPC"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn quote(name: &str) -> Vec<u8> {
+        let path = format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name);
+        hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap()
+    }
+
+    #[test]
+    fn test_malformed_cert_data() {
+        let mut raw_quote = quote("v3_sgx_quote.hex");
+        let (fmspc, pck_ca) = try_get_pck_fmspc_and_issuer(&raw_quote, 3, SGX_TEE_TYPE).unwrap();
+        assert_eq!((fmspc.len(), pck_ca), (12, CA::PLATFORM));
+
+        // a well-formed header whose certificate chain is cut short
+        raw_quote.truncate(raw_quote.len() - 600);
+        assert!(try_get_pck_fmspc_and_issuer(&raw_quote, 3, SGX_TEE_TYPE).is_err());
+        raw_quote.truncate(1000);
+        assert!(try_get_pck_fmspc_and_issuer(&raw_quote, 3, SGX_TEE_TYPE).is_err());
+    }
 }
@@ -1,17 +1,14 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, ENCLAVE_ID_DAO_ADDRESS};
+use crate::pccs::NotStored;
+use crate::reader::PccsReader;
 
-use alloy::{
-    primitives::{Address, U256},
-    providers::ProviderBuilder,
-    sol,
-};
+use alloy::{primitives::U256, sol};
 
 sol! {
     #[sol(rpc)]
     interface IEnclaveIdentityDao {
-        #[derive(Debug)]
+        #[derive(Debug, PartialEq, Eq)]
         struct EnclaveIdentityJsonObj {
             string identityStr;
             bytes signature;
@@ -19,63 +16,129 @@ sol! {
 
         #[derive(Debug)]
         function getEnclaveIdentity(uint256 id, uint256 version) returns (EnclaveIdentityJsonObj memory enclaveIdObj);
+
+        #[derive(Debug)]
+        function upsertEnclaveIdentity(uint256 id, uint256 version, EnclaveIdentityJsonObj calldata enclaveIdentityObj) external returns (bytes32 attestationId);
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+pub use IEnclaveIdentityDao::EnclaveIdentityJsonObj;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum EnclaveIdType {
     QE,
     QVE,
     TDQE,
 }
 
+impl EnclaveIdType {
+    // The id the DAO stores the identity under
+    pub fn to_uint256(self) -> U256 {
+        match self {
+            EnclaveIdType::QE => U256::from(0),
+            EnclaveIdType::QVE => U256::from(1),
+            EnclaveIdType::TDQE => U256::from(2),
+        }
+    }
+
+    pub fn from_uint256(id: U256) -> Option<Self> {
+        [EnclaveIdType::QE, EnclaveIdType::QVE, EnclaveIdType::TDQE]
+            .into_iter()
+            .find(|candidate| candidate.to_uint256() == id)
+    }
+}
+
 pub async fn get_enclave_identity(id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let enclave_id_dao_address_slice =
-        hex::decode(ENCLAVE_ID_DAO_ADDRESS).expect("Invalid address hex");
-
-    let enclave_id_dao_contract = IEnclaveIdentityDao::new(
-        Address::from_slice(&enclave_id_dao_address_slice),
-        &provider,
-    );
-
-    let enclave_id_type_uint256;
-    match id {
-        EnclaveIdType::QE => enclave_id_type_uint256 = U256::from(0),
-        EnclaveIdType::QVE => enclave_id_type_uint256 = U256::from(1),
-        EnclaveIdType::TDQE => enclave_id_type_uint256 = U256::from(2),
+    PccsReader::default().get_enclave_identity(id, version).await
+}
+
+pub async fn get_enclave_identity_obj(
+    id: EnclaveIdType,
+    version: u32,
+) -> Result<EnclaveIdentityJsonObj> {
+    PccsReader::default().get_enclave_identity_obj(id, version).await
+}
+
+impl PccsReader {
+    pub async fn get_enclave_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        let enclave_id_obj = self.get_enclave_identity_obj(id, version).await?;
+
+        Ok(enclave_id_obj.to_pcs_json().into_bytes())
     }
 
-    let call_builder =
-        enclave_id_dao_contract.getEnclaveIdentity(enclave_id_type_uint256, U256::from(version));
+    /// Returns the Enclave Identity body and its signature exactly as stored in the DAO,
+    /// without wrapping them into the Intel PCS JSON response.
+    pub async fn get_enclave_identity_obj(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        if let Some(cached) = self.cached_enclave_identity(id, version) {
+            return Ok(cached);
+        }
+        let enclave_id_obj = self.read_enclave_identity_obj(id, version).await?;
+        self.cache_enclave_identity(id, version, &enclave_id_obj);
+
+        Ok(enclave_id_obj)
+    }
 
-    let call_return = call_builder.call().await?;
+    // Reads the DAO, bypassing the cache
+    pub(crate) async fn read_enclave_identity_obj(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        let enclave_id_dao_contract =
+            IEnclaveIdentityDao::new(self.network().enclave_id_dao, self.provider());
 
-    let identity_str = call_return.enclaveIdObj.identityStr;
-    let signature_bytes = call_return.enclaveIdObj.signature;
+        let call_builder =
+            enclave_id_dao_contract.getEnclaveIdentity(id.to_uint256(), U256::from(version));
+
+        let call_return = self.prepare_call(call_builder).call().await?;
+        let enclave_id_obj = call_return.enclaveIdObj;
+
+        enclave_id_obj.into_stored()
+    }
+}
 
-    if identity_str.len() == 0 || signature_bytes.len() == 0 {
-        return Err(anyhow::Error::msg("missing"));
+impl EnclaveIdentityJsonObj {
+    // The DAO returns an empty object for what it does not store
+    pub(crate) fn into_stored(self) -> Result<Self> {
+        if self.identityStr.is_empty() || self.signature.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok(self)
     }
 
-    let signature = signature_bytes.to_string();
+    /// Formats the object the way the Intel PCS API serves it,
+    /// i.e. `{"enclaveIdentity": {...}, "signature": "<hex>"}`.
+    pub fn to_pcs_json(&self) -> String {
+        let signature = self.signature.to_string();
+        format!(
+            "{{\"enclaveIdentity\": {}, \"signature\": \"{}\"}}",
+            self.identityStr,
+            remove_prefix_if_found(signature.as_str())
+        )
+    }
 
-    let ret_str = format!(
-        "{{\"enclaveIdentity\": {}, \"signature\": \"{}\"}}",
-        identity_str,
-        remove_prefix_if_found(signature.as_str())
-    );
+    /// Parses an Intel PCS formatted response back into the object stored in the DAO.
+    /// The body is kept byte-for-byte, since the signature covers its exact encoding.
+    pub fn from_pcs_json(json: &str) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct PcsJson<'a> {
+            #[serde(rename = "enclaveIdentity", borrow)]
+            body: &'a serde_json::value::RawValue,
+            signature: String,
+        }
 
-    let ret = ret_str.into_bytes();
-    Ok(ret)
+        let parsed: PcsJson = serde_json::from_str(json)?;
+        Ok(Self {
+            identityStr: parsed.body.get().to_string(),
+            signature: hex::decode(remove_prefix_if_found(&parsed.signature))?.into(),
+        })
+    }
 }
 
 fn remove_prefix_if_found(h: &str) -> &str {
-    if h.starts_with("0x") {
-        &h[2..]
-    } else {
-        &h
-    }
-}
\ No newline at end of file
+    h.strip_prefix("0x").unwrap_or(h)
+}
@@ -0,0 +1,317 @@
+//! Upsert events of the DAOs, and a local index of them built by scanning logs
+//! over block ranges. The index records the last block it scanned, so that
+//! a scan saved to a checkpoint file resumes where it stopped.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::ca_index;
+use crate::pccs::enclave_id::EnclaveIdType;
+use crate::reader::PccsReader;
+use crate::CA;
+
+use alloy::{
+    primitives::{Address, B256},
+    providers::Provider,
+    rpc::types::{Filter, Log},
+    sol,
+    sol_types::SolEventInterface,
+};
+
+sol! {
+    interface IPCSDaoEvents {
+        event UpsertedPcsCertificate(uint8 indexed ca, bytes32 attestationId);
+        event UpsertedPcsCrl(uint8 indexed ca, bytes32 attestationId);
+    }
+
+    interface IFmspcTcbDaoEvents {
+        event UpsertedFmspcTcb(uint8 indexed tcbType, bytes6 indexed fmspc, uint32 indexed version, bytes32 attestationId);
+    }
+
+    interface IEnclaveIdentityDaoEvents {
+        event UpsertedEnclaveIdentity(uint256 indexed id, uint256 indexed version, bytes32 attestationId);
+    }
+
+    interface IPckDaoEvents {
+        event UpsertedPckCert(bytes16 indexed qeid, bytes2 indexed pceid, bytes18 indexed tcbm, bytes32 attestationId);
+    }
+}
+
+use IEnclaveIdentityDaoEvents::IEnclaveIdentityDaoEventsEvents;
+use IFmspcTcbDaoEvents::IFmspcTcbDaoEventsEvents;
+use IPCSDaoEvents::IPCSDaoEventsEvents;
+use IPckDaoEvents::IPckDaoEventsEvents;
+
+pub const DEFAULT_CHUNK_SIZE: u64 = 10_000;
+
+/// A collateral item the DAOs emit upsert events for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CollateralItem {
+    PcsCertificate(#[serde(with = "ca_index")] CA),
+    PcsCrl(#[serde(with = "ca_index")] CA),
+    TcbInfo {
+        tcb_type: u8,
+        fmspc: String,
+        version: u32,
+    },
+    EnclaveIdentity {
+        id: EnclaveIdType,
+        version: u32,
+    },
+    // hex encoded, as in the PCK certificate requests
+    PckCertificate {
+        qeid: String,
+        pceid: String,
+        tcbm: String,
+    },
+}
+
+/// An upsert of a collateral item, and where it happened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollateralEvent {
+    pub item: CollateralItem,
+    pub dao: Address,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub transaction_hash: B256,
+    pub log_index: u64,
+}
+
+impl CollateralEvent {
+    /// Decodes an upsert log of one of the DAOs, `None` for any other log.
+    pub fn from_log(log: &Log) -> Option<Self> {
+        Some(CollateralEvent {
+            item: decode_item(log)?,
+            dao: log.address(),
+            block_number: log.block_number?,
+            block_hash: log.block_hash?,
+            transaction_hash: log.transaction_hash?,
+            log_index: log.log_index?,
+        })
+    }
+}
+
+fn decode_item(log: &Log) -> Option<CollateralItem> {
+    let (topics, data) = (log.topics(), &log.data().data);
+
+    if let Ok(event) = IPCSDaoEventsEvents::decode_raw_log(topics, data, true) {
+        return match event {
+            IPCSDaoEventsEvents::UpsertedPcsCertificate(event) => {
+                Some(CollateralItem::PcsCertificate(CA::try_from(event.ca).ok()?))
+            }
+            IPCSDaoEventsEvents::UpsertedPcsCrl(event) => {
+                Some(CollateralItem::PcsCrl(CA::try_from(event.ca).ok()?))
+            }
+        };
+    }
+    if let Ok(IFmspcTcbDaoEventsEvents::UpsertedFmspcTcb(event)) =
+        IFmspcTcbDaoEventsEvents::decode_raw_log(topics, data, true)
+    {
+        return Some(CollateralItem::TcbInfo {
+            tcb_type: event.tcbType,
+            fmspc: hex::encode(event.fmspc),
+            version: event.version,
+        });
+    }
+    if let Ok(IEnclaveIdentityDaoEventsEvents::UpsertedEnclaveIdentity(event)) =
+        IEnclaveIdentityDaoEventsEvents::decode_raw_log(topics, data, true)
+    {
+        return Some(CollateralItem::EnclaveIdentity {
+            id: EnclaveIdType::from_uint256(event.id)?,
+            version: event.version.try_into().ok()?,
+        });
+    }
+    if let Ok(IPckDaoEventsEvents::UpsertedPckCert(event)) =
+        IPckDaoEventsEvents::decode_raw_log(topics, data, true)
+    {
+        return Some(CollateralItem::PckCertificate {
+            qeid: hex::encode(event.qeid),
+            pceid: hex::encode(event.pceid),
+            tcbm: hex::encode(event.tcbm),
+        });
+    }
+    None
+}
+
+/// Every upsert seen from `start_block` up to the checkpoint, in chain order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventIndex {
+    pub start_block: u64,
+    // the last block scanned, None before the first scan
+    pub checkpoint: Option<u64>,
+    pub events: Vec<CollateralEvent>,
+}
+
+impl EventIndex {
+    pub fn new(start_block: u64) -> Self {
+        EventIndex {
+            start_block,
+            ..Default::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    // Written aside then renamed, so that a crash never leaves a truncated file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// The first block the next scan covers.
+    pub fn next_block(&self) -> u64 {
+        self.checkpoint.map_or(self.start_block, |checkpoint| checkpoint + 1)
+    }
+
+    /// Every upsert of an item, the first one being its insertion.
+    pub fn history(&self, item: &CollateralItem) -> Vec<&CollateralEvent> {
+        self.events.iter().filter(|event| &event.item == item).collect()
+    }
+
+    /// Every item upserted at least once, in the order they were first inserted.
+    pub fn items(&self) -> Vec<&CollateralItem> {
+        let mut seen = HashSet::new();
+        self.events
+            .iter()
+            .map(|event| &event.item)
+            .filter(|item| seen.insert(*item))
+            .collect()
+    }
+
+    /// Every FMSPC with a TCB Info on chain, of any type and version.
+    pub fn fmspcs_with_tcb_info(&self) -> BTreeSet<String> {
+        self.events
+            .iter()
+            .filter_map(|event| match &event.item {
+                CollateralItem::TcbInfo { fmspc, .. } => Some(fmspc.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every upsert of a TCB Info for the FMSPC, of any type and version.
+    pub fn tcb_info_history(&self, fmspc: &str) -> Vec<&CollateralEvent> {
+        self.events
+            .iter()
+            .filter(|event| {
+                matches!(&event.item, CollateralItem::TcbInfo { fmspc: f, .. } if f.eq_ignore_ascii_case(fmspc))
+            })
+            .collect()
+    }
+}
+
+/// Scans the logs of the DAOs of a network into an [`EventIndex`],
+/// one `eth_getLogs` per chunk of blocks.
+#[derive(Debug, Clone)]
+pub struct EventIndexer {
+    reader: PccsReader,
+    chunk_size: u64,
+    // the index is saved there after every chunk
+    checkpoint: Option<PathBuf>,
+}
+
+impl EventIndexer {
+    pub fn new(reader: PccsReader) -> Self {
+        EventIndexer {
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            checkpoint: None,
+        }
+    }
+
+    pub fn with_chunk_size(self, chunk_size: u64) -> Self {
+        EventIndexer {
+            chunk_size: chunk_size.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_checkpoint(self, path: impl Into<PathBuf>) -> Self {
+        EventIndexer {
+            checkpoint: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// The index saved at the checkpoint, or a new one from `start_block`.
+    pub fn load_index(&self, start_block: u64) -> Result<EventIndex> {
+        match &self.checkpoint {
+            Some(path) if path.exists() => EventIndex::load(path),
+            _ => Ok(EventIndex::new(start_block)),
+        }
+    }
+
+    pub fn reader(&self) -> &PccsReader {
+        &self.reader
+    }
+
+    pub fn daos(&self) -> Vec<Address> {
+        let network = self.reader.network();
+        vec![
+            network.pcs_dao,
+            network.fmspc_tcb_dao,
+            network.enclave_id_dao,
+            network.pck_dao,
+        ]
+    }
+
+    /// The upserts of the given blocks, in chain order.
+    pub async fn events(&self, from_block: u64, to_block: u64) -> Result<Vec<CollateralEvent>> {
+        let mut events = Vec::new();
+        let mut chunk_start = from_block;
+        while chunk_start <= to_block {
+            let chunk_end = to_block.min(chunk_start.saturating_add(self.chunk_size - 1));
+            let filter = Filter::new()
+                .address(self.daos())
+                .from_block(chunk_start)
+                .to_block(chunk_end);
+            let logs = self.reader.provider().get_logs(&filter).await?;
+
+            events.extend(
+                logs.iter()
+                    .filter(|log| !log.removed)
+                    .filter_map(CollateralEvent::from_log),
+            );
+            chunk_start = chunk_end.saturating_add(1);
+            if chunk_end == u64::MAX {
+                break;
+            }
+        }
+        events.sort_by_key(|event| (event.block_number, event.log_index));
+        Ok(events)
+    }
+
+    /// Scans from the checkpoint of the index up to `to_block`, returns the number of new events.
+    pub async fn index_until(&self, index: &mut EventIndex, to_block: u64) -> Result<usize> {
+        let mut found = 0;
+        while index.next_block() <= to_block {
+            let from_block = index.next_block();
+            let chunk_end = to_block.min(from_block.saturating_add(self.chunk_size - 1));
+
+            let events = self.events(from_block, chunk_end).await?;
+            found += events.len();
+            index.events.extend(events);
+            index.checkpoint = Some(chunk_end);
+
+            if let Some(path) = &self.checkpoint {
+                index.save(path)?;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Scans up to the latest block.
+    pub async fn index_to_latest(&self, index: &mut EventIndex) -> Result<usize> {
+        let latest = self.reader.provider().get_block_number().await?;
+        self.index_until(index, latest).await
+    }
+}
@@ -1,17 +1,14 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, FMSPC_TCB_DAO_ADDRESS};
+use crate::pccs::NotStored;
+use crate::reader::PccsReader;
 
-use alloy::{
-    primitives::{Address, U256},
-    providers::ProviderBuilder,
-    sol,
-};
+use alloy::{primitives::U256, sol};
 
 sol! {
     #[sol(rpc)]
     interface IFmspcTcbDao {
-        #[derive(Debug)]
+        #[derive(Debug, PartialEq, Eq)]
         struct TcbInfoJsonObj {
             string tcbInfoStr;
             bytes signature;
@@ -19,48 +16,107 @@ sol! {
 
         #[derive(Debug)]
         function getTcbInfo(uint256 tcbType, string calldata fmspc, uint256 version) returns (TcbInfoJsonObj memory tcbObj);
+
+        #[derive(Debug)]
+        function upsertFmspcTcb(TcbInfoJsonObj calldata tcbInfoObj) external returns (bytes32 attestationId);
     }
 }
 
+pub use IFmspcTcbDao::TcbInfoJsonObj;
+
 pub async fn get_tcb_info(tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let fmspc_tcb_dao_address_slice =
-        hex::decode(FMSPC_TCB_DAO_ADDRESS).expect("Invalid address hex");
-    let fmspc_tcb_dao_contract =
-        IFmspcTcbDao::new(Address::from_slice(&fmspc_tcb_dao_address_slice), &provider);
-
-    let call_builder = fmspc_tcb_dao_contract.getTcbInfo(
-        U256::from(tcb_type),
-        String::from(fmspc),
-        U256::from(version),
-    );
-
-    let call_return = call_builder.call().await?;
-    let tcb_info_str = call_return.tcbObj.tcbInfoStr;
-    let signature_bytes = call_return.tcbObj.signature;
-
-    if tcb_info_str.len() == 0 || signature_bytes.len() == 0 {
-        return Err(anyhow::Error::msg("missing"));
+    PccsReader::default().get_tcb_info(tcb_type, fmspc, version).await
+}
+
+pub async fn get_tcb_info_obj(tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+    PccsReader::default().get_tcb_info_obj(tcb_type, fmspc, version).await
+}
+
+impl PccsReader {
+    pub async fn get_tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        let tcb_obj = self.get_tcb_info_obj(tcb_type, fmspc, version).await?;
+
+        Ok(tcb_obj.to_pcs_json().into_bytes())
     }
 
-    let signature = signature_bytes.to_string();
+    /// Returns the TCB Info body and its signature exactly as stored in the DAO,
+    /// without wrapping them into the Intel PCS JSON response.
+    pub async fn get_tcb_info_obj(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+    ) -> Result<TcbInfoJsonObj> {
+        if let Some(cached) = self.cached_tcb_info(tcb_type, fmspc, version) {
+            return Ok(cached);
+        }
+        let tcb_obj = self.read_tcb_info_obj(tcb_type, fmspc, version).await?;
+        self.cache_tcb_info(tcb_type, fmspc, version, &tcb_obj);
+
+        Ok(tcb_obj)
+    }
+
+    // Reads the DAO, bypassing the cache
+    pub(crate) async fn read_tcb_info_obj(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+    ) -> Result<TcbInfoJsonObj> {
+        let fmspc_tcb_dao_contract =
+            IFmspcTcbDao::new(self.network().fmspc_tcb_dao, self.provider());
+
+        let call_builder = fmspc_tcb_dao_contract.getTcbInfo(
+            U256::from(tcb_type),
+            String::from(fmspc),
+            U256::from(version),
+        );
 
-    let ret_str = format!(
-        "{{\"tcbInfo\": {}, \"signature\": \"{}\"}}",
-        tcb_info_str,
-        remove_prefix_if_found(signature.as_str())
-    );
+        let call_return = self.prepare_call(call_builder).call().await?;
+        let tcb_obj = call_return.tcbObj;
 
-    let ret = ret_str.into_bytes();
-    Ok(ret)
+        tcb_obj.into_stored()
+    }
 }
 
-fn remove_prefix_if_found(h: &str) -> &str {
-    if h.starts_with("0x") {
-        &h[2..]
-    } else {
-        &h
+impl TcbInfoJsonObj {
+    // The DAO returns an empty object for what it does not store
+    pub(crate) fn into_stored(self) -> Result<Self> {
+        if self.tcbInfoStr.is_empty() || self.signature.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok(self)
     }
-}
\ No newline at end of file
+
+    /// Formats the object the way the Intel PCS API serves it,
+    /// i.e. `{"tcbInfo": {...}, "signature": "<hex>"}`.
+    pub fn to_pcs_json(&self) -> String {
+        let signature = self.signature.to_string();
+        format!(
+            "{{\"tcbInfo\": {}, \"signature\": \"{}\"}}",
+            self.tcbInfoStr,
+            remove_prefix_if_found(signature.as_str())
+        )
+    }
+
+    /// Parses an Intel PCS formatted response back into the object stored in the DAO.
+    /// The body is kept byte-for-byte, since the signature covers its exact encoding.
+    pub fn from_pcs_json(json: &str) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct PcsJson<'a> {
+            #[serde(rename = "tcbInfo", borrow)]
+            body: &'a serde_json::value::RawValue,
+            signature: String,
+        }
+
+        let parsed: PcsJson = serde_json::from_str(json)?;
+        Ok(Self {
+            tcbInfoStr: parsed.body.get().to_string(),
+            signature: hex::decode(remove_prefix_if_found(&parsed.signature))?.into(),
+        })
+    }
+}
+
+fn remove_prefix_if_found(h: &str) -> &str {
+    h.strip_prefix("0x").unwrap_or(h)
+}
@@ -0,0 +1,423 @@
+//! The parser contracts the DAOs run collateral through before storing it, and the
+//! collateral as they see it. [`crate::consistency`] parses the same collateral off chain.
+
+use alloy::{primitives::U256, sol};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::attestation::TcbStatus;
+use crate::network::HelperAddresses;
+use crate::reader::PccsReader;
+
+sol! {
+    #[sol(rpc)]
+    interface IEnclaveIdentityHelper {
+        #[derive(Debug)]
+        enum EnclaveId {
+            QE,
+            QVE,
+            TD_QE
+        }
+
+        #[derive(Debug)]
+        enum EnclaveIdTcbStatus {
+            SGX_ENCLAVE_REPORT_ISVSVN_NOT_SUPPORTED,
+            OK,
+            SGX_ENCLAVE_REPORT_ISVSVN_REVOKED,
+            SGX_ENCLAVE_REPORT_ISVSVN_OUT_OF_DATE
+        }
+
+        #[derive(Debug)]
+        struct Tcb {
+            uint16 isvsvn;
+            uint256 dateTimestamp;
+            EnclaveIdTcbStatus status;
+        }
+
+        #[derive(Debug)]
+        struct IdentityObj {
+            EnclaveId id;
+            uint32 version;
+            uint64 issueDateTimestamp;
+            uint64 nextUpdateTimestamp;
+            uint32 tcbEvaluationDataNumber;
+            bytes4 miscselect;
+            bytes4 miscselectMask;
+            bytes16 attributes;
+            bytes16 attributesMask;
+            bytes32 mrsigner;
+            uint16 isvprodid;
+            Tcb[] tcb;
+        }
+
+        #[derive(Debug)]
+        function parseIdentityString(string calldata identityStr) external pure returns (IdentityObj memory identity);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IFmspcTcbHelper {
+        #[derive(Debug)]
+        enum TcbId {
+            SGX,
+            TDX
+        }
+
+        #[derive(Debug)]
+        enum TCBStatus {
+            OK,
+            TCB_SW_HARDENING_NEEDED,
+            TCB_CONFIGURATION_AND_SW_HARDENING_NEEDED,
+            TCB_CONFIGURATION_NEEDED,
+            TCB_OUT_OF_DATE,
+            TCB_OUT_OF_DATE_CONFIGURATION_NEEDED,
+            TCB_REVOKED,
+            TCB_UNRECOGNIZED
+        }
+
+        #[derive(Debug)]
+        struct TcbInfoBasic {
+            uint8 tcbType;
+            TcbId id;
+            uint32 version;
+            uint64 issueDate;
+            uint64 nextUpdate;
+            uint32 evaluationDataNumber;
+            bytes6 fmspc;
+            bytes2 pceid;
+        }
+
+        #[derive(Debug)]
+        struct TCBLevelsObj {
+            uint16 pcesvn;
+            uint8[] sgxComponentCpuSvns;
+            uint8[] tdxSvns;
+            uint64 tcbDateTimestamp;
+            TCBStatus status;
+            string[] advisoryIDs;
+        }
+
+        #[derive(Debug)]
+        function parseTcbString(string calldata tcbInfoStr) external pure returns (TcbInfoBasic memory tcbInfo, string memory tcbLevelsString, string memory tdxModuleString, string memory tdxModuleIdentitiesString);
+
+        #[derive(Debug)]
+        function parseTcbLevels(uint256 tcbInfoVersion, string calldata tcbLevelsString) external pure returns (TCBLevelsObj[] memory tcbLevels);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IX509Helper {
+        #[derive(Debug)]
+        struct X509CertObj {
+            uint256 serialNumber;
+            string issuerCommonName;
+            uint256 validityNotBefore;
+            uint256 validityNotAfter;
+            string subjectCommonName;
+            bytes subjectPublicKey;
+            uint256 extensionPtr;
+            bytes authorityKeyIdentifier;
+            bytes subjectKeyIdentifier;
+            bytes signature;
+            bytes tbs;
+        }
+
+        #[derive(Debug)]
+        function parseX509DER(bytes calldata der) external pure returns (X509CertObj memory cert);
+
+        #[derive(Debug)]
+        function parsePckExtension(bytes memory der, uint256 extensionPtr) external pure returns (uint16 pcesvn, uint8[] memory cpusvns, bytes memory fmspcBytes, bytes memory pceidBytes);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IX509CRLHelper {
+        #[derive(Debug)]
+        struct X509CRLObj {
+            uint256 serialNumber;
+            string issuerCommonName;
+            uint256 validityNotBefore;
+            uint256 validityNotAfter;
+            uint256[] serialNumbersRevoked;
+            bytes authorityKeyIdentifier;
+            bytes signature;
+            bytes tbs;
+        }
+
+        #[derive(Debug)]
+        function parseCRLDER(bytes calldata der) external pure returns (X509CRLObj memory crl);
+    }
+}
+
+// Subject of the PCK certificates, the only ones with the SGX extension
+pub const PCK_CERT_SUBJECT: &str = "Intel SGX PCK Certificate";
+
+/// The TCB status of an Enclave Identity TCB level, in the order of `EnclaveIdTcbStatus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EnclaveTcbStatus {
+    NotSupported,
+    UpToDate,
+    Revoked,
+    OutOfDate,
+}
+
+impl EnclaveTcbStatus {
+    pub fn from_u8(status: u8) -> Result<Self> {
+        Ok(match status {
+            0 => EnclaveTcbStatus::NotSupported,
+            1 => EnclaveTcbStatus::UpToDate,
+            2 => EnclaveTcbStatus::Revoked,
+            3 => EnclaveTcbStatus::OutOfDate,
+            _ => return Err(anyhow::anyhow!("Unknown enclave TCB status {}", status)),
+        })
+    }
+}
+
+/// An Enclave Identity body, timestamps in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedIdentity {
+    // 0 for QE, 1 for QVE, 2 for TD_QE
+    pub id: u8,
+    pub version: u32,
+    pub issue_date: u64,
+    pub next_update: u64,
+    pub tcb_evaluation_data_number: u32,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub miscselect: [u8; 4],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub miscselect_mask: [u8; 4],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub attributes: [u8; 16],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub attributes_mask: [u8; 16],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mrsigner: [u8; 32],
+    pub isvprodid: u16,
+    pub tcb_levels: Vec<IdentityTcbLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IdentityTcbLevel {
+    pub isvsvn: u16,
+    pub tcb_date: u64,
+    pub status: EnclaveTcbStatus,
+}
+
+/// A TCB Info body, timestamps in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedTcbInfo {
+    pub tcb_type: u8,
+    // 0 for SGX, 1 for TDX
+    pub id: u8,
+    pub version: u32,
+    pub issue_date: u64,
+    pub next_update: u64,
+    pub tcb_evaluation_data_number: u32,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub fmspc: [u8; 6],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub pceid: [u8; 2],
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TcbLevel {
+    pub pcesvn: u16,
+    pub sgx_cpu_svns: Vec<u8>,
+    // empty for SGX
+    pub tdx_svns: Vec<u8>,
+    pub tcb_date: u64,
+    pub status: TcbStatus,
+    pub advisory_ids: Vec<String>,
+}
+
+/// An X.509 certificate, validity in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedCertificate {
+    pub serial_number: U256,
+    pub issuer_common_name: String,
+    pub subject_common_name: String,
+    pub not_before: u64,
+    pub not_after: u64,
+    // the uncompressed point, without its 0x04 prefix
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub subject_public_key: Vec<u8>,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub authority_key_identifier: Vec<u8>,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub subject_key_identifier: Vec<u8>,
+    // only for PCK certificates
+    pub pck_extension: Option<PckExtension>,
+}
+
+/// The TCB, FMSPC and PCE ID of the SGX extension of a PCK certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PckExtension {
+    pub pcesvn: u16,
+    pub cpu_svns: Vec<u8>,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub fmspc: Vec<u8>,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub pceid: Vec<u8>,
+}
+
+/// An X.509 CRL, timestamps in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedCrl {
+    // the CRL number
+    pub serial_number: U256,
+    pub issuer_common_name: String,
+    pub this_update: u64,
+    pub next_update: u64,
+    pub revoked_serial_numbers: Vec<U256>,
+}
+
+impl TryFrom<IEnclaveIdentityHelper::IdentityObj> for ParsedIdentity {
+    type Error = anyhow::Error;
+
+    fn try_from(identity: IEnclaveIdentityHelper::IdentityObj) -> Result<Self> {
+        let tcb_levels = identity
+            .tcb
+            .into_iter()
+            .map(|level| {
+                Ok(IdentityTcbLevel {
+                    isvsvn: level.isvsvn,
+                    tcb_date: level.dateTimestamp.saturating_to(),
+                    status: EnclaveTcbStatus::from_u8(level.status as u8)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(ParsedIdentity {
+            id: identity.id as u8,
+            version: identity.version,
+            issue_date: identity.issueDateTimestamp,
+            next_update: identity.nextUpdateTimestamp,
+            tcb_evaluation_data_number: identity.tcbEvaluationDataNumber,
+            miscselect: identity.miscselect.0,
+            miscselect_mask: identity.miscselectMask.0,
+            attributes: identity.attributes.0,
+            attributes_mask: identity.attributesMask.0,
+            mrsigner: identity.mrsigner.0,
+            isvprodid: identity.isvprodid,
+            tcb_levels,
+        })
+    }
+}
+
+impl TryFrom<IFmspcTcbHelper::TCBLevelsObj> for TcbLevel {
+    type Error = anyhow::Error;
+
+    fn try_from(level: IFmspcTcbHelper::TCBLevelsObj) -> Result<Self> {
+        Ok(TcbLevel {
+            pcesvn: level.pcesvn,
+            sgx_cpu_svns: level.sgxComponentCpuSvns,
+            tdx_svns: level.tdxSvns,
+            tcb_date: level.tcbDateTimestamp,
+            status: TcbStatus::try_from(level.status as u8)?,
+            advisory_ids: level.advisoryIDs,
+        })
+    }
+}
+
+impl From<IX509CRLHelper::X509CRLObj> for ParsedCrl {
+    fn from(crl: IX509CRLHelper::X509CRLObj) -> Self {
+        ParsedCrl {
+            serial_number: crl.serialNumber,
+            issuer_common_name: crl.issuerCommonName,
+            this_update: crl.validityNotBefore.saturating_to(),
+            next_update: crl.validityNotAfter.saturating_to(),
+            revoked_serial_numbers: crl.serialNumbersRevoked,
+        }
+    }
+}
+
+impl PccsReader {
+    pub fn helpers(&self) -> Result<HelperAddresses> {
+        self.network().helpers.ok_or_else(|| {
+            anyhow::anyhow!("No helper contracts known for {}", self.network().name)
+        })
+    }
+
+    /// Parses an Enclave Identity body with `parseIdentityString`.
+    pub async fn parse_identity_onchain(&self, identity_str: &str) -> Result<ParsedIdentity> {
+        let helper = IEnclaveIdentityHelper::new(self.helpers()?.enclave_identity, self.provider());
+        let call_builder = helper.parseIdentityString(identity_str.to_string());
+        let call_return = self.prepare_call(call_builder).call().await?;
+        ParsedIdentity::try_from(call_return.identity)
+    }
+
+    /// Parses a TCB Info body with `parseTcbString`, then its levels with `parseTcbLevels`.
+    pub async fn parse_tcb_info_onchain(&self, tcb_info_str: &str) -> Result<ParsedTcbInfo> {
+        let helper = IFmspcTcbHelper::new(self.helpers()?.fmspc_tcb, self.provider());
+        let call_builder = helper.parseTcbString(tcb_info_str.to_string());
+        let call_return = self.prepare_call(call_builder).call().await?;
+        let basic = call_return.tcbInfo;
+
+        let call_builder =
+            helper.parseTcbLevels(U256::from(basic.version), call_return.tcbLevelsString);
+        let tcb_levels = self
+            .prepare_call(call_builder)
+            .call()
+            .await?
+            .tcbLevels
+            .into_iter()
+            .map(TcbLevel::try_from)
+            .collect::<Result<_>>()?;
+
+        Ok(ParsedTcbInfo {
+            tcb_type: basic.tcbType,
+            id: basic.id as u8,
+            version: basic.version,
+            issue_date: basic.issueDate,
+            next_update: basic.nextUpdate,
+            tcb_evaluation_data_number: basic.evaluationDataNumber,
+            fmspc: basic.fmspc.0,
+            pceid: basic.pceid.0,
+            tcb_levels,
+        })
+    }
+
+    /// Parses a DER certificate with `parseX509DER`, and its SGX extension with
+    /// `parsePckExtension` when it is a PCK certificate.
+    pub async fn parse_certificate_onchain(&self, der: &[u8]) -> Result<ParsedCertificate> {
+        let helper = IX509Helper::new(self.helpers()?.x509, self.provider());
+        let call_builder = helper.parseX509DER(der.to_vec().into());
+        let cert = self.prepare_call(call_builder).call().await?.cert;
+
+        let pck_extension = match cert.subjectCommonName == PCK_CERT_SUBJECT {
+            true => {
+                let call_builder = helper.parsePckExtension(der.to_vec().into(), cert.extensionPtr);
+                let extension = self.prepare_call(call_builder).call().await?;
+                Some(PckExtension {
+                    pcesvn: extension.pcesvn,
+                    cpu_svns: extension.cpusvns,
+                    fmspc: extension.fmspcBytes.to_vec(),
+                    pceid: extension.pceidBytes.to_vec(),
+                })
+            }
+            false => None,
+        };
+
+        Ok(ParsedCertificate {
+            serial_number: cert.serialNumber,
+            issuer_common_name: cert.issuerCommonName,
+            subject_common_name: cert.subjectCommonName,
+            not_before: cert.validityNotBefore.saturating_to(),
+            not_after: cert.validityNotAfter.saturating_to(),
+            subject_public_key: cert.subjectPublicKey.to_vec(),
+            authority_key_identifier: cert.authorityKeyIdentifier.to_vec(),
+            subject_key_identifier: cert.subjectKeyIdentifier.to_vec(),
+            pck_extension,
+        })
+    }
+
+    /// Parses a DER CRL with `parseCRLDER`.
+    pub async fn parse_crl_onchain(&self, der: &[u8]) -> Result<ParsedCrl> {
+        let helper = IX509CRLHelper::new(self.helpers()?.x509_crl, self.provider());
+        let call_builder = helper.parseCRLDER(der.to_vec().into());
+        Ok(self.prepare_call(call_builder).call().await?.crl.into())
+    }
+}
@@ -1,3 +1,23 @@
 pub mod pcs;
 pub mod enclave_id;
-pub mod fmspc_tcb;
\ No newline at end of file
+pub mod fmspc_tcb;
+pub mod helpers;
+pub mod multicall;
+pub mod events;
+
+/// The error of a DAO read that found nothing stored, as opposed to a read that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotStored;
+
+impl std::fmt::Display for NotStored {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "missing")
+    }
+}
+
+impl std::error::Error for NotStored {}
+
+/// Whether `e` only says that the collateral is not stored.
+pub fn is_not_stored(e: &anyhow::Error) -> bool {
+    e.is::<NotStored>()
+}
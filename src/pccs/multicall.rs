@@ -0,0 +1,237 @@
+use anyhow::Result;
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj, IEnclaveIdentityDao};
+use crate::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use crate::pccs::pcs::IPCSDao;
+use crate::reader::PccsReader;
+use crate::CA;
+
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+    sol_types::SolCall,
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        #[derive(Debug)]
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        #[derive(Debug)]
+        struct Call3Result {
+            bool success;
+            bytes returnData;
+        }
+
+        #[derive(Debug)]
+        function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+    }
+}
+
+pub use IMulticall3::{Call3, Call3Result};
+
+/// Every DAO read needed to check a quote, each of which may fail on its own.
+#[derive(Debug)]
+pub(crate) struct QuoteCollateralReads {
+    pub root: Result<(Vec<u8>, Vec<u8>)>,
+    pub qe_identity: Result<EnclaveIdentityJsonObj>,
+    pub tcb_info: Result<TcbInfoJsonObj>,
+    pub signing: Result<(Vec<u8>, Vec<u8>)>,
+    pub pck: Result<(Vec<u8>, Vec<u8>)>,
+}
+
+impl PccsReader {
+    /// Makes every call in a single Multicall3 `aggregate3`, allowing each one to fail.
+    /// Errors when the batch as a whole fails, e.g. when Multicall3 is not deployed.
+    pub async fn aggregate3(&self, calls: Vec<Call3>) -> Result<Vec<Call3Result>> {
+        let count = calls.len();
+        let multicall = IMulticall3::new(self.network().multicall, self.provider());
+
+        let call_builder = multicall.aggregate3(calls);
+
+        // without code at the address the call succeeds with no data, which fails to decode
+        let call_return = self.prepare_call(call_builder).call().await?;
+        if call_return.returnData.len() != count {
+            return Err(anyhow::anyhow!(
+                "Multicall3 returned {} results for {} calls",
+                call_return.returnData.len(),
+                count
+            ));
+        }
+
+        Ok(call_return.returnData)
+    }
+
+    /// Reads the collateral of a quote from the cache, then what is not cached
+    /// in one Multicall3 batch, or one call at a time when the batch cannot be made.
+    pub(crate) async fn read_quote_collateral(
+        &self,
+        qe_id_type: EnclaveIdType,
+        qe_id_version: u32,
+        tcb: (u8, &str, u32),
+        pck_ca: CA,
+    ) -> QuoteCollateralReads {
+        let (tcb_type, fmspc, tcb_version) = tcb;
+        let cached = (
+            self.cached_certificate(CA::ROOT),
+            self.cached_enclave_identity(qe_id_type, qe_id_version),
+            self.cached_tcb_info(tcb_type, fmspc, tcb_version),
+            self.cached_certificate(CA::SIGNING),
+            self.cached_certificate(pck_ca),
+        );
+        let cached = match cached {
+            (Some(root), Some(qe_identity), Some(tcb_info), Some(signing), Some(pck)) => {
+                return QuoteCollateralReads {
+                    root: Ok(root),
+                    qe_identity: Ok(qe_identity),
+                    tcb_info: Ok(tcb_info),
+                    signing: Ok(signing),
+                    pck: Ok(pck),
+                }
+            }
+            cached => cached,
+        };
+        let missed = (
+            cached.0.is_none(),
+            cached.1.is_none(),
+            cached.2.is_none(),
+            cached.3.is_none(),
+            cached.4.is_none(),
+        );
+
+        let reads = self
+            .read_uncached(qe_id_type, qe_id_version, tcb, pck_ca, cached)
+            .await;
+
+        if let (true, Ok(root)) = (missed.0, &reads.root) {
+            self.cache_certificate(CA::ROOT, root);
+        }
+        if let (true, Ok(qe_identity)) = (missed.1, &reads.qe_identity) {
+            self.cache_enclave_identity(qe_id_type, qe_id_version, qe_identity);
+        }
+        if let (true, Ok(tcb_info)) = (missed.2, &reads.tcb_info) {
+            self.cache_tcb_info(tcb_type, fmspc, tcb_version, tcb_info);
+        }
+        if let (true, Ok(signing)) = (missed.3, &reads.signing) {
+            self.cache_certificate(CA::SIGNING, signing);
+        }
+        if let (true, Ok(pck)) = (missed.4, &reads.pck) {
+            self.cache_certificate(pck_ca, pck);
+        }
+        reads
+    }
+
+    // Reads what is not cached, the batch is made in full since it costs a single call
+    async fn read_uncached(
+        &self,
+        qe_id_type: EnclaveIdType,
+        qe_id_version: u32,
+        tcb: (u8, &str, u32),
+        pck_ca: CA,
+        cached: CachedQuoteCollateral,
+    ) -> QuoteCollateralReads {
+        let (tcb_type, fmspc, tcb_version) = tcb;
+        let (root, qe_identity, tcb_info, signing, pck) = cached;
+        let network = self.network();
+        let pcs_call = |ca: CA| call3(network.pcs_dao, IPCSDao::getCertificateByIdCall { ca });
+        let calls = vec![
+            pcs_call(CA::ROOT),
+            call3(
+                network.enclave_id_dao,
+                IEnclaveIdentityDao::getEnclaveIdentityCall {
+                    id: qe_id_type.to_uint256(),
+                    version: U256::from(qe_id_version),
+                },
+            ),
+            call3(
+                network.fmspc_tcb_dao,
+                IFmspcTcbDao::getTcbInfoCall {
+                    tcbType: U256::from(tcb_type),
+                    fmspc: fmspc.to_string(),
+                    version: U256::from(tcb_version),
+                },
+            ),
+            pcs_call(CA::SIGNING),
+            pcs_call(pck_ca),
+        ];
+
+        let results = match self.aggregate3(calls).await {
+            Ok(results) => results,
+            Err(_) => {
+                let (root, qe_identity, tcb_info, signing, pck) = futures::join!(
+                    or_read(root, self.read_certificate_by_id(CA::ROOT)),
+                    or_read(qe_identity, self.read_enclave_identity_obj(qe_id_type, qe_id_version)),
+                    or_read(tcb_info, self.read_tcb_info_obj(tcb_type, fmspc, tcb_version)),
+                    or_read(signing, self.read_certificate_by_id(CA::SIGNING)),
+                    or_read(pck, self.read_certificate_by_id(pck_ca)),
+                );
+                return QuoteCollateralReads {
+                    root,
+                    qe_identity,
+                    tcb_info,
+                    signing,
+                    pck,
+                };
+            }
+        };
+
+        let certificate = |cached: Option<(Vec<u8>, Vec<u8>)>, result: &Call3Result| match cached {
+            Some(cached) => Ok(cached),
+            None => {
+                let decoded = decode::<IPCSDao::getCertificateByIdCall>(result)?;
+                Ok((decoded.cert.to_vec(), decoded.crl.to_vec()))
+            }
+        };
+        QuoteCollateralReads {
+            root: certificate(root, &results[0]),
+            qe_identity: match qe_identity {
+                Some(cached) => Ok(cached),
+                None => decode::<IEnclaveIdentityDao::getEnclaveIdentityCall>(&results[1])
+                    .and_then(|decoded| decoded.enclaveIdObj.into_stored()),
+            },
+            tcb_info: match tcb_info {
+                Some(cached) => Ok(cached),
+                None => decode::<IFmspcTcbDao::getTcbInfoCall>(&results[2])
+                    .and_then(|decoded| decoded.tcbObj.into_stored()),
+            },
+            signing: certificate(signing, &results[3]),
+            pck: certificate(pck, &results[4]),
+        }
+    }
+}
+
+// Root, QE identity, TCB Info, signing and PCK CA, as found in the cache
+type CachedQuoteCollateral = (
+    Option<(Vec<u8>, Vec<u8>)>,
+    Option<EnclaveIdentityJsonObj>,
+    Option<TcbInfoJsonObj>,
+    Option<(Vec<u8>, Vec<u8>)>,
+    Option<(Vec<u8>, Vec<u8>)>,
+);
+
+async fn or_read<T>(cached: Option<T>, read: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    match cached {
+        Some(cached) => Ok(cached),
+        None => read.await,
+    }
+}
+
+fn call3<C: SolCall>(target: Address, call: C) -> Call3 {
+    Call3 {
+        target,
+        allowFailure: true,
+        callData: call.abi_encode().into(),
+    }
+}
+
+fn decode<C: SolCall>(result: &Call3Result) -> Result<C::Return> {
+    if !result.success {
+        return Err(anyhow::Error::msg("call reverted"));
+    }
+    Ok(C::abi_decode_returns(&result.returnData, true)?)
+}
@@ -1,13 +1,13 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, PCS_DAO_ADDRESS};
+use crate::reader::PccsReader;
 
-use alloy::{primitives::Address, providers::ProviderBuilder, sol};
+use alloy::sol;
 
 sol! {
     #[sol(rpc)]
     interface IPCSDao {
-        #[derive(Debug)]
+        #[derive(Debug, PartialEq, Eq, Hash)]
         enum CA {
             ROOT,
             PROCESSOR,
@@ -17,22 +17,47 @@ sol! {
 
         #[derive(Debug)]
         function getCertificateById(CA ca) external view returns (bytes memory cert, bytes memory crl);
+
+        #[derive(Debug)]
+        function upsertPcsCertificates(CA ca, bytes calldata cert) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertPckCrl(CA ca, bytes calldata crl) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertRootCACrl(bytes calldata rootcacrl) external returns (bytes32 attestationId);
     }
 }
 
 pub async fn get_certificate_by_id(ca_id: IPCSDao::CA) -> Result<(Vec<u8>, Vec<u8>)> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
+    PccsReader::default().get_certificate_by_id(ca_id).await
+}
+
+impl PccsReader {
+    pub async fn get_certificate_by_id(&self, ca_id: IPCSDao::CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        if let Some(cached) = self.cached_certificate(ca_id) {
+            return Ok(cached);
+        }
+        let certificate = self.read_certificate_by_id(ca_id).await?;
+        self.cache_certificate(ca_id, &certificate);
 
-    let pcs_dao_address_slice = hex::decode(PCS_DAO_ADDRESS).expect("invalid address hex");
-    let pcs_dao_contract = IPCSDao::new(Address::from_slice(&pcs_dao_address_slice), &provider);
+        Ok(certificate)
+    }
+
+    // Reads the DAO, bypassing the cache
+    pub(crate) async fn read_certificate_by_id(
+        &self,
+        ca_id: IPCSDao::CA,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let pcs_dao_contract = IPCSDao::new(self.network().pcs_dao, self.provider());
 
-    let call_builder = pcs_dao_contract.getCertificateById(ca_id);
+        let call_builder = pcs_dao_contract.getCertificateById(ca_id);
 
-    let call_return = call_builder.call().await?;
+        let call_return = self.prepare_call(call_builder).call().await?;
 
-    let cert = call_return.cert.to_vec();
-    let crl = call_return.crl.to_vec();
+        let cert = call_return.cert.to_vec();
+        let crl = call_return.crl.to_vec();
 
-    Ok((cert, crl))
+        Ok((cert, crl))
+    }
 }
@@ -0,0 +1,861 @@
+//! Verified reads: collateral decoded from DAO storage proven with `eth_getProof`
+//! against a trusted state root, instead of trusting the result of an `eth_call`.
+//!
+//! The RPC only supplies proofs, so a dishonest provider can refuse to answer
+//! but cannot make the reader accept collateral that is not in the trusted state.
+
+use alloy::consensus::Header;
+use alloy::eips::BlockId;
+use alloy::primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::rlp::{Decodable, Header as RlpHeader};
+use alloy::rpc::types::EIP1186AccountProofResponse;
+use alloy::sol_types::SolValue;
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::pccs::NotStored;
+use crate::source::CollateralSource;
+use crate::{PccsReader, CA};
+
+// keccak256(rlp("")), the root of an empty trie
+const EMPTY_ROOT: B256 = B256::new([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// The state root proofs are checked against, and the block they are requested at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedState {
+    pub state_root: B256,
+    pub block: BlockId,
+}
+
+impl TrustedState {
+    pub fn new(state_root: B256, block: impl Into<BlockId>) -> Self {
+        TrustedState {
+            state_root,
+            block: block.into(),
+        }
+    }
+
+    /// Trusts the state root of a block header obtained out of band, e.g. from a light client.
+    pub fn from_header(header: &Header) -> Self {
+        TrustedState::new(header.state_root, header.hash_slow())
+    }
+}
+
+/// A Solidity `bytes` value in the storage of a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageSlot {
+    pub address: Address,
+    pub slot: B256,
+}
+
+/// Where each collateral is stored. Certificates and CRLs are raw DER,
+/// TCB Info and Enclave Identities are the ABI encoded DAO structs.
+pub trait StorageLayout: Send + Sync {
+    fn certificate(&self, ca: CA) -> StorageSlot;
+
+    fn crl(&self, ca: CA) -> StorageSlot;
+
+    fn tcb_info(&self, tcb_type: u8, fmspc: [u8; 6], version: u32) -> StorageSlot;
+
+    fn enclave_identity(&self, id: EnclaveIdType, version: u32) -> StorageSlot;
+}
+
+/// Collateral kept in a `mapping(bytes32 => bytes)` at `slot` of the `storage` contract,
+/// keyed by the hash of the packed lookup arguments:
+///
+/// - certificates and CRLs: `keccak256(abi.encodePacked(uint8 ca, bool isCrl))`
+/// - TCB Info: `keccak256(abi.encodePacked(uint8 tcbType, bytes6 fmspc, uint32 version))`
+/// - Enclave Identities: `keccak256(abi.encodePacked(uint256 id, uint256 version))`
+///
+/// Deployments laid out differently implement [`StorageLayout`] themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaoStorageLayout {
+    pub storage: Address,
+    pub slot: U256,
+}
+
+impl DaoStorageLayout {
+    pub fn new(storage: Address, slot: U256) -> Self {
+        DaoStorageLayout { storage, slot }
+    }
+
+    fn entry(&self, key: B256) -> StorageSlot {
+        StorageSlot {
+            address: self.storage,
+            slot: mapping_slot(key, self.slot),
+        }
+    }
+
+    fn pcs_key(ca: CA, is_crl: bool) -> B256 {
+        keccak256([ca as u8, is_crl as u8])
+    }
+}
+
+impl StorageLayout for DaoStorageLayout {
+    fn certificate(&self, ca: CA) -> StorageSlot {
+        self.entry(Self::pcs_key(ca, false))
+    }
+
+    fn crl(&self, ca: CA) -> StorageSlot {
+        self.entry(Self::pcs_key(ca, true))
+    }
+
+    fn tcb_info(&self, tcb_type: u8, fmspc: [u8; 6], version: u32) -> StorageSlot {
+        let mut packed = vec![tcb_type];
+        packed.extend_from_slice(&fmspc);
+        packed.extend_from_slice(&version.to_be_bytes());
+        self.entry(keccak256(packed))
+    }
+
+    fn enclave_identity(&self, id: EnclaveIdType, version: u32) -> StorageSlot {
+        let mut packed = id.to_uint256().to_be_bytes::<32>().to_vec();
+        packed.extend_from_slice(&U256::from(version).to_be_bytes::<32>());
+        self.entry(keccak256(packed))
+    }
+}
+
+// Slot of `mapping[key]` for a mapping declared at `slot`
+fn mapping_slot(key: B256, slot: U256) -> B256 {
+    let mut preimage = key.to_vec();
+    preimage.extend_from_slice(&slot.to_be_bytes::<32>());
+    keccak256(preimage)
+}
+
+/// Reads collateral from storage proofs checked against a [`TrustedState`].
+#[derive(Debug, Clone)]
+pub struct VerifiedReader<L> {
+    reader: PccsReader,
+    state: TrustedState,
+    layout: L,
+}
+
+impl PccsReader {
+    /// A reader that only returns collateral proven against `state`.
+    pub fn verified<L: StorageLayout>(&self, state: TrustedState, layout: L) -> VerifiedReader<L> {
+        VerifiedReader {
+            reader: self.at_block(state.block),
+            state,
+            layout,
+        }
+    }
+}
+
+impl<L: StorageLayout> VerifiedReader<L> {
+    pub fn state(&self) -> TrustedState {
+        self.state
+    }
+
+    /// Same as [`PccsReader::get_certificate_by_id`], empty when not stored.
+    pub async fn get_certificate_by_id(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let cert = self.read_bytes(self.layout.certificate(ca)).await?;
+        let crl = self.read_bytes(self.layout.crl(ca)).await?;
+        Ok((cert, crl))
+    }
+
+    pub async fn get_tcb_info_obj(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+    ) -> Result<TcbInfoJsonObj> {
+        let fmspc: [u8; 6] = hex::decode(fmspc)?
+            .try_into()
+            .map_err(|_| anyhow::Error::msg("FMSPC must be 6 bytes"))?;
+        let stored = self.read_bytes(self.layout.tcb_info(tcb_type, fmspc, version)).await?;
+        if stored.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok(TcbInfoJsonObj::abi_decode(&stored, true)?)
+    }
+
+    pub async fn get_enclave_identity_obj(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        let stored = self.read_bytes(self.layout.enclave_identity(id, version)).await?;
+        if stored.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok(EnclaveIdentityJsonObj::abi_decode(&stored, true)?)
+    }
+
+    /// Reads a Solidity `bytes` value, proving its length slot and then its data slots.
+    pub async fn read_bytes(&self, location: StorageSlot) -> Result<Vec<u8>> {
+        let head = self.storage(location.address, vec![location.slot]).await?[0];
+        match bytes_layout(location.slot, head) {
+            SolidityBytes::Short(bytes) => Ok(bytes),
+            SolidityBytes::Long { len, slots } => {
+                let words = self.storage(location.address, slots).await?;
+                let mut bytes: Vec<u8> = words
+                    .iter()
+                    .flat_map(|word| word.to_be_bytes::<32>())
+                    .collect();
+                bytes.truncate(len);
+                Ok(bytes)
+            }
+        }
+    }
+
+    async fn storage(&self, address: Address, slots: Vec<B256>) -> Result<Vec<U256>> {
+        let response = self
+            .reader
+            .provider()
+            .get_proof(address, slots.clone())
+            .block_id(self.state.block)
+            .await?;
+        verify_account_storage(self.state.state_root, address, &slots, &response)
+    }
+}
+
+#[async_trait]
+impl<L: StorageLayout> CollateralSource for VerifiedReader<L> {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (cert, crl) = self.get_certificate_by_id(ca).await?;
+        if cert.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok((cert, crl))
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        self.get_tcb_info_obj(tcb_type, fmspc, version).await
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        self.get_enclave_identity_obj(id, version).await
+    }
+}
+
+enum SolidityBytes {
+    Short(Vec<u8>),
+    Long { len: usize, slots: Vec<B256> },
+}
+
+// Values up to 31 bytes share the slot with their length, longer ones
+// only keep the length there and start at keccak256(slot)
+fn bytes_layout(slot: B256, head: U256) -> SolidityBytes {
+    let word = head.to_be_bytes::<32>();
+    if !head.bit(0) {
+        let len = (word[31] / 2) as usize;
+        return SolidityBytes::Short(word[..len.min(31)].to_vec());
+    }
+
+    let len = (head >> 1usize).saturating_to::<usize>();
+    let start = U256::from_be_bytes(keccak256(slot).0);
+    let slots = (0..len.div_ceil(32))
+        .map(|i| B256::from(start.wrapping_add(U256::from(i))))
+        .collect();
+    SolidityBytes::Long { len, slots }
+}
+
+/// The slots a Solidity `bytes` value stored at `slot` occupies, with their words.
+pub fn bytes_storage(slot: B256, value: &[u8]) -> Vec<(B256, B256)> {
+    if value.len() < 32 {
+        let mut word = [0u8; 32];
+        word[..value.len()].copy_from_slice(value);
+        word[31] = (value.len() * 2) as u8;
+        return vec![(slot, B256::from(word))];
+    }
+
+    let head = U256::from(value.len() * 2 + 1);
+    let start = U256::from_be_bytes(keccak256(slot).0);
+    let mut words = vec![(slot, B256::from(head))];
+    for (i, chunk) in value.chunks(32).enumerate() {
+        let mut word = [0u8; 32];
+        word[..chunk.len()].copy_from_slice(chunk);
+        words.push((B256::from(start.wrapping_add(U256::from(i))), B256::from(word)));
+    }
+    words
+}
+
+/// Checks the account proof of `response` against `state_root`, then the proof
+/// of every slot against the account storage root, and returns the proven values.
+pub fn verify_account_storage(
+    state_root: B256,
+    address: Address,
+    slots: &[B256],
+    response: &EIP1186AccountProofResponse,
+) -> Result<Vec<U256>> {
+    if response.address != address {
+        return Err(anyhow::anyhow!("proof is for {}, not {}", response.address, address));
+    }
+
+    let storage_root = match verify_proof(state_root, keccak256(address), &response.account_proof)? {
+        Some(account) => decode_storage_root(&account)?,
+        // no account, hence no storage
+        None => EMPTY_ROOT,
+    };
+
+    slots
+        .iter()
+        .map(|slot| {
+            let proof = response
+                .storage_proof
+                .iter()
+                .find(|proof| proof.key.0 == *slot)
+                .ok_or_else(|| anyhow::anyhow!("no proof for slot {}", slot))?;
+            let value = match verify_proof(storage_root, keccak256(slot), &proof.proof)? {
+                Some(value) => U256::decode(&mut value.as_slice())?,
+                None => U256::ZERO,
+            };
+            if value != proof.value {
+                return Err(anyhow::anyhow!("slot {} does not hold the value returned", slot));
+            }
+            Ok(value)
+        })
+        .collect()
+}
+
+// Accounts are rlp([nonce, balance, storageRoot, codeHash])
+fn decode_storage_root(account: &[u8]) -> Result<B256> {
+    let mut buf = account;
+    let header = RlpHeader::decode(&mut buf)?;
+    if !header.list {
+        return Err(anyhow::Error::msg("account is not a list"));
+    }
+    u64::decode(&mut buf)?;
+    U256::decode(&mut buf)?;
+    Ok(B256::decode(&mut buf)?)
+}
+
+/// Walks a Merkle-Patricia proof from `root` along `path`, the hashed key.
+/// Returns the proven value, or None when the proof shows the key is absent.
+pub fn verify_proof(root: B256, path: B256, proof: &[Bytes]) -> Result<Option<Vec<u8>>> {
+    let nibbles: Vec<u8> = path.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+    let mut depth = 0;
+    let mut proof = proof.iter();
+
+    if root == EMPTY_ROOT {
+        return Ok(None);
+    }
+    let mut node = next_node(&mut proof, root)?;
+
+    loop {
+        let items = node_items(&node)?;
+        let child = match items.len() {
+            17 if depth == nibbles.len() => {
+                let value = decode_string(items[16])?;
+                return Ok((!value.is_empty()).then(|| value.to_vec()));
+            }
+            17 => {
+                depth += 1;
+                items[nibbles[depth - 1] as usize]
+            }
+            2 => {
+                let (is_leaf, key) = decode_path(decode_string(items[0])?)?;
+                let rest = &nibbles[depth..];
+                if is_leaf {
+                    return match rest == key.as_slice() {
+                        true => Ok(Some(decode_string(items[1])?.to_vec())),
+                        false => Ok(None),
+                    };
+                }
+                if !rest.starts_with(&key) {
+                    return Ok(None);
+                }
+                depth += key.len();
+                items[1]
+            }
+            n => return Err(anyhow::anyhow!("trie node with {} items", n)),
+        };
+
+        node = match ChildRef::decode(child)? {
+            ChildRef::Empty => return Ok(None),
+            ChildRef::Hash(hash) => next_node(&mut proof, hash)?,
+            ChildRef::Inline(raw) => raw.to_vec(),
+        };
+    }
+}
+
+enum ChildRef<'a> {
+    Empty,
+    Hash(B256),
+    // nodes shorter than 32 bytes are embedded in their parent
+    Inline(&'a [u8]),
+}
+
+impl<'a> ChildRef<'a> {
+    fn decode(item: &'a [u8]) -> Result<Self> {
+        let mut buf = item;
+        let header = RlpHeader::decode(&mut buf)?;
+        match (header.list, header.payload_length) {
+            (true, _) => Ok(ChildRef::Inline(item)),
+            (false, 0) => Ok(ChildRef::Empty),
+            (false, 32) => Ok(ChildRef::Hash(B256::from_slice(buf))),
+            (false, n) => Err(anyhow::anyhow!("child reference of {} bytes", n)),
+        }
+    }
+}
+
+fn next_node<'a>(proof: &mut impl Iterator<Item = &'a Bytes>, hash: B256) -> Result<Vec<u8>> {
+    let node = proof
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("proof ends before the key is reached"))?;
+    if keccak256(node) != hash {
+        return Err(anyhow::anyhow!("proof node does not hash to {}", hash));
+    }
+    Ok(node.to_vec())
+}
+
+// The raw encoding of every item of an rlp list
+fn node_items(node: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut buf = node;
+    let header = RlpHeader::decode(&mut buf)?;
+    if !header.list || buf.len() != header.payload_length {
+        return Err(anyhow::Error::msg("trie node is not a list"));
+    }
+
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let start = buf;
+        let item = RlpHeader::decode(&mut buf)?;
+        if buf.len() < item.payload_length {
+            return Err(anyhow::Error::msg("truncated trie node"));
+        }
+        buf = &buf[item.payload_length..];
+        items.push(&start[..start.len() - buf.len()]);
+    }
+    Ok(items)
+}
+
+fn decode_string(item: &[u8]) -> Result<&[u8]> {
+    let mut buf = item;
+    Ok(RlpHeader::decode_bytes(&mut buf, false)?)
+}
+
+// Hex prefix encoding: the high nibble flags a leaf (2) and an odd length (1)
+fn decode_path(encoded: &[u8]) -> Result<(bool, Vec<u8>)> {
+    let first = *encoded
+        .first()
+        .ok_or_else(|| anyhow::Error::msg("empty trie path"))?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(anyhow::anyhow!("invalid trie path flag {}", flag));
+    }
+
+    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    for byte in &encoded[1..] {
+        nibbles.extend_from_slice(&[byte >> 4, byte & 0x0f]);
+    }
+    Ok((flag & 2 == 2, nibbles))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::rlp::{encode, Encodable};
+    use alloy::rpc::types::EIP1186StorageProof;
+    use axum::{extract::State, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use crate::pccs::is_not_stored;
+    use crate::NetworkProfile;
+
+    // Builds a trie from (path, value) pairs, keeping every hashed node to answer proofs
+    #[derive(Default)]
+    struct Trie {
+        nodes: HashMap<B256, Vec<u8>>,
+        root: B256,
+    }
+
+    impl Trie {
+        fn new(entries: &[(B256, Vec<u8>)]) -> Self {
+            let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+                .iter()
+                .map(|(path, value)| {
+                    (path.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect(), value.clone())
+                })
+                .collect();
+            entries.sort();
+
+            let mut trie = Trie::default();
+            let root = trie.build(&entries, 0);
+            trie.root = keccak256(&root);
+            trie.nodes.insert(trie.root, root);
+            trie
+        }
+
+        fn build(&mut self, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+            if let [(path, value)] = entries {
+                return list(&[string(&hex_prefix(&path[depth..], true)), string(value)]);
+            }
+
+            let first = &entries[0].0;
+            let last = &entries[entries.len() - 1].0;
+            let shared = (depth..first.len()).take_while(|&i| first[i] == last[i]).count();
+            if shared > 0 {
+                let child = self.build(entries, depth + shared);
+                let child = self.reference(child);
+                return list(&[string(&hex_prefix(&first[depth..depth + shared], false)), child]);
+            }
+
+            let mut items: Vec<Vec<u8>> = (0..16u8)
+                .map(|nibble| {
+                    let group: Vec<_> = entries
+                        .iter()
+                        .filter(|(path, _)| path[depth] == nibble)
+                        .cloned()
+                        .collect();
+                    match group.is_empty() {
+                        true => string(&[]),
+                        false => {
+                            let child = self.build(&group, depth + 1);
+                            self.reference(child)
+                        }
+                    }
+                })
+                .collect();
+            items.push(string(&[]));
+            list(&items)
+        }
+
+        fn reference(&mut self, node: Vec<u8>) -> Vec<u8> {
+            if node.len() < 32 {
+                return node;
+            }
+            let hash = keccak256(&node);
+            self.nodes.insert(hash, node);
+            string(hash.as_slice())
+        }
+
+        // The hashed nodes on the way to `path`, in order
+        fn proof(&self, path: B256) -> Vec<Bytes> {
+            let nibbles: Vec<u8> = path.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+            let mut proof = vec![];
+            let mut node = self.nodes[&self.root].clone();
+            proof.push(Bytes::from(node.clone()));
+            let mut depth = 0;
+            loop {
+                let items = node_items(&node).unwrap();
+                let child = match items.len() {
+                    17 => {
+                        depth += 1;
+                        items[nibbles[depth - 1] as usize].to_vec()
+                    }
+                    _ => {
+                        let (is_leaf, key) = decode_path(decode_string(items[0]).unwrap()).unwrap();
+                        if is_leaf || !nibbles[depth..].starts_with(&key) {
+                            return proof;
+                        }
+                        depth += key.len();
+                        items[1].to_vec()
+                    }
+                };
+                node = match ChildRef::decode(&child).unwrap() {
+                    ChildRef::Empty => return proof,
+                    ChildRef::Hash(hash) => {
+                        proof.push(Bytes::from(self.nodes[&hash].clone()));
+                        self.nodes[&hash].clone()
+                    }
+                    ChildRef::Inline(raw) => raw.to_vec(),
+                };
+            }
+        }
+    }
+
+    fn string(bytes: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        bytes.encode(&mut out);
+        out
+    }
+
+    fn list(items: &[Vec<u8>]) -> Vec<u8> {
+        let payload: Vec<u8> = items.concat();
+        let mut out = vec![];
+        RlpHeader {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut out);
+        out.extend(payload);
+        out
+    }
+
+    fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = (is_leaf as u8) * 2 + (nibbles.len() % 2) as u8;
+        let mut out = vec![];
+        let rest = if nibbles.len() % 2 == 1 {
+            out.push(flag << 4 | nibbles[0]);
+            &nibbles[1..]
+        } else {
+            out.push(flag << 4);
+            nibbles
+        };
+        out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+        out
+    }
+
+    // One contract holding `storage`, in a state with a few other accounts
+    fn state(address: Address, storage: &[(B256, U256)]) -> (Trie, Trie) {
+        let storage_trie = Trie::new(
+            &storage
+                .iter()
+                .map(|(slot, value)| (keccak256(slot), encode(value)))
+                .collect::<Vec<_>>(),
+        );
+        let account = |storage_root: B256| {
+            list(&[encode(1u64), encode(U256::ZERO), encode(storage_root), encode(B256::ZERO)])
+        };
+        let mut accounts = vec![(keccak256(address), account(storage_trie.root))];
+        for byte in 1..8u8 {
+            accounts.push((keccak256(Address::repeat_byte(byte)), account(EMPTY_ROOT)));
+        }
+        (Trie::new(&accounts), storage_trie)
+    }
+
+    fn response(
+        address: Address,
+        state: &Trie,
+        storage: &Trie,
+        slots: &[(B256, U256)],
+    ) -> EIP1186AccountProofResponse {
+        EIP1186AccountProofResponse {
+            address,
+            account_proof: state.proof(keccak256(address)),
+            storage_proof: slots
+                .iter()
+                .map(|(slot, value)| EIP1186StorageProof {
+                    key: (*slot).into(),
+                    value: *value,
+                    proof: storage.proof(keccak256(slot)),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_verify_storage_proofs() {
+        let dao = Address::repeat_byte(0x44);
+        let layout = DaoStorageLayout::new(dao, U256::from(3));
+        let cert: Vec<u8> = (0..70).collect();
+
+        // a long `bytes` value: the length slot and three data slots
+        let head = layout.certificate(CA::ROOT).slot;
+        let SolidityBytes::Long { len, slots } = bytes_layout(head, U256::from(70 * 2 + 1)) else {
+            panic!("expected a long value");
+        };
+        assert_eq!((len, slots.len()), (70, 3));
+
+        let mut storage = vec![(head, U256::from(70 * 2 + 1))];
+        for (i, slot) in slots.iter().enumerate() {
+            let mut word = [0u8; 32];
+            let chunk = &cert[i * 32..cert.len().min(i * 32 + 32)];
+            word[..chunk.len()].copy_from_slice(chunk);
+            storage.push((*slot, U256::from_be_bytes(word)));
+        }
+        // a short value, sharing its slot with the length
+        let crl_slot = layout.crl(CA::ROOT).slot;
+        let mut crl_word = [0u8; 32];
+        crl_word[..3].copy_from_slice(&[0x30, 0x01, 0x00]);
+        crl_word[31] = 3 * 2;
+        storage.push((crl_slot, U256::from_be_bytes(crl_word)));
+
+        let (state_trie, storage_trie) = state(dao, &storage);
+        let proven = verify_account_storage(
+            state_trie.root,
+            dao,
+            &[head, crl_slot],
+            &response(dao, &state_trie, &storage_trie, &[storage[0], storage[4]]),
+        )
+        .unwrap();
+        assert_eq!(proven, vec![storage[0].1, storage[4].1]);
+
+        let data = verify_account_storage(
+            state_trie.root,
+            dao,
+            &slots,
+            &response(dao, &state_trie, &storage_trie, &storage[1..4]),
+        )
+        .unwrap();
+        let mut bytes: Vec<u8> = data.iter().flat_map(|word| word.to_be_bytes::<32>()).collect();
+        bytes.truncate(len);
+        assert_eq!(bytes, cert);
+
+        let SolidityBytes::Short(crl) = bytes_layout(crl_slot, proven[1]) else {
+            panic!("expected a short value");
+        };
+        assert_eq!(crl, vec![0x30, 0x01, 0x00]);
+
+        // an unset slot is proven to be zero
+        let unset = layout.crl(CA::PLATFORM).slot;
+        let proven = verify_account_storage(
+            state_trie.root,
+            dao,
+            &[unset],
+            &response(dao, &state_trie, &storage_trie, &[(unset, U256::ZERO)]),
+        )
+        .unwrap();
+        assert_eq!(proven, vec![U256::ZERO]);
+    }
+
+    struct ProvenState {
+        address: Address,
+        state: Trie,
+        storage: Trie,
+        values: HashMap<B256, U256>,
+    }
+
+    // Answers `eth_getProof` for the slots asked for, like a node would
+    async fn mock_rpc(
+        State(proven): State<Arc<ProvenState>>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        let result = match request["method"].as_str() {
+            Some("eth_getProof") => {
+                let slots: Vec<(B256, U256)> =
+                    serde_json::from_value::<Vec<B256>>(request["params"][1].clone())
+                        .unwrap()
+                        .into_iter()
+                        .map(|slot| (slot, proven.values.get(&slot).copied().unwrap_or_default()))
+                        .collect();
+                json!(response(
+                    proven.address,
+                    &proven.state,
+                    &proven.storage,
+                    &slots
+                ))
+            }
+            _ => json!(null),
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    #[tokio::test]
+    async fn test_reads_verified_collateral_over_rpc() {
+        let dao = Address::repeat_byte(0x44);
+        let layout = DaoStorageLayout::new(dao, U256::from(3));
+        let cert: Vec<u8> = (0..70).collect();
+        let crl = vec![0x30, 0x01, 0x00];
+        let tcb_info = TcbInfoJsonObj {
+            tcbInfoStr: r#"{"id":"SGX","version":3}"#.to_string(),
+            signature: vec![0xaa; 64].into(),
+        };
+        let mut storage = bytes_storage(layout.certificate(CA::ROOT).slot, &cert);
+        storage.extend(bytes_storage(layout.crl(CA::ROOT).slot, &crl));
+        storage.extend(bytes_storage(
+            layout
+                .tcb_info(0, [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00], 3)
+                .slot,
+            &tcb_info.abi_encode(),
+        ));
+        let values: HashMap<B256, U256> = storage
+            .iter()
+            .map(|(slot, word)| (*slot, U256::from_be_bytes(word.0)))
+            .collect();
+        let (state_trie, storage_trie) = state(
+            dao,
+            &values
+                .iter()
+                .map(|(slot, value)| (*slot, *value))
+                .collect::<Vec<_>>(),
+        );
+        let root = state_trie.root;
+
+        let proven = Arc::new(ProvenState {
+            address: dao,
+            state: state_trie,
+            storage: storage_trie,
+            values,
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/", post(mock_rpc)).with_state(proven);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let reader = PccsReader::new(NetworkProfile::default().with_rpc_url(&url)).unwrap();
+        let verified = reader.verified(TrustedState::new(root, 1u64), layout);
+        assert_eq!(
+            verified.get_certificate_by_id(CA::ROOT).await.unwrap(),
+            (cert, crl)
+        );
+        let read = verified
+            .get_tcb_info_obj(0, "00606a000000", 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            (read.tcbInfoStr, read.signature),
+            (tcb_info.tcbInfoStr, tcb_info.signature)
+        );
+
+        // absent collateral is told apart from a failed read
+        let absent = verified
+            .get_enclave_identity_obj(EnclaveIdType::QE, 3)
+            .await
+            .unwrap_err();
+        assert!(is_not_stored(&absent));
+        let absent = CollateralSource::certificate(&verified, CA::PLATFORM)
+            .await
+            .unwrap_err();
+        assert!(is_not_stored(&absent));
+
+        // the same answers do not prove anything against another state root
+        let untrusted = reader.verified(TrustedState::new(B256::repeat_byte(1), 1u64), layout);
+        let failed = untrusted.get_certificate_by_id(CA::ROOT).await.unwrap_err();
+        assert!(!is_not_stored(&failed));
+    }
+
+    #[test]
+    fn test_bytes_storage_round_trip() {
+        let slot = B256::repeat_byte(0x07);
+        for len in [0, 3, 31, 32, 70] {
+            let value: Vec<u8> = (0..len as u8).map(|i| i.wrapping_add(1)).collect();
+            let words = bytes_storage(slot, &value);
+            assert_eq!(words[0].0, slot);
+
+            let head = U256::from_be_bytes(words[0].1 .0);
+            let decoded = match bytes_layout(slot, head) {
+                SolidityBytes::Short(bytes) => bytes,
+                SolidityBytes::Long { len, slots } => {
+                    assert_eq!(slots, words[1..].iter().map(|(slot, _)| *slot).collect::<Vec<_>>());
+                    let mut bytes: Vec<u8> = words[1..].iter().flat_map(|(_, word)| word.0).collect();
+                    bytes.truncate(len);
+                    bytes
+                }
+            };
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_rejects_forged_proofs() {
+        let dao = Address::repeat_byte(0x44);
+        let slot = B256::with_last_byte(1);
+        let storage = [(slot, U256::from(42)), (B256::with_last_byte(2), U256::from(7))];
+        let (state_trie, storage_trie) = state(dao, &storage);
+
+        // a value the proof does not support
+        let mut lying = response(dao, &state_trie, &storage_trie, &storage[..1]);
+        lying.storage_proof[0].value = U256::from(43);
+        assert!(verify_account_storage(state_trie.root, dao, &[slot], &lying).is_err());
+
+        // a tampered node no longer hashes to its reference
+        let mut tampered = response(dao, &state_trie, &storage_trie, &storage[..1]);
+        let last = tampered.storage_proof[0].proof.len() - 1;
+        let mut node = tampered.storage_proof[0].proof[last].to_vec();
+        *node.last_mut().unwrap() ^= 1;
+        tampered.storage_proof[0].proof[last] = node.into();
+        assert!(verify_account_storage(state_trie.root, dao, &[slot], &tampered).is_err());
+
+        // proofs against another state root
+        let honest = response(dao, &state_trie, &storage_trie, &storage[..1]);
+        assert!(verify_account_storage(B256::repeat_byte(1), dao, &[slot], &honest).is_err());
+        assert!(verify_account_storage(state_trie.root, dao, &[slot], &honest).is_ok());
+    }
+}
@@ -0,0 +1,257 @@
+//! Quote Provider Library (QPL) entry points, so that DCAP applications can
+//! replace Intel's `libdcap_quoteprov.so` and read collateral from the on-chain PCCS.
+//!
+//! The library is configured with a JSON file in the spirit of `sgx_default_qcnl.conf`,
+//! read from `$PCCS_READER_QPL_CONF` or [`DEFAULT_QPL_CONF_PATH`].
+
+use std::alloc::{alloc, dealloc, Layout};
+use std::ffi::CStr;
+use std::mem::{align_of, offset_of};
+use std::os::raw::c_char;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::runtime::Runtime;
+
+use crate::collateral::qve::{SgxQlQveCollateral, QVE_COLLATERAL_FIELDS};
+use crate::config::{from_commented_json, NetworkConfig};
+use crate::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
+use crate::{NetworkProfile, PccsReader, CA};
+
+pub const QPL_CONF_ENV: &str = "PCCS_READER_QPL_CONF";
+pub const DEFAULT_QPL_CONF_PATH: &str = "/etc/pccs_reader_qpl.conf";
+
+// quote3_error_t values from sgx_ql_lib_common.h
+pub type Quote3Error = u32;
+pub const SGX_QL_SUCCESS: Quote3Error = 0x0000;
+pub const SGX_QL_ERROR_UNEXPECTED: Quote3Error = 0xE001;
+pub const SGX_QL_ERROR_INVALID_PARAMETER: Quote3Error = 0xE002;
+pub const SGX_QL_ERROR_OUT_OF_MEMORY: Quote3Error = 0xE003;
+pub const SGX_QL_NO_QUOTE_COLLATERAL_DATA: Quote3Error = 0xE01B;
+
+/// Contents of the QPL configuration file. Lines starting with `//` are ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct QplConfig {
+    #[serde(flatten)]
+    pub network: NetworkConfig,
+    // "v3" or "v4", the Intel PCS API version of the collateral to serve
+    pub collateral_version: Option<String>,
+}
+
+impl QplConfig {
+    pub fn from_json(json: &str) -> Result<Self> {
+        from_commented_json(json)
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = match std::env::var(QPL_CONF_ENV) {
+            Ok(path) => path,
+            // Fall back to the default network, like the Intel QPL does without a config
+            Err(_) if !std::path::Path::new(DEFAULT_QPL_CONF_PATH).exists() => {
+                return Ok(Self::default())
+            }
+            Err(_) => DEFAULT_QPL_CONF_PATH.to_string(),
+        };
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn network(&self) -> Result<NetworkProfile> {
+        self.network.profile()
+    }
+
+    // The quote version whose collateral is served
+    fn quote_version(&self) -> Result<u16> {
+        match self.collateral_version.as_deref() {
+            None | Some("v4") | Some("4") => Ok(4),
+            Some("v3") | Some("3") => Ok(3),
+            Some(v) => Err(anyhow::anyhow!("Unsupported collateral_version {}", v)),
+        }
+    }
+}
+
+struct Qpl {
+    runtime: Runtime,
+    reader: PccsReader,
+    quote_version: u16,
+}
+
+fn qpl() -> Option<&'static Qpl> {
+    static QPL: OnceLock<Option<Qpl>> = OnceLock::new();
+    QPL.get_or_init(|| {
+        let init = || -> Result<Qpl> {
+            let config = QplConfig::load()?;
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()?;
+            let reader = runtime.block_on(config.network.reader())?;
+            Ok(Qpl {
+                runtime,
+                reader,
+                quote_version: config.quote_version()?,
+            })
+        };
+        init()
+            .map_err(|e| eprintln!("[pccs-reader-qpl] initialization failed: {}", e))
+            .ok()
+    })
+    .as_ref()
+}
+
+/// # Safety
+///
+/// `fmspc` must point to `fmspc_size` readable bytes, `pck_ca` must be a
+/// null-terminated string and `pp_quote_collateral` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_get_quote_verification_collateral(
+    fmspc: *const u8,
+    fmspc_size: u16,
+    pck_ca: *const c_char,
+    pp_quote_collateral: *mut *mut SgxQlQveCollateral,
+) -> Quote3Error {
+    get_quote_verification_collateral(SGX_TEE_TYPE, fmspc, fmspc_size, pck_ca, pp_quote_collateral)
+}
+
+/// # Safety
+///
+/// `p_quote_collateral` must have been returned by
+/// [`sgx_ql_get_quote_verification_collateral`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sgx_ql_free_quote_verification_collateral(
+    p_quote_collateral: *const SgxQlQveCollateral,
+) -> Quote3Error {
+    free_quote_verification_collateral(p_quote_collateral)
+}
+
+/// # Safety
+///
+/// Same as [`sgx_ql_get_quote_verification_collateral`].
+#[no_mangle]
+pub unsafe extern "C" fn tdx_ql_get_quote_verification_collateral(
+    fmspc: *const u8,
+    fmspc_size: u16,
+    pck_ca: *const c_char,
+    pp_quote_collateral: *mut *mut SgxQlQveCollateral,
+) -> Quote3Error {
+    get_quote_verification_collateral(TDX_TEE_TYPE, fmspc, fmspc_size, pck_ca, pp_quote_collateral)
+}
+
+/// # Safety
+///
+/// `p_quote_collateral` must have been returned by
+/// [`tdx_ql_get_quote_verification_collateral`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tdx_ql_free_quote_verification_collateral(
+    p_quote_collateral: *const SgxQlQveCollateral,
+) -> Quote3Error {
+    free_quote_verification_collateral(p_quote_collateral)
+}
+
+unsafe fn get_quote_verification_collateral(
+    tee_type: u32,
+    fmspc: *const u8,
+    fmspc_size: u16,
+    pck_ca: *const c_char,
+    pp_quote_collateral: *mut *mut SgxQlQveCollateral,
+) -> Quote3Error {
+    if fmspc.is_null() || fmspc_size != 6 || pck_ca.is_null() || pp_quote_collateral.is_null() {
+        return SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+
+    let fmspc = hex::encode(std::slice::from_raw_parts(fmspc, fmspc_size as usize));
+    let pck_ca = match CStr::from_ptr(pck_ca).to_str() {
+        Ok("processor") => CA::PROCESSOR,
+        Ok("platform") => CA::PLATFORM,
+        _ => return SGX_QL_ERROR_INVALID_PARAMETER,
+    };
+
+    // Never unwind into the caller
+    let collateral = std::panic::catch_unwind(|| {
+        let qpl = qpl().ok_or(SGX_QL_ERROR_UNEXPECTED)?;
+        qpl.runtime
+            .block_on(
+                qpl.reader
+                    .get_collateral(tee_type, qpl.quote_version, &fmspc, pck_ca),
+            )
+            .map_err(|_| SGX_QL_NO_QUOTE_COLLATERAL_DATA)
+    });
+    let collateral = match collateral {
+        Ok(Ok(collateral)) => collateral,
+        Ok(Err(e)) => return e,
+        Err(_) => return SGX_QL_ERROR_UNEXPECTED,
+    };
+
+    let buf = collateral.to_qve_bytes();
+    let layout = match Layout::from_size_align(buf.len(), align_of::<SgxQlQveCollateral>()) {
+        Ok(layout) => layout,
+        Err(_) => return SGX_QL_ERROR_UNEXPECTED,
+    };
+    let base = alloc(layout);
+    if base.is_null() {
+        return SGX_QL_ERROR_OUT_OF_MEMORY;
+    }
+    std::ptr::copy_nonoverlapping(buf.as_ptr(), base, buf.len());
+
+    // The serialized pointers are offsets into the buffer, rebase them
+    for (ptr_offset, _) in QVE_COLLATERAL_FIELDS {
+        let field = base.add(ptr_offset) as *mut usize;
+        *field += base as usize;
+    }
+
+    *pp_quote_collateral = base as *mut SgxQlQveCollateral;
+    SGX_QL_SUCCESS
+}
+
+unsafe fn free_quote_verification_collateral(
+    p_quote_collateral: *const SgxQlQveCollateral,
+) -> Quote3Error {
+    if p_quote_collateral.is_null() {
+        return SGX_QL_ERROR_INVALID_PARAMETER;
+    }
+
+    // The last string ends the allocation
+    let base = p_quote_collateral as *mut u8;
+    let (ptr_offset, size_offset) = QVE_COLLATERAL_FIELDS[QVE_COLLATERAL_FIELDS.len() - 1];
+    debug_assert_eq!(size_offset, offset_of!(SgxQlQveCollateral, qe_identity_size));
+    let last = *(base.add(ptr_offset) as *const usize);
+    let last_size = *(base.add(size_offset) as *const u32) as usize;
+    let size = last - base as usize + last_size;
+
+    match Layout::from_size_align(size, align_of::<SgxQlQveCollateral>()) {
+        Ok(layout) => {
+            dealloc(base, layout);
+            SGX_QL_SUCCESS
+        }
+        Err(_) => SGX_QL_ERROR_UNEXPECTED,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::primitives::Address;
+
+    #[test]
+    fn test_config() {
+        let config = QplConfig::from_json(
+            r#"{
+                // Same comment style as sgx_default_qcnl.conf
+                "network": "automata-mainnet",
+                "rpc_url": "http://127.0.0.1:8545",
+                "collateral_version": "v3",
+                "pcs_dao": "0x0000000000000000000000000000000000000001"
+            }"#,
+        )
+        .unwrap();
+        let network = config.network().unwrap();
+
+        assert_eq!(network.rpc_url, "http://127.0.0.1:8545");
+        assert_eq!(network.pcs_dao, Address::with_last_byte(1));
+        assert_eq!(network.pck_dao, NetworkProfile::automata_mainnet().pck_dao);
+        assert_eq!(config.quote_version().unwrap(), 3);
+    }
+}
@@ -0,0 +1,338 @@
+//! Typed views over the quote header and report bodies,
+//! as laid out in the Intel SGX and TDX DCAP quote formats.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
+
+pub const HEADER_LENGTH: usize = 48;
+pub const ENCLAVE_REPORT_LENGTH: usize = 384;
+pub const TD10_REPORT_LENGTH: usize = 584;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuoteHeader {
+    pub version: u16,
+    pub attestation_key_type: u16,
+    pub tee_type: u32,
+    // reserved in V4 quotes
+    pub qe_svn: u16,
+    // reserved in V4 quotes
+    pub pce_svn: u16,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub qe_vendor_id: [u8; 16],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub user_data: [u8; 20],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnclaveReport {
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub cpu_svn: [u8; 16],
+    pub misc_select: u32,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub attributes: [u8; 16],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_enclave: [u8; 32],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub report_data: [u8; 64],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Td10ReportBody {
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub tee_tcb_svn: [u8; 16],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_seam: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_signer_seam: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub seam_attributes: [u8; 8],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub td_attributes: [u8; 8],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub xfam: [u8; 8],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_td: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_config_id: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_owner: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub mr_owner_config: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub rt_mr0: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub rt_mr1: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub rt_mr2: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub rt_mr3: [u8; 48],
+    #[serde(serialize_with = "hex::serde::serialize")]
+    pub report_data: [u8; 64],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum QuoteBody {
+    SgxEnclaveReport(EnclaveReport),
+    Td10Report(Box<Td10ReportBody>),
+}
+
+impl QuoteHeader {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(raw, HEADER_LENGTH)?;
+
+        let header = QuoteHeader {
+            version: reader.u16(),
+            attestation_key_type: reader.u16(),
+            tee_type: reader.u32(),
+            qe_svn: reader.u16(),
+            pce_svn: reader.u16(),
+            qe_vendor_id: reader.array(),
+            user_data: reader.array(),
+        };
+
+        if !(3..=4).contains(&header.version) {
+            return Err(anyhow::Error::msg("Unsupported quote version"));
+        }
+
+        if header.tee_type != SGX_TEE_TYPE && header.tee_type != TDX_TEE_TYPE {
+            return Err(anyhow::Error::msg("Unsupported tee type"));
+        }
+
+        Ok(header)
+    }
+}
+
+impl EnclaveReport {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(raw, ENCLAVE_REPORT_LENGTH)?;
+
+        let cpu_svn = reader.array();
+        let misc_select = reader.u32();
+        reader.skip(28);
+        let attributes = reader.array();
+        let mr_enclave = reader.array();
+        reader.skip(32);
+        let mr_signer = reader.array();
+        reader.skip(96);
+        let isv_prod_id = reader.u16();
+        let isv_svn = reader.u16();
+        reader.skip(60);
+        let report_data = reader.array();
+
+        Ok(EnclaveReport {
+            cpu_svn,
+            misc_select,
+            attributes,
+            mr_enclave,
+            mr_signer,
+            isv_prod_id,
+            isv_svn,
+            report_data,
+        })
+    }
+
+    /// Lays the report out as in a quote, with the reserved fields zeroed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(ENCLAVE_REPORT_LENGTH);
+        raw.extend_from_slice(&self.cpu_svn);
+        raw.extend_from_slice(&self.misc_select.to_le_bytes());
+        raw.extend_from_slice(&[0; 28]);
+        raw.extend_from_slice(&self.attributes);
+        raw.extend_from_slice(&self.mr_enclave);
+        raw.extend_from_slice(&[0; 32]);
+        raw.extend_from_slice(&self.mr_signer);
+        raw.extend_from_slice(&[0; 96]);
+        raw.extend_from_slice(&self.isv_prod_id.to_le_bytes());
+        raw.extend_from_slice(&self.isv_svn.to_le_bytes());
+        raw.extend_from_slice(&[0; 60]);
+        raw.extend_from_slice(&self.report_data);
+        raw
+    }
+}
+
+impl Td10ReportBody {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(raw, TD10_REPORT_LENGTH)?;
+
+        Ok(Td10ReportBody {
+            tee_tcb_svn: reader.array(),
+            mr_seam: reader.array(),
+            mr_signer_seam: reader.array(),
+            seam_attributes: reader.array(),
+            td_attributes: reader.array(),
+            xfam: reader.array(),
+            mr_td: reader.array(),
+            mr_config_id: reader.array(),
+            mr_owner: reader.array(),
+            mr_owner_config: reader.array(),
+            rt_mr0: reader.array(),
+            rt_mr1: reader.array(),
+            rt_mr2: reader.array(),
+            rt_mr3: reader.array(),
+            report_data: reader.array(),
+        })
+    }
+
+    /// Lays the report out as in a quote.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.tee_tcb_svn[..],
+            &self.mr_seam,
+            &self.mr_signer_seam,
+            &self.seam_attributes,
+            &self.td_attributes,
+            &self.xfam,
+            &self.mr_td,
+            &self.mr_config_id,
+            &self.mr_owner,
+            &self.mr_owner_config,
+            &self.rt_mr0,
+            &self.rt_mr1,
+            &self.rt_mr2,
+            &self.rt_mr3,
+            &self.report_data,
+        ]
+        .concat()
+    }
+}
+
+impl QuoteBody {
+    /// Parses the report the TEE type of the quote calls for.
+    pub fn from_bytes(tee_type: u32, raw: &[u8]) -> Result<Self> {
+        Ok(if tee_type == TDX_TEE_TYPE {
+            QuoteBody::Td10Report(Box::new(Td10ReportBody::from_bytes(raw)?))
+        } else {
+            QuoteBody::SgxEnclaveReport(EnclaveReport::from_bytes(raw)?)
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            QuoteBody::SgxEnclaveReport(report) => report.to_bytes(),
+            QuoteBody::Td10Report(report) => report.to_bytes(),
+        }
+    }
+}
+
+/// Parses the header and the report body of a V3 or V4 quote.
+pub fn parse_quote(raw_quote: &[u8]) -> Result<(QuoteHeader, QuoteBody)> {
+    let header = QuoteHeader::from_bytes(raw_quote)?;
+    let body = QuoteBody::from_bytes(header.tee_type, &raw_quote[HEADER_LENGTH..])?;
+
+    Ok((header, body))
+}
+
+// Little endian reader over a slice whose length has already been checked
+struct ByteReader<'a> {
+    raw: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(raw: &'a [u8], length: usize) -> Result<Self> {
+        if raw.len() < length {
+            return Err(anyhow::anyhow!(
+                "expected at least {} bytes, found {}",
+                length,
+                raw.len()
+            ));
+        }
+        Ok(ByteReader { raw: &raw[..length], offset: 0 })
+    }
+
+    fn array<const N: usize>(&mut self) -> [u8; N] {
+        let bytes = self.raw[self.offset..self.offset + N].try_into().unwrap();
+        self.offset += N;
+        bytes
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.offset += n;
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.array())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.array())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header(version: u16, tee_type: u32) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        header.extend_from_slice(&version.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&tee_type.to_le_bytes());
+        header.extend_from_slice(&[0x0b, 0x00, 0x0d, 0x00]);
+        header.extend_from_slice(&[0x93; 16]);
+        header.extend_from_slice(&[0xee; 20]);
+        header
+    }
+
+    #[test]
+    fn test_parse_sgx_quote() {
+        let mut quote = header(3, SGX_TEE_TYPE);
+        let mut report = vec![0u8; ENCLAVE_REPORT_LENGTH];
+        report[64..96].copy_from_slice(&[0xaa; 32]);
+        report[128..160].copy_from_slice(&[0xbb; 32]);
+        report[256..258].copy_from_slice(&1u16.to_le_bytes());
+        report[258..260].copy_from_slice(&7u16.to_le_bytes());
+        report[320..].copy_from_slice(&[0xcc; 64]);
+        quote.extend_from_slice(&report);
+
+        let (header, body) = parse_quote(&quote).unwrap();
+        assert_eq!(body.to_bytes(), quote[HEADER_LENGTH..]);
+        assert_eq!(header.version, 3);
+        assert_eq!(header.qe_svn, 11);
+        assert_eq!(header.pce_svn, 13);
+        assert_eq!(header.qe_vendor_id, [0x93; 16]);
+
+        let QuoteBody::SgxEnclaveReport(report) = body else {
+            panic!("expected an enclave report");
+        };
+        assert_eq!(report.mr_enclave, [0xaa; 32]);
+        assert_eq!(report.mr_signer, [0xbb; 32]);
+        assert_eq!(report.isv_prod_id, 1);
+        assert_eq!(report.isv_svn, 7);
+        assert_eq!(report.report_data, [0xcc; 64]);
+    }
+
+    #[test]
+    fn test_parse_tdx_quote() {
+        let mut quote = header(4, TDX_TEE_TYPE);
+        let mut report = vec![0u8; TD10_REPORT_LENGTH];
+        report[136..184].copy_from_slice(&[0xdd; 48]);
+        report[520..].copy_from_slice(&[0xcc; 64]);
+        quote.extend_from_slice(&report);
+
+        let (_, body) = parse_quote(&quote).unwrap();
+        assert_eq!(body.to_bytes(), quote[HEADER_LENGTH..]);
+        let QuoteBody::Td10Report(report) = body else {
+            panic!("expected a TD report");
+        };
+        assert_eq!(report.mr_td, [0xdd; 48]);
+        assert_eq!(report.report_data, [0xcc; 64]);
+
+        // truncated body
+        assert!(parse_quote(&quote[..HEADER_LENGTH + 100]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_quotes() {
+        assert!(QuoteHeader::from_bytes(&header(5, SGX_TEE_TYPE)).is_err());
+        assert!(QuoteHeader::from_bytes(&header(4, 0x80)).is_err());
+        assert!(QuoteHeader::from_bytes(&[0x03, 0x00]).is_err());
+    }
+}
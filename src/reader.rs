@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use alloy::{
+    contract::{CallBuilder, CallDecoder},
+    eips::{BlockId, BlockNumberOrTag},
+    network::Network,
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::types::state::StateOverride,
+    transports::{BoxTransport, Transport},
+};
+
+use crate::cache::CollateralCache;
+use crate::network::NetworkProfile;
+use crate::resolve::ResolvedAddresses;
+use crate::rpc;
+
+/// Reads collateral from the PCCS DAOs of a single network.
+/// The DAO specific calls live next to their bindings under [`crate::pccs`].
+#[derive(Debug, Clone)]
+pub struct PccsReader {
+    network: NetworkProfile,
+    provider: RootProvider<BoxTransport>,
+    // every DAO call is made at this block
+    block: BlockId,
+    cache: Option<Arc<CollateralCache>>,
+    // false once an explicit block is asked for, the cache only holds recent state
+    follows_head: bool,
+    // applied to every DAO call, to read a state that is not on chain
+    overrides: Option<Arc<StateOverride>>,
+    // the addresses the network profile was updated with, when resolved on chain
+    resolved: Option<Arc<ResolvedAddresses>>,
+}
+
+impl PccsReader {
+    pub fn new(network: NetworkProfile) -> Result<Self> {
+        let client = rpc::client(&network)?;
+        let provider = ProviderBuilder::new().on_client(client);
+
+        Ok(PccsReader {
+            network,
+            provider,
+            block: BlockId::latest(),
+            cache: None,
+            follows_head: true,
+            overrides: None,
+            resolved: None,
+        })
+    }
+
+    pub fn network(&self) -> &NetworkProfile {
+        &self.network
+    }
+
+    pub fn provider(&self) -> &RootProvider<BoxTransport> {
+        &self.provider
+    }
+
+    pub fn block(&self) -> BlockId {
+        self.block
+    }
+
+    /// The addresses resolved from the PCCS router, None when the profile is used as is.
+    pub fn resolved_addresses(&self) -> Option<&ResolvedAddresses> {
+        self.resolved.as_deref()
+    }
+
+    // Same RPC, with the addresses of the profile replaced by resolved ones
+    pub(crate) fn with_resolved(&self, network: NetworkProfile, resolved: ResolvedAddresses) -> Self {
+        PccsReader {
+            network,
+            resolved: Some(Arc::new(resolved)),
+            ..self.clone()
+        }
+    }
+
+    /// A reader whose calls are all made at the given block number, tag or hash.
+    /// Reads at a block other than the latest one bypass the cache.
+    pub fn at_block(&self, block: impl Into<BlockId>) -> Self {
+        let block = block.into();
+        PccsReader {
+            block,
+            follows_head: block == BlockId::latest(),
+            ..self.clone()
+        }
+    }
+
+    /// A reader that looks the collateral up in the given cache before reading the DAOs.
+    pub fn with_cache(self, cache: Arc<CollateralCache>) -> Self {
+        PccsReader {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    pub fn cache(&self) -> Option<&Arc<CollateralCache>> {
+        self.cache.as_ref()
+    }
+
+    /// A reader whose calls are all made over the given `eth_call` state overrides.
+    /// Reads over overrides bypass the cache, they do not reflect the chain.
+    pub fn with_state_overrides(self, overrides: StateOverride) -> Self {
+        PccsReader {
+            overrides: Some(Arc::new(overrides)),
+            ..self
+        }
+    }
+
+    pub fn state_overrides(&self) -> Option<&StateOverride> {
+        self.overrides.as_deref()
+    }
+
+    // The cache, unless reading at an explicit block or over state overrides
+    pub(crate) fn live_cache(&self) -> Option<&CollateralCache> {
+        self.cache
+            .as_deref()
+            .filter(|_| self.follows_head && self.overrides.is_none())
+    }
+
+    // Makes a call at the block of the reader, over its state overrides if any
+    pub(crate) fn prepare_call<T, P, D, N>(
+        &self,
+        call: CallBuilder<T, P, D, N>,
+    ) -> CallBuilder<T, P, D, N>
+    where
+        T: Transport + Clone,
+        P: Provider<T, N>,
+        D: CallDecoder,
+        N: Network,
+    {
+        let call = call.block(self.block);
+        match &self.overrides {
+            Some(overrides) => call.state(StateOverride::clone(overrides)),
+            None => call,
+        }
+    }
+
+    /// A reader pinned to the block number the current tag resolves to, so that
+    /// a sequence of calls sees a single state. Numbers and hashes are kept as they are.
+    /// A reader pinned from the latest block keeps using the cache, for the entries read
+    /// at its block or before it.
+    pub async fn pin_block(&self) -> Result<Self> {
+        let number = match self.block {
+            BlockId::Number(BlockNumberOrTag::Latest) => self.provider.get_block_number().await?,
+            BlockId::Number(BlockNumberOrTag::Number(_)) | BlockId::Hash(_) => {
+                return Ok(self.clone())
+            }
+            BlockId::Number(tag) => self
+                .provider
+                .get_block_by_number(tag, false)
+                .await?
+                .and_then(|block| block.header.number)
+                .ok_or_else(|| anyhow::anyhow!("No block for {}", tag))?,
+        };
+        Ok(PccsReader {
+            block: BlockId::number(number),
+            ..self.clone()
+        })
+    }
+}
+
+impl Default for PccsReader {
+    fn default() -> Self {
+        PccsReader::new(NetworkProfile::default()).expect("Failed to parse RPC URL")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::primitives::B256;
+
+    #[tokio::test]
+    async fn test_block_pinning() {
+        let reader = PccsReader::default();
+        assert_eq!(reader.block(), BlockId::latest());
+
+        // concrete blocks are pinned already, no RPC needed
+        let at_number = reader.at_block(1234);
+        assert_eq!(at_number.pin_block().await.unwrap().block(), BlockId::number(1234));
+
+        let hash = B256::repeat_byte(0xab);
+        let at_hash = reader.at_block(hash);
+        assert_eq!(at_hash.pin_block().await.unwrap().block(), BlockId::hash(hash));
+
+        // the original reader is left untouched
+        assert_eq!(reader.block(), BlockId::latest());
+    }
+}
@@ -0,0 +1,419 @@
+//! Keeps the collateral of a watch list fresh on chain: each round compares the
+//! on-chain PCCS with a [`CollateralSource`] and upserts whatever is missing or behind.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{keccak256, Address, TxHash, B256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    transports::Transport,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{from_commented_json, NetworkConfig};
+use crate::freshness::{Freshness, VersionedCollateral};
+use crate::pccs::enclave_id::EnclaveIdType;
+use crate::source::intel_pcs::INTEL_PCS_URL;
+use crate::source::{CollateralSource, DirectoryCollateralSource, IntelPcsCollateralSource};
+use crate::writer::UpsertCall;
+use crate::{MissingCollateral, PccsReader, CA};
+
+pub const DEFAULT_STATE_FILE: &str = "pccs-relayer.state.json";
+pub const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RelayerConfig {
+    #[serde(flatten)]
+    pub network: NetworkConfig,
+    // base URL of the Intel PCS API, unused when source_dir is set
+    pub pcs_url: String,
+    pub source_dir: Option<PathBuf>,
+    pub watch: Vec<WatchedFmspc>,
+    pub identities: Vec<WatchedIdentity>,
+    pub interval_secs: u64,
+    pub state_file: PathBuf,
+    // EIP-1559 fees in wei, estimated from the fee history when unset
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchedFmspc {
+    pub fmspc: String,
+    // 0 for SGX, 1 for TDX
+    #[serde(default)]
+    pub tcb_type: u8,
+    #[serde(default = "default_tcb_version")]
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchedIdentity {
+    pub id: EnclaveIdType,
+    pub version: u32,
+}
+
+fn default_tcb_version() -> u32 {
+    3
+}
+
+impl Default for RelayerConfig {
+    fn default() -> Self {
+        RelayerConfig {
+            network: NetworkConfig::default(),
+            pcs_url: INTEL_PCS_URL.to_string(),
+            source_dir: None,
+            watch: vec![],
+            identities: vec![
+                WatchedIdentity { id: EnclaveIdType::QE, version: 3 },
+                WatchedIdentity { id: EnclaveIdType::QE, version: 4 },
+                WatchedIdentity { id: EnclaveIdType::TDQE, version: 4 },
+            ],
+            interval_secs: DEFAULT_INTERVAL_SECS,
+            state_file: PathBuf::from(DEFAULT_STATE_FILE),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        }
+    }
+}
+
+impl RelayerConfig {
+    pub fn from_json(json: &str) -> Result<Self> {
+        from_commented_json(json)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn source(&self) -> Box<dyn CollateralSource> {
+        match &self.source_dir {
+            Some(dir) => Box::new(DirectoryCollateralSource::new(dir)),
+            None => Box::new(IntelPcsCollateralSource::new(&self.pcs_url)),
+        }
+    }
+
+    // The CRLs, TCB Info and identities kept fresh, issuers first
+    fn versioned_collaterals(&self) -> Vec<VersionedCollateral> {
+        let crls = [CA::ROOT, CA::PROCESSOR, CA::PLATFORM].map(VersionedCollateral::Crl);
+        let identities = self
+            .identities
+            .iter()
+            .map(|identity| VersionedCollateral::EnclaveIdentity(identity.id, identity.version));
+        let tcb_infos = self.watch.iter().map(|watched| {
+            VersionedCollateral::TcbInfo(
+                watched.tcb_type,
+                watched.fmspc.to_ascii_lowercase(),
+                watched.version,
+            )
+        });
+        crls.into_iter().chain(identities).chain(tcb_infos).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubmissionStatus {
+    Pending,
+    Mined,
+    Reverted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Submission {
+    pub collateral: String,
+    pub tx_hash: TxHash,
+    pub nonce: u64,
+    pub status: SubmissionStatus,
+}
+
+/// Upserts already sent, keyed by the hash of their target and calldata,
+/// so that a restart does not send the same collateral twice.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayerState {
+    pub submissions: BTreeMap<B256, Submission>,
+}
+
+impl RelayerState {
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::anyhow!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    // Written aside then renamed, so that a crash never leaves a truncated file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn is_submitted(&self, call: &UpsertCall) -> bool {
+        self.submissions
+            .get(&call_hash(call))
+            .is_some_and(|submission| submission.status != SubmissionStatus::Reverted)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RoundSummary {
+    // every upsert needed this round, including those already submitted
+    pub upserts: Vec<UpsertCall>,
+    pub submitted: Vec<TxHash>,
+    pub skipped: usize,
+    // collateral that could not be compared, with the reason
+    pub errors: Vec<(VersionedCollateral, String)>,
+}
+
+pub struct Relayer {
+    reader: PccsReader,
+    source: Box<dyn CollateralSource>,
+    config: RelayerConfig,
+    // None runs dry, nothing is signed nor sent
+    signer: Option<PrivateKeySigner>,
+    state: RelayerState,
+}
+
+impl Relayer {
+    pub fn new(
+        config: RelayerConfig,
+        source: Box<dyn CollateralSource>,
+        signer: Option<PrivateKeySigner>,
+    ) -> Result<Self> {
+        let reader = PccsReader::new(config.network.profile()?)?;
+        let state = match signer {
+            Some(_) => RelayerState::load(&config.state_file)?,
+            None => RelayerState::default(),
+        };
+
+        Ok(Relayer {
+            reader,
+            source,
+            config,
+            signer,
+            state,
+        })
+    }
+
+    /// Reads the DAO addresses from the PCCS router when the configuration asks for it.
+    pub async fn resolve_addresses(&mut self) -> Result<()> {
+        if self.config.network.resolve_addresses {
+            self.reader = self.reader.resolve_addresses().await?;
+        }
+        Ok(())
+    }
+
+    pub fn reader(&self) -> &PccsReader {
+        &self.reader
+    }
+
+    pub fn config(&self) -> &RelayerConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &RelayerState {
+        &self.state
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.signer.is_none()
+    }
+
+    pub async fn run_once(&mut self) -> Result<RoundSummary> {
+        let mut summary = RoundSummary::default();
+
+        // Step 1: certificates only need to be present
+        let mut missing = Vec::new();
+        for ca in [CA::ROOT, CA::SIGNING, CA::PROCESSOR, CA::PLATFORM] {
+            let (cert, _) = self.reader.get_certificate_by_id(ca).await?;
+            if cert.is_empty() {
+                missing.push(MissingCollateral::PCS(ca, true, false));
+            }
+        }
+
+        // Step 2: everything else must be as fresh as the source
+        for collateral in self.config.versioned_collaterals() {
+            let reports = self
+                .reader
+                .compare_with_source(std::slice::from_ref(&collateral), self.source.as_ref())
+                .await;
+            match reports {
+                Ok(reports) => {
+                    let stale = reports.iter().any(|report| {
+                        matches!(report.freshness, Freshness::Behind | Freshness::MissingOnChain)
+                    });
+                    if stale {
+                        missing.push(to_missing(collateral));
+                    }
+                }
+                Err(e) => summary.errors.push((collateral, e.to_string())),
+            }
+        }
+
+        summary.upserts = self.reader.upsert_calls(&missing, self.source.as_ref()).await?;
+
+        let Some(signer) = self.signer.clone() else {
+            return Ok(summary);
+        };
+
+        // Step 3: send what has not been sent yet, with locally tracked nonces
+        let from = signer.address();
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer))
+            .on_http(self.reader.network().rpc_url.parse()?);
+
+        self.reconcile(&provider, from).await?;
+
+        let pending: Vec<&UpsertCall> = summary
+            .upserts
+            .iter()
+            .filter(|call| !self.state.is_submitted(call))
+            .collect();
+        summary.skipped = summary.upserts.len() - pending.len();
+        if pending.is_empty() {
+            return Ok(summary);
+        }
+
+        let mut nonce = provider.get_transaction_count(from).pending().await?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) =
+            match (self.config.max_fee_per_gas, self.config.max_priority_fee_per_gas) {
+                (Some(max_fee), Some(priority_fee)) => (max_fee, priority_fee),
+                (max_fee, priority_fee) => {
+                    let estimate = provider.estimate_eip1559_fees(None).await?;
+                    (
+                        max_fee.unwrap_or(estimate.max_fee_per_gas),
+                        priority_fee.unwrap_or(estimate.max_priority_fee_per_gas),
+                    )
+                }
+            };
+
+        for call in pending {
+            let request = call
+                .to_request()
+                .with_nonce(nonce)
+                .with_max_fee_per_gas(max_fee_per_gas)
+                .with_max_priority_fee_per_gas(max_priority_fee_per_gas);
+            let tx_hash = *provider.send_transaction(request).await?.tx_hash();
+
+            self.state.submissions.insert(
+                call_hash(call),
+                Submission {
+                    collateral: call.kind.to_string(),
+                    tx_hash,
+                    nonce,
+                    status: SubmissionStatus::Pending,
+                },
+            );
+            self.state.save(&self.config.state_file)?;
+
+            summary.submitted.push(tx_hash);
+            nonce += 1;
+        }
+
+        Ok(summary)
+    }
+
+    // Settles pending submissions: mined or reverted once they have a receipt,
+    // forgotten once their nonce is used without one (replaced or dropped)
+    async fn reconcile<P: Provider<T>, T: Transport + Clone>(
+        &mut self,
+        provider: &P,
+        from: Address,
+    ) -> Result<()> {
+        let mined_nonce = provider.get_transaction_count(from).latest().await?;
+
+        let pending: Vec<(B256, Submission)> = self
+            .state
+            .submissions
+            .iter()
+            .filter(|(_, submission)| submission.status == SubmissionStatus::Pending)
+            .map(|(hash, submission)| (*hash, submission.clone()))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        for (hash, submission) in pending {
+            match provider.get_transaction_receipt(submission.tx_hash).await? {
+                Some(receipt) => {
+                    let status = if receipt.status() {
+                        SubmissionStatus::Mined
+                    } else {
+                        SubmissionStatus::Reverted
+                    };
+                    if let Some(submission) = self.state.submissions.get_mut(&hash) {
+                        submission.status = status;
+                    }
+                }
+                None if submission.nonce < mined_nonce => {
+                    self.state.submissions.remove(&hash);
+                }
+                None => {}
+            }
+        }
+
+        self.state.save(&self.config.state_file)
+    }
+}
+
+fn to_missing(collateral: VersionedCollateral) -> MissingCollateral {
+    match collateral {
+        VersionedCollateral::Crl(ca) => MissingCollateral::PCS(ca, false, true),
+        VersionedCollateral::TcbInfo(tcb_type, fmspc, version) => {
+            MissingCollateral::FMSPCTCB(tcb_type, fmspc, version)
+        }
+        VersionedCollateral::EnclaveIdentity(id, version) => {
+            MissingCollateral::QEIdentity(id, version)
+        }
+    }
+}
+
+fn call_hash(call: &UpsertCall) -> B256 {
+    keccak256([call.to.as_slice(), call.input.as_ref()].concat())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = RelayerConfig::from_json(
+            r#"{
+                "network": "automata-mainnet",
+                // kept fresh on top of the CRLs and identities
+                "watch": [
+                    { "fmspc": "00606A000000" },
+                    { "fmspc": "90c06f000000", "tcb_type": 1 }
+                ],
+                "interval_secs": 600,
+                "max_priority_fee_per_gas": 1000000000
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.interval_secs, 600);
+        assert_eq!(config.state_file, PathBuf::from(DEFAULT_STATE_FILE));
+        assert_eq!(config.max_priority_fee_per_gas, Some(1_000_000_000));
+        assert_eq!(config.network.profile().unwrap().chain_id, 65536);
+
+        let collaterals = config.versioned_collaterals();
+        assert_eq!(collaterals.len(), 8);
+        assert_eq!(collaterals[0], VersionedCollateral::Crl(CA::ROOT));
+        assert_eq!(
+            collaterals[7],
+            VersionedCollateral::TcbInfo(1, "90c06f000000".to_string(), 3)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Resolves the current DAO addresses from the PCCS router, so that a reader only needs
+//! the router (or the DCAP attestation entrypoint) and follows Automata's redeployments.
+
+use alloy::{primitives::Address, sol};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{NetworkProfile, PccsReader};
+
+sol! {
+    #[sol(rpc)]
+    interface IPCCSRouter {
+        function qeIdDaoAddr() external view returns (address);
+        function fmspcTcbDaoAddr() external view returns (address);
+        function pckDaoAddr() external view returns (address);
+        function pcsDaoAddr() external view returns (address);
+        function pckHelperAddr() external view returns (address);
+        function crlHelperAddr() external view returns (address);
+        function fmspcTcbHelperAddr() external view returns (address);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IAttestationEntrypoint {
+        function quoteVerifiers(uint16 quoteVersion) external view returns (address);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IQuoteVerifier {
+        function pccsRouter() external view returns (address);
+    }
+}
+
+// The verifiers share the router, any of them leads to it
+const QUOTE_VERSIONS: [u16; 2] = [4, 3];
+
+/// What the PCCS router points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ResolvedAddresses {
+    pub pccs_router: Address,
+    pub enclave_id_dao: Address,
+    pub fmspc_tcb_dao: Address,
+    pub pcs_dao: Address,
+    pub pck_dao: Address,
+    pub fmspc_tcb_helper: Address,
+    pub x509_helper: Address,
+    pub x509_crl_helper: Address,
+}
+
+impl std::fmt::Display for ResolvedAddresses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "PCCS_ROUTER: {}", self.pccs_router)?;
+        writeln!(f, "ENCLAVE_ID_DAO: {}", self.enclave_id_dao)?;
+        writeln!(f, "FMSPC_TCB_DAO: {}", self.fmspc_tcb_dao)?;
+        writeln!(f, "PCS_DAO: {}", self.pcs_dao)?;
+        writeln!(f, "PCK_DAO: {}", self.pck_dao)?;
+        writeln!(f, "FMSPC_TCB_HELPER: {}", self.fmspc_tcb_helper)?;
+        writeln!(f, "X509_HELPER: {}", self.x509_helper)?;
+        write!(f, "X509_CRL_HELPER: {}", self.x509_crl_helper)
+    }
+}
+
+pub async fn resolve_addresses() -> Result<ResolvedAddresses> {
+    PccsReader::default().read_router().await
+}
+
+impl PccsReader {
+    /// A reader for `network` whose DAO addresses are read from `pccs_router`.
+    pub async fn from_router(network: NetworkProfile, pccs_router: Address) -> Result<Self> {
+        let network = NetworkProfile {
+            pccs_router,
+            ..network
+        };
+        PccsReader::new(network)?.resolve_addresses().await
+    }
+
+    /// A reader for `network` whose DAO addresses are read from the router
+    /// the quote verifiers of `dcap_attestation` use.
+    pub async fn from_dcap_attestation(
+        network: NetworkProfile,
+        dcap_attestation: Address,
+    ) -> Result<Self> {
+        let network = NetworkProfile {
+            dcap_attestation,
+            ..network
+        };
+        let reader = PccsReader::new(network)?;
+        let pccs_router = reader.resolve_router().await?;
+        PccsReader::from_router(reader.network().clone(), pccs_router).await
+    }
+
+    /// The same reader, with the DAO and helper addresses of its profile replaced by
+    /// what the PCCS router of the profile currently points to. The addresses are read
+    /// once, they are kept by the reader and its clones.
+    pub async fn resolve_addresses(&self) -> Result<Self> {
+        let resolved = self.pin_block().await?.read_router().await?;
+
+        let mut network = self.network().clone();
+        network.enclave_id_dao = resolved.enclave_id_dao;
+        network.fmspc_tcb_dao = resolved.fmspc_tcb_dao;
+        network.pcs_dao = resolved.pcs_dao;
+        network.pck_dao = resolved.pck_dao;
+        // the router does not know the Enclave Identity helper, which is kept
+        if let Some(helpers) = &mut network.helpers {
+            helpers.fmspc_tcb = resolved.fmspc_tcb_helper;
+            helpers.x509 = resolved.x509_helper;
+            helpers.x509_crl = resolved.x509_crl_helper;
+        }
+        Ok(self.with_resolved(network, resolved))
+    }
+
+    /// The PCCS router of the quote verifiers registered in the DCAP attestation entrypoint.
+    pub async fn resolve_router(&self) -> Result<Address> {
+        let entrypoint =
+            IAttestationEntrypoint::new(self.network().dcap_attestation, self.provider());
+        for version in QUOTE_VERSIONS {
+            let call_builder = entrypoint.quoteVerifiers(version);
+            let verifier = self.prepare_call(call_builder).call().await?._0;
+            if verifier.is_zero() {
+                continue;
+            }
+
+            let verifier_contract = IQuoteVerifier::new(verifier, self.provider());
+            let call_builder = verifier_contract.pccsRouter();
+            let pccs_router = self.prepare_call(call_builder).call().await?._0;
+            if !pccs_router.is_zero() {
+                return Ok(pccs_router);
+            }
+        }
+        Err(anyhow::anyhow!(
+            "No quote verifier of {} has a PCCS router",
+            self.network().dcap_attestation
+        ))
+    }
+
+    async fn read_router(&self) -> Result<ResolvedAddresses> {
+        let router = IPCCSRouter::new(self.network().pccs_router, self.provider());
+        let nonzero = |name: &str, address: Address| {
+            match address.is_zero() {
+                true => Err(anyhow::anyhow!("The PCCS router has no {}", name)),
+                false => Ok(address),
+            }
+        };
+
+        let enclave_id_dao = self.prepare_call(router.qeIdDaoAddr()).call().await?._0;
+        let fmspc_tcb_dao = self.prepare_call(router.fmspcTcbDaoAddr()).call().await?._0;
+        let pcs_dao = self.prepare_call(router.pcsDaoAddr()).call().await?._0;
+        let pck_dao = self.prepare_call(router.pckDaoAddr()).call().await?._0;
+        let fmspc_tcb_helper = self.prepare_call(router.fmspcTcbHelperAddr()).call().await?._0;
+        let x509_helper = self.prepare_call(router.pckHelperAddr()).call().await?._0;
+        let x509_crl_helper = self.prepare_call(router.crlHelperAddr()).call().await?._0;
+
+        Ok(ResolvedAddresses {
+            pccs_router: self.network().pccs_router,
+            enclave_id_dao: nonzero("Enclave ID DAO", enclave_id_dao)?,
+            fmspc_tcb_dao: nonzero("FMSPC TCB DAO", fmspc_tcb_dao)?,
+            pcs_dao: nonzero("PCS DAO", pcs_dao)?,
+            pck_dao: nonzero("PCK DAO", pck_dao)?,
+            fmspc_tcb_helper,
+            x509_helper,
+            x509_crl_helper,
+        })
+    }
+}
@@ -0,0 +1,304 @@
+//! The JSON-RPC transport of the reader: per-call timeouts, retries with exponential
+//! backoff and jitter, failover across the RPC URLs of a network profile, and an
+//! optional quorum of endpoints that must return the same answer.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::Http;
+use alloy::transports::{
+    BoxTransport, RpcError, Transport, TransportError, TransportErrorKind, TransportFut,
+};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer};
+
+use crate::NetworkProfile;
+
+/// How the reader talks to the RPC endpoints of a network.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RpcPolicy {
+    // limit for a single request to a single endpoint
+    #[serde(rename = "timeout_ms", deserialize_with = "millis")]
+    pub timeout: Duration,
+    // rounds over the endpoints after the first one, only for retryable errors
+    pub max_retries: u32,
+    #[serde(rename = "initial_backoff_ms", deserialize_with = "millis")]
+    pub initial_backoff: Duration,
+    #[serde(rename = "max_backoff_ms", deserialize_with = "millis")]
+    pub max_backoff: Duration,
+    // number of endpoints that must return the same answer, 1 takes the first answer
+    pub quorum: usize,
+}
+
+impl Default for RpcPolicy {
+    fn default() -> Self {
+        RpcPolicy {
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            quorum: 1,
+        }
+    }
+}
+
+impl RpcPolicy {
+    // Exponential backoff with up to 50% of random jitter
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        backoff + backoff.mul_f64(jitter as f64 / 2000.0)
+    }
+}
+
+fn millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+}
+
+/// The RPC client for a network profile, over every one of its RPC URLs.
+pub fn client(network: &NetworkProfile) -> Result<RpcClient<BoxTransport>> {
+    let endpoints = std::iter::once(&network.rpc_url)
+        .chain(&network.fallback_rpc_urls)
+        .map(|url| Ok(Http::new(url.parse()?)))
+        .collect::<Result<Vec<_>>>()?;
+    if network.rpc.quorum > endpoints.len() {
+        return Err(anyhow::anyhow!(
+            "A quorum of {} needs as many RPC URLs, the {} network has {}",
+            network.rpc.quorum,
+            network.name,
+            endpoints.len()
+        ));
+    }
+    let is_local = endpoints.iter().all(|endpoint| endpoint.guess_local());
+
+    let transport = ResilientTransport {
+        endpoints,
+        policy: network.rpc.clone(),
+    };
+    Ok(RpcClient::new(transport.boxed(), is_local))
+}
+
+#[derive(Debug, Clone)]
+pub struct ResilientTransport {
+    endpoints: Vec<Http<reqwest::Client>>,
+    policy: RpcPolicy,
+}
+
+impl tower::Service<RequestPacket> for ResilientTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move { this.send(request).await })
+    }
+}
+
+// Why an endpoint, or a round over all of them, gave no answer
+struct Failure {
+    error: TransportError,
+    retryable: bool,
+}
+
+impl Failure {
+    // Keeps the last error, retryable when any endpoint failed in a retryable way
+    fn after(self, previous: Option<Failure>) -> Failure {
+        Failure {
+            retryable: self.retryable || previous.is_some_and(|previous| previous.retryable),
+            error: self.error,
+        }
+    }
+}
+
+impl ResilientTransport {
+    async fn send(&self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let mut retry = 0;
+        loop {
+            let round = match self.policy.quorum {
+                0 | 1 => self.first_answer(&request).await,
+                quorum => self.quorum_answer(&request, quorum).await,
+            };
+            match round {
+                Ok(response) => return Ok(response),
+                Err(failure) if !failure.retryable || retry >= self.policy.max_retries => {
+                    return Err(failure.error)
+                }
+                Err(_) => {
+                    tokio::time::sleep(self.policy.backoff(retry)).await;
+                    retry += 1;
+                }
+            }
+        }
+    }
+
+    // The first endpoint to answer, in order
+    async fn first_answer(&self, request: &RequestPacket) -> Result<ResponsePacket, Failure> {
+        let mut failure = None;
+        for endpoint in &self.endpoints {
+            match self.attempt(endpoint, request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(error) => failure = Some(error.after(failure)),
+            }
+        }
+        Err(failure.unwrap_or(Failure {
+            error: TransportErrorKind::custom_str("no RPC endpoint configured"),
+            retryable: false,
+        }))
+    }
+
+    // The answer of at least `quorum` endpoints, all queried at once
+    async fn quorum_answer(
+        &self,
+        request: &RequestPacket,
+        quorum: usize,
+    ) -> Result<ResponsePacket, Failure> {
+        let attempts = self
+            .endpoints
+            .iter()
+            .map(|endpoint| self.attempt(endpoint, request.clone()));
+        let results = futures::future::join_all(attempts).await;
+
+        let mut votes: HashMap<String, (usize, ResponsePacket)> = HashMap::new();
+        let mut failure = None;
+        for result in results {
+            match result {
+                Ok(response) => {
+                    let vote = votes.entry(answer_key(&response)).or_insert((0, response));
+                    vote.0 += 1;
+                }
+                Err(error) => failure = Some(error.after(failure)),
+            }
+        }
+
+        if let Some((_, response)) = votes.into_values().find(|(count, _)| *count >= quorum) {
+            return Ok(response);
+        }
+        Err(match failure {
+            // endpoints may catch up with each other, so disagreement is worth retrying
+            None => Failure {
+                error: TransportErrorKind::custom_str("no quorum among the RPC endpoints"),
+                retryable: true,
+            },
+            Some(Failure { error, retryable }) => Failure {
+                error: TransportErrorKind::custom_str(&format!(
+                    "no quorum among the RPC endpoints, last error: {}",
+                    error
+                )),
+                retryable,
+            },
+        })
+    }
+
+    async fn attempt(
+        &self,
+        endpoint: &Http<reqwest::Client>,
+        request: RequestPacket,
+    ) -> Result<ResponsePacket, Failure> {
+        let mut endpoint = endpoint.clone();
+        let call = tower::Service::call(&mut endpoint, request);
+        let response = match tokio::time::timeout(self.policy.timeout, call).await {
+            Ok(response) => response,
+            Err(_) => {
+                return Err(Failure {
+                    error: TransportErrorKind::custom_str(&format!(
+                        "request to {} timed out",
+                        endpoint.url()
+                    )),
+                    retryable: true,
+                })
+            }
+        };
+
+        match response {
+            // rate limits come back as error payloads too
+            Ok(response) => match response.as_error() {
+                Some(payload) if payload.is_retry_err() => Err(Failure {
+                    error: RpcError::ErrorResp(payload.clone()),
+                    retryable: true,
+                }),
+                _ => Ok(response),
+            },
+            Err(error) => Err(Failure {
+                retryable: is_retryable(&error),
+                error,
+            }),
+        }
+    }
+}
+
+// Rate limits, server errors and connection failures may go away on their own
+fn is_retryable(error: &TransportError) -> bool {
+    match error {
+        RpcError::Transport(TransportErrorKind::HttpError(http)) => {
+            http.status == 429 || http.status >= 500
+        }
+        RpcError::Transport(TransportErrorKind::Custom(_)) => true,
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        RpcError::NullResp => true,
+        _ => false,
+    }
+}
+
+// Endpoints agree when they return the same payloads
+fn answer_key(response: &ResponsePacket) -> String {
+    match response {
+        ResponsePacket::Single(single) => serde_json::to_string(single),
+        ResponsePacket::Batch(batch) => serde_json::to_string(batch),
+    }
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_up_to_the_limit() {
+        let policy = RpcPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        };
+
+        for (retry, base) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000), (30, 1000)] {
+            let backoff = policy.backoff(retry);
+            let base = Duration::from_millis(base);
+            assert!(backoff >= base && backoff <= base.mul_f64(1.5), "{:?}", backoff);
+        }
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: RpcPolicy =
+            serde_json::from_str(r#"{"timeout_ms": 2500, "quorum": 2}"#).unwrap();
+        assert_eq!(policy.timeout, Duration::from_millis(2500));
+        assert_eq!(policy.quorum, 2);
+        assert_eq!(policy.max_retries, RpcPolicy::default().max_retries);
+    }
+
+    #[test]
+    fn test_quorum_needs_enough_endpoints() {
+        let mut network = NetworkProfile::default().with_rpc_url("http://localhost:8545");
+        network.fallback_rpc_urls = vec!["http://localhost:8546".to_string()];
+        network.rpc.quorum = 2;
+        assert!(client(&network).is_ok());
+
+        network.rpc.quorum = 3;
+        let err = client(&network).unwrap_err();
+        assert!(err.to_string().contains("quorum of 3"), "{}", err);
+    }
+}
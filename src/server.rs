@@ -0,0 +1,333 @@
+//! An Intel PCCS compatible REST API served from the on-chain PCCS.
+//! Only the endpoints needed for quote verification are exposed,
+//! PCK certificates are expected to be embedded in the quote (cert_type = 5).
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+use crate::collateral::der_to_pem;
+use crate::pccs::enclave_id::EnclaveIdType;
+use crate::pccs::is_not_stored;
+use crate::{PccsReader, CA};
+
+// Same set as encodeURIComponent, which is what Intel PCCS uses for issuer chains
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+const SGX_TCB_INFO_ISSUER_CHAIN: &str = "SGX-TCB-Info-Issuer-Chain";
+const TCB_INFO_ISSUER_CHAIN: &str = "TCB-Info-Issuer-Chain";
+const SGX_ENCLAVE_IDENTITY_ISSUER_CHAIN: &str = "SGX-Enclave-Identity-Issuer-Chain";
+const SGX_PCK_CRL_ISSUER_CHAIN: &str = "SGX-PCK-CRL-Issuer-Chain";
+
+type ApiResult = Result<Response, StatusCode>;
+
+pub fn router(reader: PccsReader) -> Router {
+    Router::new()
+        .route("/sgx/certification/:version/tcb", get(sgx_tcb_info))
+        .route("/tdx/certification/:version/tcb", get(tdx_tcb_info))
+        .route("/sgx/certification/:version/qe/identity", get(sgx_qe_identity))
+        .route("/sgx/certification/:version/qve/identity", get(qve_identity))
+        .route("/tdx/certification/:version/qe/identity", get(tdx_qe_identity))
+        .route("/sgx/certification/:version/pckcrl", get(pck_crl))
+        .route("/sgx/certification/:version/rootcacrl", get(root_ca_crl))
+        .route("/cache/stats", get(cache_stats))
+        .with_state(Arc::new(reader))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApiVersion {
+    V3,
+    V4,
+}
+
+impl ApiVersion {
+    fn parse(version: &str) -> Result<Self, StatusCode> {
+        match version {
+            "v3" => Ok(ApiVersion::V3),
+            "v4" => Ok(ApiVersion::V4),
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+
+    // The on-chain TCB Info version served by this API version
+    fn tcb_info_version(self) -> u32 {
+        match self {
+            ApiVersion::V3 => 2,
+            ApiVersion::V4 => 3,
+        }
+    }
+
+    // The on-chain Enclave Identity version served by this API version
+    fn enclave_identity_version(self) -> u32 {
+        match self {
+            ApiVersion::V3 => 3,
+            ApiVersion::V4 => 4,
+        }
+    }
+
+    fn tcb_info_issuer_chain_header(self) -> &'static str {
+        match self {
+            ApiVersion::V3 => SGX_TCB_INFO_ISSUER_CHAIN,
+            ApiVersion::V4 => TCB_INFO_ISSUER_CHAIN,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TcbInfoQuery {
+    fmspc: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PckCrlQuery {
+    ca: Option<String>,
+    encoding: Option<String>,
+}
+
+async fn sgx_tcb_info(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+    Query(query): Query<TcbInfoQuery>,
+) -> ApiResult {
+    tcb_info(&reader, 0, ApiVersion::parse(&version)?, query).await
+}
+
+async fn tdx_tcb_info(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+    Query(query): Query<TcbInfoQuery>,
+) -> ApiResult {
+    match ApiVersion::parse(&version)? {
+        ApiVersion::V4 => tcb_info(&reader, 1, ApiVersion::V4, query).await,
+        ApiVersion::V3 => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn sgx_qe_identity(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+) -> ApiResult {
+    enclave_identity(&reader, EnclaveIdType::QE, ApiVersion::parse(&version)?).await
+}
+
+async fn qve_identity(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+) -> ApiResult {
+    enclave_identity(&reader, EnclaveIdType::QVE, ApiVersion::parse(&version)?).await
+}
+
+async fn tdx_qe_identity(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+) -> ApiResult {
+    match ApiVersion::parse(&version)? {
+        ApiVersion::V4 => enclave_identity(&reader, EnclaveIdType::TDQE, ApiVersion::V4).await,
+        ApiVersion::V3 => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn pck_crl(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+    Query(query): Query<PckCrlQuery>,
+) -> ApiResult {
+    ApiVersion::parse(&version)?;
+
+    let ca = match query.ca.as_deref().map(str::to_ascii_lowercase).as_deref() {
+        Some("processor") => CA::PROCESSOR,
+        Some("platform") => CA::PLATFORM,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let der = match query.encoding.as_deref() {
+        None | Some("pem") => false,
+        Some("der") => true,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let (_, crl) = reader
+        .get_certificate_by_id(ca)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    if crl.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let issuer_chain = issuer_chain(&reader, ca).await?;
+
+    let (content_type, body) = if der {
+        ("application/pkix-crl", crl)
+    } else {
+        ("application/x-pem-file", der_to_pem("X509 CRL", &crl).into_bytes())
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE.as_str(), content_type.to_string()),
+            (SGX_PCK_CRL_ISSUER_CHAIN, issuer_chain),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+async fn root_ca_crl(
+    State(reader): State<Arc<PccsReader>>,
+    Path(version): Path<String>,
+) -> ApiResult {
+    ApiVersion::parse(&version)?;
+
+    let (_, crl) = reader
+        .get_certificate_by_id(CA::ROOT)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    if crl.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(([(header::CONTENT_TYPE, "application/pkix-crl")], crl).into_response())
+}
+
+// Not part of the Intel PCCS API, only served when a cache is in use
+async fn cache_stats(State(reader): State<Arc<PccsReader>>) -> ApiResult {
+    let cache = reader.cache().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(cache.stats()).into_response())
+}
+
+async fn tcb_info(
+    reader: &PccsReader,
+    tcb_type: u8,
+    version: ApiVersion,
+    query: TcbInfoQuery,
+) -> ApiResult {
+    let fmspc = query
+        .fmspc
+        .filter(|fmspc| fmspc.len() == 12 && hex::decode(fmspc).is_ok())
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_ascii_lowercase();
+
+    let tcb_info = reader
+        .get_tcb_info(tcb_type, &fmspc, version.tcb_info_version())
+        .await
+        .map_err(read_error)?;
+    let issuer_chain = issuer_chain(reader, CA::SIGNING).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE.as_str(), "application/json".to_string()),
+            (version.tcb_info_issuer_chain_header(), issuer_chain),
+        ],
+        tcb_info,
+    )
+        .into_response())
+}
+
+async fn enclave_identity(
+    reader: &PccsReader,
+    id: EnclaveIdType,
+    version: ApiVersion,
+) -> ApiResult {
+    let identity = reader
+        .get_enclave_identity(id, version.enclave_identity_version())
+        .await
+        .map_err(read_error)?;
+    let issuer_chain = issuer_chain(reader, CA::SIGNING).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE.as_str(), "application/json".to_string()),
+            (SGX_ENCLAVE_IDENTITY_ISSUER_CHAIN, issuer_chain),
+        ],
+        identity,
+    )
+        .into_response())
+}
+
+// URL encoded PEM chain of the given CA followed by the Root CA
+async fn issuer_chain(reader: &PccsReader, ca: CA) -> Result<String, StatusCode> {
+    let (cert, _) = reader
+        .get_certificate_by_id(ca)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    let (root, _) = reader
+        .get_certificate_by_id(CA::ROOT)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    if cert.is_empty() || root.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let chain = der_to_pem("CERTIFICATE", &cert) + &der_to_pem("CERTIFICATE", &root);
+    Ok(utf8_percent_encode(&chain, URI_COMPONENT).to_string())
+}
+
+// 404 only when the DAO stores nothing, a PCCS client caches it as such
+fn read_error(e: anyhow::Error) -> StatusCode {
+    match is_not_stored(&e) {
+        true => StatusCode::NOT_FOUND,
+        false => StatusCode::BAD_GATEWAY,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn status_of(uri: &str) -> StatusCode {
+        let reader = PccsReader::default();
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        router(reader).oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_requests() {
+        assert_eq!(status_of("/sgx/certification/v2/qe/identity").await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of("/tdx/certification/v3/tcb?fmspc=00806f050000").await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of("/sgx/certification/v4/tcb").await, StatusCode::BAD_REQUEST);
+        assert_eq!(status_of("/sgx/certification/v4/tcb?fmspc=zz").await, StatusCode::BAD_REQUEST);
+        assert_eq!(status_of("/sgx/certification/v4/pckcrl?ca=root").await, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            status_of("/sgx/certification/v4/pckcrl?ca=platform&encoding=base64").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(status_of("/cache/stats").await, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_read_error() {
+        assert_eq!(read_error(crate::pccs::NotStored.into()), StatusCode::NOT_FOUND);
+        assert_eq!(read_error(anyhow::anyhow!("request timed out")), StatusCode::BAD_GATEWAY);
+    }
+
+    #[test]
+    fn test_issuer_chain_encoding() {
+        let pem = der_to_pem("CERTIFICATE", &[0xfb; 48]);
+        let encoded = utf8_percent_encode(&pem, URI_COMPONENT).to_string();
+
+        assert!(encoded.starts_with("-----BEGIN%20CERTIFICATE-----%0A"));
+        assert!(!encoded.contains('+') && !encoded.contains('/'));
+        assert_eq!(
+            percent_encoding::percent_decode_str(&encoded).decode_utf8().unwrap(),
+            pem
+        );
+    }
+}
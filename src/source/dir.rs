@@ -0,0 +1,217 @@
+//! Collateral stored as files, laid out as
+//!
+//! ```text
+//! root_ca.der        root_ca_crl.der
+//! processor_ca.der   processor_ca_crl.der
+//! platform_ca.der    platform_ca_crl.der
+//! signing_ca.der
+//! tcb_info/{sgx,tdx}/v{version}/{fmspc}.json
+//! identity/{qe,qve,tdqe}/v{version}.json
+//! ```
+//!
+//! Certificates and CRLs may be DER or PEM, JSON files are Intel PCS responses.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::collateral::pem_to_der;
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::source::CollateralSource;
+use crate::CA;
+
+#[derive(Debug, Clone)]
+pub struct DirectoryCollateralSource {
+    root: PathBuf,
+}
+
+impl DirectoryCollateralSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DirectoryCollateralSource { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn certificate_path(&self, ca: CA) -> PathBuf {
+        self.root.join(format!("{}_ca.der", ca_name(ca)))
+    }
+
+    pub fn crl_path(&self, ca: CA) -> PathBuf {
+        self.root.join(format!("{}_ca_crl.der", ca_name(ca)))
+    }
+
+    pub fn tcb_info_path(&self, tcb_type: u8, fmspc: &str, version: u32) -> PathBuf {
+        let platform = if tcb_type == 1 { "tdx" } else { "sgx" };
+        self.root
+            .join("tcb_info")
+            .join(platform)
+            .join(format!("v{}", version))
+            .join(format!("{}.json", fmspc.to_ascii_lowercase()))
+    }
+
+    pub fn enclave_identity_path(&self, id: EnclaveIdType, version: u32) -> PathBuf {
+        let id = match id {
+            EnclaveIdType::QE => "qe",
+            EnclaveIdType::QVE => "qve",
+            EnclaveIdType::TDQE => "tdqe",
+        };
+        self.root
+            .join("identity")
+            .join(id)
+            .join(format!("v{}.json", version))
+    }
+
+    /// Writes a certificate and its CRL, if any, in the layout read by this source.
+    pub fn write_certificate(&self, ca: CA, cert: &[u8], crl: &[u8]) -> Result<()> {
+        write(&self.certificate_path(ca), cert)?;
+        if !crl.is_empty() {
+            write(&self.crl_path(ca), crl)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_tcb_info(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+        tcb_info: &TcbInfoJsonObj,
+    ) -> Result<()> {
+        let path = self.tcb_info_path(tcb_type, fmspc, version);
+        write(&path, tcb_info.to_pcs_json().as_bytes())
+    }
+
+    pub fn write_enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+        identity: &EnclaveIdentityJsonObj,
+    ) -> Result<()> {
+        let path = self.enclave_identity_path(id, version);
+        write(&path, identity.to_pcs_json().as_bytes())
+    }
+}
+
+#[async_trait]
+impl CollateralSource for DirectoryCollateralSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let cert = read_der(&self.certificate_path(ca))?;
+        let crl = match ca {
+            CA::SIGNING => vec![],
+            _ => read_der(&self.crl_path(ca))?,
+        };
+        Ok((cert, crl))
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        let json = read_to_string(&self.tcb_info_path(tcb_type, fmspc, version))?;
+        TcbInfoJsonObj::from_pcs_json(&json)
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        let json = read_to_string(&self.enclave_identity_path(id, version))?;
+        EnclaveIdentityJsonObj::from_pcs_json(&json)
+    }
+}
+
+pub(crate) fn ca_name(ca: CA) -> &'static str {
+    match ca {
+        CA::ROOT => "root",
+        CA::PROCESSOR => "processor",
+        CA::PLATFORM => "platform",
+        CA::SIGNING => "signing",
+        _ => "unknown",
+    }
+}
+
+// DER as is, or the first PEM block
+fn read_der(path: &Path) -> Result<Vec<u8>> {
+    let content = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    if !content.starts_with(b"-----BEGIN") {
+        return Ok(content);
+    }
+    pem_to_der(std::str::from_utf8(&content)?)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("{} is empty", path.display()))
+}
+
+fn read_to_string(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collateral::{der_to_pem, sample_collateral};
+    use crate::constants::SGX_TEE_TYPE;
+
+    #[tokio::test]
+    async fn test_directory_round_trip() {
+        let root = std::env::temp_dir().join(format!("pccs-reader-dir-{}", std::process::id()));
+        let source = DirectoryCollateralSource::new(&root);
+        let collateral = sample_collateral(SGX_TEE_TYPE);
+
+        source
+            .write_certificate(CA::ROOT, &collateral.root_ca, &collateral.root_ca_crl)
+            .unwrap();
+        source
+            .write_certificate(CA::SIGNING, &collateral.tcb_signing_ca, &[])
+            .unwrap();
+        source
+            .write_tcb_info(0, "00906ED50000", 3, &collateral.tcb_info)
+            .unwrap();
+        source
+            .write_enclave_identity(EnclaveIdType::QE, 4, &collateral.qe_identity)
+            .unwrap();
+        // PEM is accepted as well
+        std::fs::write(
+            source.certificate_path(CA::PLATFORM),
+            der_to_pem("CERTIFICATE", &collateral.pck_ca),
+        )
+        .unwrap();
+        std::fs::write(source.crl_path(CA::PLATFORM), &collateral.pck_crl).unwrap();
+
+        assert_eq!(
+            source.certificate(CA::ROOT).await.unwrap(),
+            (collateral.root_ca.clone(), collateral.root_ca_crl.clone())
+        );
+        assert_eq!(
+            source.certificate(CA::SIGNING).await.unwrap(),
+            (collateral.tcb_signing_ca.clone(), vec![])
+        );
+        assert_eq!(
+            source.certificate(CA::PLATFORM).await.unwrap(),
+            (collateral.pck_ca.clone(), collateral.pck_crl.clone())
+        );
+        assert_eq!(
+            source.tcb_info(0, "00906ed50000", 3).await.unwrap(),
+            collateral.tcb_info
+        );
+        assert_eq!(
+            source.enclave_identity(EnclaveIdType::QE, 4).await.unwrap(),
+            collateral.qe_identity
+        );
+        assert!(source.certificate(CA::PROCESSOR).await.is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+//! Client for the Intel PCS API (v3 and v4), or anything serving the same
+//! endpoints such as an Intel PCCS or `pccs-reader-server`.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use reqwest::Response;
+
+use crate::collateral::pem_to_der;
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::source::CollateralSource;
+use crate::CA;
+
+pub const INTEL_PCS_URL: &str = "https://api.trustedservices.intel.com";
+
+const SGX_TCB_INFO_ISSUER_CHAIN: &str = "SGX-TCB-Info-Issuer-Chain";
+const TCB_INFO_ISSUER_CHAIN: &str = "TCB-Info-Issuer-Chain";
+const SGX_ENCLAVE_IDENTITY_ISSUER_CHAIN: &str = "SGX-Enclave-Identity-Issuer-Chain";
+const SGX_PCK_CRL_ISSUER_CHAIN: &str = "SGX-PCK-CRL-Issuer-Chain";
+
+/// A response body with the DER certificates of its issuer chain, signer first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithIssuerChain<T> {
+    pub body: T,
+    pub issuer_chain: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IntelPcsCollateralSource {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl IntelPcsCollateralSource {
+    pub fn new(base_url: &str) -> Self {
+        IntelPcsCollateralSource {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// TCB Info version 3 is served by the v4 API, version 2 by the v3 API.
+    pub async fn get_tcb_info(
+        &self,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+    ) -> Result<WithIssuerChain<TcbInfoJsonObj>> {
+        let (api, header) = match version {
+            2 => ("v3", SGX_TCB_INFO_ISSUER_CHAIN),
+            3 => ("v4", TCB_INFO_ISSUER_CHAIN),
+            _ => return Err(anyhow::anyhow!("Unsupported TCB Info version {}", version)),
+        };
+        let platform = match tcb_type {
+            0 => "sgx",
+            1 => "tdx",
+            _ => return Err(anyhow::anyhow!("Unsupported TCB type {}", tcb_type)),
+        };
+
+        let url = format!("{}/{}/certification/{}/tcb", self.base_url, platform, api);
+        let response = self.get(&url, &[("fmspc", fmspc)]).await?;
+        let issuer_chain = issuer_chain(&response, header)?;
+        let body = TcbInfoJsonObj::from_pcs_json(&response.text().await?)?;
+
+        Ok(WithIssuerChain { body, issuer_chain })
+    }
+
+    /// Identities of version 4 are served by the v4 API, version 3 by the v3 API,
+    /// following the quote versions they verify.
+    pub async fn get_enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<WithIssuerChain<EnclaveIdentityJsonObj>> {
+        let api = match version {
+            3 => "v3",
+            4 => "v4",
+            _ => return Err(anyhow::anyhow!("Unsupported identity version {}", version)),
+        };
+        let path = match id {
+            EnclaveIdType::QE => "sgx/certification/{}/qe/identity",
+            EnclaveIdType::QVE => "sgx/certification/{}/qve/identity",
+            EnclaveIdType::TDQE => "tdx/certification/{}/qe/identity",
+        };
+
+        let url = format!("{}/{}", self.base_url, path.replace("{}", api));
+        let response = self.get(&url, &[]).await?;
+        let issuer_chain = issuer_chain(&response, SGX_ENCLAVE_IDENTITY_ISSUER_CHAIN)?;
+        let body = EnclaveIdentityJsonObj::from_pcs_json(&response.text().await?)?;
+
+        Ok(WithIssuerChain { body, issuer_chain })
+    }
+
+    /// DER encoded CRL of the PCK Processor or Platform CA.
+    pub async fn get_pck_crl(&self, ca: CA) -> Result<WithIssuerChain<Vec<u8>>> {
+        let ca = match ca {
+            CA::PROCESSOR => "processor",
+            CA::PLATFORM => "platform",
+            _ => return Err(anyhow::anyhow!("{:?} CA has no PCK CRL", ca)),
+        };
+
+        let url = format!("{}/sgx/certification/v4/pckcrl", self.base_url);
+        let response = self.get(&url, &[("ca", ca), ("encoding", "der")]).await?;
+        let issuer_chain = issuer_chain(&response, SGX_PCK_CRL_ISSUER_CHAIN)?;
+        let body = response.bytes().await?.to_vec();
+
+        Ok(WithIssuerChain { body, issuer_chain })
+    }
+
+    /// DER encoded Root CA CRL. Intel PCS serves it as DER, Intel PCCS as hex.
+    pub async fn get_root_ca_crl(&self) -> Result<Vec<u8>> {
+        let url = format!("{}/sgx/certification/v4/rootcacrl", self.base_url);
+        let body = self.get(&url, &[]).await?.bytes().await?;
+
+        match std::str::from_utf8(&body).ok().and_then(|text| hex::decode(text.trim()).ok()) {
+            Some(der) => Ok(der),
+            None => Ok(body.to_vec()),
+        }
+    }
+
+    async fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<Response> {
+        let response = self.client.get(url).query(query).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("GET {} returned {}", url, response.status()));
+        }
+        Ok(response)
+    }
+}
+
+impl Default for IntelPcsCollateralSource {
+    fn default() -> Self {
+        IntelPcsCollateralSource::new(INTEL_PCS_URL)
+    }
+}
+
+#[async_trait]
+impl CollateralSource for IntelPcsCollateralSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        match ca {
+            // every issuer chain ends with the Root CA
+            CA::ROOT => {
+                let identity = self.get_enclave_identity(EnclaveIdType::QE, 4).await?;
+                let root = last_certificate(identity.issuer_chain)?;
+                Ok((root, self.get_root_ca_crl().await?))
+            }
+            CA::SIGNING => {
+                let identity = self.get_enclave_identity(EnclaveIdType::QE, 4).await?;
+                Ok((first_certificate(identity.issuer_chain)?, vec![]))
+            }
+            _ => {
+                let crl = self.get_pck_crl(ca).await?;
+                Ok((first_certificate(crl.issuer_chain)?, crl.body))
+            }
+        }
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        Ok(self.get_tcb_info(tcb_type, fmspc, version).await?.body)
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        Ok(self.get_enclave_identity(id, version).await?.body)
+    }
+}
+
+// URL encoded PEM chain to DER certificates
+fn issuer_chain(response: &Response, header: &str) -> Result<Vec<Vec<u8>>> {
+    let encoded = response
+        .headers()
+        .get(header)
+        .ok_or_else(|| anyhow::anyhow!("{} header is missing", header))?
+        .to_str()?;
+    pem_to_der(&percent_decode_str(encoded).decode_utf8()?)
+}
+
+fn first_certificate(chain: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    chain
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("empty issuer chain"))
+}
+
+fn last_certificate(chain: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    chain
+        .into_iter()
+        .last()
+        .ok_or_else(|| anyhow::Error::msg("empty issuer chain"))
+}
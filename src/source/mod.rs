@@ -0,0 +1,84 @@
+//! Where collateral comes from when it is not read through a [`PccsReader`] call directly,
+//! e.g. to fill the on-chain PCCS from another store.
+
+pub mod dir;
+pub mod intel_pcs;
+pub mod snapshot;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::pccs::multicall::QuoteCollateralReads;
+use crate::pccs::NotStored;
+use crate::{PccsReader, CA};
+
+pub use dir::DirectoryCollateralSource;
+pub use intel_pcs::IntelPcsCollateralSource;
+pub use snapshot::SnapshotCollateralSource;
+
+#[async_trait]
+pub trait CollateralSource: Send + Sync {
+    /// DER encoded certificate and CRL of the CA.
+    /// The CRL is empty for the TCB Signing CA, which has none.
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)>;
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj>;
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj>;
+}
+
+#[async_trait]
+impl CollateralSource for PccsReader {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (cert, crl) = self.get_certificate_by_id(ca).await?;
+        if cert.is_empty() {
+            return Err(NotStored.into());
+        }
+        Ok((cert, crl))
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        self.get_tcb_info_obj(tcb_type, fmspc, version).await
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        self.get_enclave_identity_obj(id, version).await
+    }
+}
+
+impl QuoteCollateralReads {
+    /// Reads the collateral of a quote from a source instead of the DAOs.
+    pub(crate) async fn from_source<S: CollateralSource + ?Sized>(
+        source: &S,
+        qe_id_type: EnclaveIdType,
+        qe_id_version: u32,
+        tcb: (u8, &str, u32),
+        pck_ca: CA,
+    ) -> Self {
+        let (tcb_type, fmspc, tcb_version) = tcb;
+        let (root, qe_identity, tcb_info, signing, pck) = futures::join!(
+            source.certificate(CA::ROOT),
+            source.enclave_identity(qe_id_type, qe_id_version),
+            source.tcb_info(tcb_type, fmspc, tcb_version),
+            source.certificate(CA::SIGNING),
+            source.certificate(pck_ca),
+        );
+        QuoteCollateralReads {
+            root,
+            qe_identity,
+            tcb_info,
+            signing,
+            pck,
+        }
+    }
+}
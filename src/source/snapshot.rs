@@ -0,0 +1,284 @@
+//! Offline snapshots of the on-chain PCCS, for verifiers that cannot reach any RPC.
+//! A snapshot is a single JSON file with every certificate, CRL and enclave identity,
+//! and the TCB Info of a list of FMSPCs, all read at one block.
+
+use std::path::Path;
+
+use alloy::eips::BlockId;
+use alloy::primitives::{keccak256, Address, B256};
+use alloy::providers::Provider;
+use alloy::rpc::types::BlockTransactionsKind;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use crate::pccs::fmspc_tcb::TcbInfoJsonObj;
+use crate::pccs::{is_not_stored, NotStored};
+use crate::source::dir::ca_name;
+use crate::source::CollateralSource;
+use crate::{PccsReader, CA};
+
+/// Bumped whenever the layout of the snapshot file changes.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format_version: u32,
+    pub network: String,
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub state_root: B256,
+    pub pcs_dao: Address,
+    pub fmspc_tcb_dao: Address,
+    pub enclave_id_dao: Address,
+    pub certificates: Vec<SnapshotCertificate>,
+    pub tcb_infos: Vec<SnapshotTcbInfo>,
+    pub enclave_identities: Vec<SnapshotEnclaveIdentity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotCertificate {
+    // root, processor, platform or signing
+    pub ca: String,
+    #[serde(with = "hex")]
+    pub cert: Vec<u8>,
+    #[serde(with = "hex")]
+    pub crl: Vec<u8>,
+    // keccak256 of the DER cert and CRL
+    pub cert_hash: B256,
+    pub crl_hash: B256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotTcbInfo {
+    pub tcb_type: u8,
+    pub fmspc: String,
+    pub version: u32,
+    // the Intel PCS response
+    pub tcb_info: String,
+    // keccak256 of the signed body
+    pub hash: B256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEnclaveIdentity {
+    pub id: EnclaveIdType,
+    pub version: u32,
+    // the Intel PCS response
+    pub identity: String,
+    // keccak256 of the signed body
+    pub hash: B256,
+}
+
+// Every CA, identity and TCB Info (type, version) the DAOs are read for
+pub(crate) const SNAPSHOT_CAS: [CA; 4] = [CA::ROOT, CA::PROCESSOR, CA::PLATFORM, CA::SIGNING];
+pub(crate) const SNAPSHOT_IDENTITIES: [(EnclaveIdType, u32); 5] = [
+    (EnclaveIdType::QE, 3),
+    (EnclaveIdType::QE, 4),
+    (EnclaveIdType::QVE, 3),
+    (EnclaveIdType::QVE, 4),
+    (EnclaveIdType::TDQE, 4),
+];
+pub(crate) const SNAPSHOT_TCB_INFOS: [(u8, u32); 3] = [(0, 2), (0, 3), (1, 3)];
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let snapshot: Snapshot = serde_json::from_str(&json)?;
+        snapshot.verify()?;
+        Ok(snapshot)
+    }
+
+    // Written aside then renamed, so that a crash never leaves a truncated file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Checks the format version and the hash of every entry.
+    pub fn verify(&self) -> Result<()> {
+        if self.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported snapshot format version {}",
+                self.format_version
+            ));
+        }
+        for certificate in &self.certificates {
+            if keccak256(&certificate.cert) != certificate.cert_hash
+                || keccak256(&certificate.crl) != certificate.crl_hash
+            {
+                return Err(anyhow::anyhow!("Hash mismatch for the {} CA", certificate.ca));
+            }
+        }
+        for tcb_info in &self.tcb_infos {
+            let obj = TcbInfoJsonObj::from_pcs_json(&tcb_info.tcb_info)?;
+            if keccak256(&obj.tcbInfoStr) != tcb_info.hash {
+                return Err(anyhow::anyhow!("Hash mismatch for the TCB Info of {}", tcb_info.fmspc));
+            }
+        }
+        for identity in &self.enclave_identities {
+            let obj = EnclaveIdentityJsonObj::from_pcs_json(&identity.identity)?;
+            if keccak256(&obj.identityStr) != identity.hash {
+                return Err(anyhow::anyhow!(
+                    "Hash mismatch for the {:?} identity v{}",
+                    identity.id,
+                    identity.version
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PccsReader {
+    /// Reads every certificate, CRL and enclave identity, and the TCB Info of the given
+    /// FMSPCs, at the block of the reader. Collateral missing on chain is left out.
+    pub async fn export_snapshot(&self, fmspcs: &[String]) -> Result<Snapshot> {
+        let block = self
+            .provider()
+            .get_block(self.block(), BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No block for {:?}", self.block()))?;
+        let block_hash = block
+            .header
+            .hash
+            .ok_or_else(|| anyhow::Error::msg("Block without a hash"))?;
+        let block_number = block
+            .header
+            .number
+            .ok_or_else(|| anyhow::Error::msg("Block without a number"))?;
+        // by hash, so that a reorg fails the reads rather than mixing two chains
+        let reader = self.at_block(BlockId::hash(block_hash));
+        let chain_id = reader.provider().get_chain_id().await?;
+
+        let mut certificates = Vec::new();
+        for ca in SNAPSHOT_CAS {
+            let (cert, crl) = reader.get_certificate_by_id(ca).await?;
+            if !cert.is_empty() {
+                certificates.push(SnapshotCertificate {
+                    ca: ca_name(ca).to_string(),
+                    cert_hash: keccak256(&cert),
+                    crl_hash: keccak256(&crl),
+                    cert,
+                    crl,
+                });
+            }
+        }
+
+        let mut enclave_identities = Vec::new();
+        for (id, version) in SNAPSHOT_IDENTITIES {
+            // only what the DAO does not store is left out, a failed read fails the export
+            let obj = match reader.get_enclave_identity_obj(id, version).await {
+                Ok(obj) => obj,
+                Err(e) if is_not_stored(&e) => continue,
+                Err(e) => return Err(e),
+            };
+            enclave_identities.push(SnapshotEnclaveIdentity {
+                id,
+                version,
+                hash: keccak256(&obj.identityStr),
+                identity: obj.to_pcs_json(),
+            });
+        }
+
+        let mut tcb_infos = Vec::new();
+        for fmspc in fmspcs {
+            let fmspc = fmspc.to_ascii_lowercase();
+            for (tcb_type, version) in SNAPSHOT_TCB_INFOS {
+                let obj = match reader.get_tcb_info_obj(tcb_type, &fmspc, version).await {
+                    Ok(obj) => obj,
+                    Err(e) if is_not_stored(&e) => continue,
+                    Err(e) => return Err(e),
+                };
+                tcb_infos.push(SnapshotTcbInfo {
+                    tcb_type,
+                    fmspc: fmspc.clone(),
+                    version,
+                    hash: keccak256(&obj.tcbInfoStr),
+                    tcb_info: obj.to_pcs_json(),
+                });
+            }
+        }
+
+        let network = self.network();
+        Ok(Snapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            network: network.name.clone(),
+            chain_id,
+            block_number,
+            block_hash,
+            state_root: block.header.state_root,
+            pcs_dao: network.pcs_dao,
+            fmspc_tcb_dao: network.fmspc_tcb_dao,
+            enclave_id_dao: network.enclave_id_dao,
+            certificates,
+            tcb_infos,
+            enclave_identities,
+        })
+    }
+}
+
+/// Reads the collateral of a snapshot, with no network at all.
+#[derive(Debug, Clone)]
+pub struct SnapshotCollateralSource {
+    snapshot: Snapshot,
+}
+
+impl SnapshotCollateralSource {
+    pub fn new(snapshot: Snapshot) -> Self {
+        SnapshotCollateralSource { snapshot }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(SnapshotCollateralSource::new(Snapshot::load(path)?))
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+}
+
+#[async_trait]
+impl CollateralSource for SnapshotCollateralSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.snapshot
+            .certificates
+            .iter()
+            .find(|certificate| certificate.ca == ca_name(ca))
+            .map(|certificate| (certificate.cert.clone(), certificate.crl.clone()))
+            .ok_or_else(|| NotStored.into())
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        let tcb_info = self
+            .snapshot
+            .tcb_infos
+            .iter()
+            .find(|tcb_info| {
+                tcb_info.tcb_type == tcb_type
+                    && tcb_info.fmspc.eq_ignore_ascii_case(fmspc)
+                    && tcb_info.version == version
+            })
+            .ok_or(NotStored)?;
+        TcbInfoJsonObj::from_pcs_json(&tcb_info.tcb_info)
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        let identity = self
+            .snapshot
+            .enclave_identities
+            .iter()
+            .find(|identity| identity.id == id && identity.version == version)
+            .ok_or(NotStored)?;
+        EnclaveIdentityJsonObj::from_pcs_json(&identity.identity)
+    }
+}
@@ -0,0 +1,316 @@
+//! A stream of the collateral changes on chain, e.g. to drop cached collateral
+//! as soon as it is upserted. Changes come from a WebSocket log subscription,
+//! or from polling `eth_getLogs` over HTTP.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use alloy::providers::{Provider, ProviderBuilder, WsConnect};
+use alloy::pubsub::{ConnectionHandle, PubSubConnect};
+use alloy::rpc::client::ClientBuilder;
+use alloy::rpc::types::{Filter, Log};
+use alloy::transports::{TransportErrorKind, TransportResult};
+use anyhow::{anyhow, Result};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::Serialize;
+
+use crate::pccs::events::{CollateralEvent, CollateralItem, EventIndexer};
+use crate::PccsReader;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum CollateralChange {
+    Upserted(CollateralEvent),
+    // the block of the upsert was reorged out, the item may be back to a previous issue
+    Reverted(CollateralEvent),
+}
+
+impl CollateralChange {
+    pub fn item(&self) -> &CollateralItem {
+        match self {
+            CollateralChange::Upserted(event) | CollateralChange::Reverted(event) => &event.item,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    // replays the changes since this block first, only new ones by default
+    pub from_block: Option<u64>,
+    // also the delay before subscribing again over a dropped WebSocket
+    pub poll_interval: Duration,
+    // recent blocks scanned again on every poll, to catch reorgs up to that depth
+    pub reorg_depth: u64,
+    // subscribes over WebSocket instead of polling over HTTP
+    pub ws_url: Option<String>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            from_block: None,
+            poll_interval: Duration::from_secs(2),
+            reorg_depth: 64,
+            ws_url: None,
+        }
+    }
+}
+
+pub fn watch_collateral(options: WatchOptions) -> BoxStream<'static, Result<CollateralChange>> {
+    PccsReader::default().watch_collateral(options)
+}
+
+impl PccsReader {
+    /// The upserts of the DAOs as they happen. A reorged out upsert is reported as
+    /// [`CollateralChange::Reverted`], and upserted again if it made it to the new chain.
+    /// Failed polls are reported and polling goes on. A dropped WebSocket is reported too,
+    /// and subscribed again after `poll_interval`, replaying the upserts missed meanwhile.
+    pub fn watch_collateral(&self, options: WatchOptions) -> BoxStream<'static, Result<CollateralChange>> {
+        let indexer = EventIndexer::new(self.clone());
+        match options.ws_url.clone() {
+            Some(ws_url) => subscribe(indexer, ws_url, options),
+            None => poll(indexer, options),
+        }
+    }
+
+    /// Drops the cached collateral a change is about, returns whether any was cached.
+    pub fn invalidate_item(&self, item: &CollateralItem) -> bool {
+        match item {
+            CollateralItem::PcsCertificate(ca) | CollateralItem::PcsCrl(ca) => {
+                self.invalidate_certificate(*ca)
+            }
+            CollateralItem::TcbInfo {
+                tcb_type,
+                fmspc,
+                version,
+            } => self.invalidate_tcb_info(*tcb_type, fmspc, *version),
+            CollateralItem::EnclaveIdentity { id, version } => {
+                self.invalidate_enclave_identity(*id, *version)
+            }
+            // PCK certificates are not cached
+            CollateralItem::PckCertificate { .. } => false,
+        }
+    }
+}
+
+struct Poller {
+    indexer: EventIndexer,
+    options: WatchOptions,
+    // the first block watched and the first block not scanned yet, resolved on the first poll
+    start_block: u64,
+    next_block: Option<u64>,
+    // upserts reported from the last `reorg_depth` blocks
+    recent: Vec<CollateralEvent>,
+    polled: bool,
+}
+
+impl Poller {
+    async fn poll(&mut self) -> Result<Vec<CollateralChange>> {
+        if self.polled {
+            tokio::time::sleep(self.options.poll_interval).await;
+        }
+        self.polled = true;
+
+        let latest = self.indexer.reader().provider().get_block_number().await?;
+        let next_block = match self.next_block {
+            Some(next_block) => next_block,
+            None => {
+                self.start_block = self.options.from_block.unwrap_or(latest + 1);
+                self.start_block
+            }
+        };
+        self.next_block = Some(next_block);
+        if latest < next_block {
+            return Ok(Vec::new());
+        }
+
+        let from_block = next_block
+            .saturating_sub(self.options.reorg_depth)
+            .max(self.start_block);
+        let events = self.indexer.events(from_block, latest).await?;
+
+        // whatever was reported from the rescanned blocks and is not there anymore was reorged out
+        let mut changes = Vec::new();
+        let (rescanned, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.recent)
+            .into_iter()
+            .partition(|event| event.block_number >= from_block);
+        for event in &rescanned {
+            if !events.contains(event) {
+                changes.push(CollateralChange::Reverted(event.clone()));
+            }
+        }
+        for event in &events {
+            if !rescanned.contains(event) {
+                changes.push(CollateralChange::Upserted(event.clone()));
+            }
+        }
+
+        let horizon = latest.saturating_sub(self.options.reorg_depth);
+        self.recent = kept
+            .into_iter()
+            .chain(events)
+            .filter(|event| event.block_number >= horizon)
+            .collect();
+        self.next_block = Some(latest + 1);
+        Ok(changes)
+    }
+}
+
+fn poll(indexer: EventIndexer, options: WatchOptions) -> BoxStream<'static, Result<CollateralChange>> {
+    let poller = Poller {
+        indexer,
+        options,
+        start_block: 0,
+        next_block: None,
+        recent: Vec::new(),
+        polled: false,
+    };
+    stream::unfold(poller, |mut poller| async move {
+        let changes: VecDeque<_> = match poller.poll().await {
+            Ok(changes) => changes.into_iter().map(Ok).collect(),
+            Err(e) => VecDeque::from([Err(e)]),
+        };
+        Some((stream::iter(changes), poller))
+    })
+    .flatten()
+    .boxed()
+}
+
+// Connects once: alloy would otherwise reconnect on its own and resubscribe, losing
+// whatever was logged in between, so a dropped socket ends the subscription instead
+struct WsOnce(WsConnect);
+
+impl PubSubConnect for WsOnce {
+    fn is_local(&self) -> bool {
+        self.0.is_local()
+    }
+
+    async fn connect(&self) -> TransportResult<ConnectionHandle> {
+        self.0.connect().await
+    }
+
+    async fn try_reconnect(&self) -> TransportResult<ConnectionHandle> {
+        Err(TransportErrorKind::custom_str(
+            "the WebSocket connection was dropped",
+        ))
+    }
+}
+
+struct Subscriber {
+    indexer: EventIndexer,
+    ws_url: String,
+    retry_interval: Duration,
+    // the first block replayed on the next connection, only new blocks on the first one by default
+    resume_from: Option<u64>,
+    // upserts already reported from `resume_from`, not reported again by the replay
+    seen: Vec<CollateralEvent>,
+    // the last block replayed on the current connection, the live logs start after it
+    replayed_to: u64,
+    live: Option<BoxStream<'static, Log>>,
+    connected: bool,
+}
+
+impl Subscriber {
+    async fn next(&mut self) -> Vec<Result<CollateralChange>> {
+        let Some(live) = &mut self.live else {
+            if self.connected {
+                tokio::time::sleep(self.retry_interval).await;
+            }
+            self.connected = true;
+            return match self.connect().await {
+                Ok(replayed) => replayed.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(
+                    e.context(format!("could not subscribe over {}", self.ws_url))
+                )],
+            };
+        };
+        let Some(log) = live.next().await else {
+            self.live = None;
+            return vec![Err(anyhow!(
+                "the WebSocket subscription over {} ended, reconnecting",
+                self.ws_url
+            ))];
+        };
+        let Some(event) = CollateralEvent::from_log(&log) else {
+            return Vec::new();
+        };
+        // the node flags the logs of reorged out blocks as removed
+        if log.removed {
+            self.seen.retain(|seen| seen != &event);
+            return vec![Ok(CollateralChange::Reverted(event))];
+        }
+        if event.block_number <= self.replayed_to {
+            return Vec::new();
+        }
+        self.remember(&event);
+        vec![Ok(CollateralChange::Upserted(event))]
+    }
+
+    // Subscribes, then replays what was upserted since the last block seen, so that nothing
+    // falls in between
+    async fn connect(&mut self) -> Result<Vec<CollateralChange>> {
+        let client = ClientBuilder::default()
+            .pubsub(WsOnce(WsConnect::new(self.ws_url.clone())))
+            .await?;
+        let provider = ProviderBuilder::new().on_client(client);
+        let filter = Filter::new().address(self.indexer.daos());
+        let subscription = provider.subscribe_logs(&filter).await?;
+
+        let latest = self.indexer.reader().provider().get_block_number().await?;
+        let mut replayed = Vec::new();
+        if let Some(from_block) = self.resume_from {
+            for event in self.indexer.events(from_block, latest).await? {
+                if !self.seen.contains(&event) {
+                    replayed.push(CollateralChange::Upserted(event));
+                }
+            }
+        }
+        self.resume_from = Some(latest + 1);
+        self.seen.clear();
+        self.replayed_to = latest;
+        self.live = Some(
+            subscription
+                .into_stream()
+                // the connection lives as long as the stream
+                .map(move |log| {
+                    let _ = &provider;
+                    log
+                })
+                .boxed(),
+        );
+        Ok(replayed)
+    }
+
+    fn remember(&mut self, event: &CollateralEvent) {
+        match self.resume_from {
+            Some(from_block) if from_block > event.block_number => {}
+            Some(from_block) if from_block == event.block_number => self.seen.push(event.clone()),
+            _ => {
+                self.resume_from = Some(event.block_number);
+                self.seen = vec![event.clone()];
+            }
+        }
+    }
+}
+
+fn subscribe(
+    indexer: EventIndexer,
+    ws_url: String,
+    options: WatchOptions,
+) -> BoxStream<'static, Result<CollateralChange>> {
+    let subscriber = Subscriber {
+        indexer,
+        ws_url,
+        retry_interval: options.poll_interval,
+        resume_from: options.from_block,
+        seen: Vec::new(),
+        replayed_to: 0,
+        live: None,
+        connected: false,
+    };
+    stream::unfold(subscriber, |mut subscriber| async move {
+        let changes = subscriber.next().await;
+        Some((stream::iter(changes), subscriber))
+    })
+    .flatten()
+    .boxed()
+}
@@ -0,0 +1,230 @@
+//! Upsert transactions that fill the collateral missing from the on-chain PCCS.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, TxHash, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use anyhow::Result;
+
+use crate::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj, IEnclaveIdentityDao};
+use crate::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use crate::pccs::pcs::IPCSDao;
+use crate::source::CollateralSource;
+use crate::{MissingCollateral, NetworkProfile, PccsReader, CA};
+
+/// What an upsert writes
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UpsertKind {
+    PcsCertificate(CA),
+    PcsCrl(CA),
+    // TcbType, Fmspc, Version
+    FmspcTcb(u8, String, u32),
+    // Id, Version
+    EnclaveIdentity(EnclaveIdType, u32),
+}
+
+impl UpsertKind {
+    // Issuers go first, the DAOs check every collateral against them
+    fn priority(&self) -> u8 {
+        match self {
+            UpsertKind::PcsCertificate(CA::ROOT) => 0,
+            UpsertKind::PcsCrl(CA::ROOT) => 1,
+            UpsertKind::PcsCertificate(CA::SIGNING) => 2,
+            UpsertKind::PcsCertificate(_) => 3,
+            UpsertKind::PcsCrl(_) => 4,
+            UpsertKind::EnclaveIdentity(..) => 5,
+            UpsertKind::FmspcTcb(..) => 6,
+        }
+    }
+}
+
+impl std::fmt::Display for UpsertKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpsertKind::PcsCertificate(ca) => write!(f, "{:?} CA certificate", ca),
+            UpsertKind::PcsCrl(ca) => write!(f, "{:?} CA CRL", ca),
+            UpsertKind::FmspcTcb(tcb_type, fmspc, version) => write!(
+                f,
+                "TCB info for fmspc {} (tcb type {}, version {})",
+                fmspc, tcb_type, version
+            ),
+            UpsertKind::EnclaveIdentity(id, version) => {
+                write!(f, "{:?} identity (version {})", id, version)
+            }
+        }
+    }
+}
+
+/// An encoded call to one of the DAO upsert functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpsertCall {
+    pub kind: UpsertKind,
+    pub to: Address,
+    pub input: Bytes,
+}
+
+impl UpsertCall {
+    pub fn pcs_certificate(network: &NetworkProfile, ca: CA, cert: Vec<u8>) -> Self {
+        let call = IPCSDao::upsertPcsCertificatesCall { ca, cert: cert.into() };
+        UpsertCall {
+            kind: UpsertKind::PcsCertificate(ca),
+            to: network.pcs_dao,
+            input: call.abi_encode().into(),
+        }
+    }
+
+    /// The Root CA CRL has its own upsert, the PCK CA CRLs share one.
+    pub fn pcs_crl(network: &NetworkProfile, ca: CA, crl: Vec<u8>) -> Self {
+        let input = match ca {
+            CA::ROOT => IPCSDao::upsertRootCACrlCall { rootcacrl: crl.into() }.abi_encode(),
+            _ => IPCSDao::upsertPckCrlCall { ca, crl: crl.into() }.abi_encode(),
+        };
+        UpsertCall {
+            kind: UpsertKind::PcsCrl(ca),
+            to: network.pcs_dao,
+            input: input.into(),
+        }
+    }
+
+    pub fn fmspc_tcb(
+        network: &NetworkProfile,
+        tcb_type: u8,
+        fmspc: &str,
+        version: u32,
+        tcb_info: TcbInfoJsonObj,
+    ) -> Self {
+        let call = IFmspcTcbDao::upsertFmspcTcbCall { tcbInfoObj: tcb_info };
+        UpsertCall {
+            kind: UpsertKind::FmspcTcb(tcb_type, fmspc.to_string(), version),
+            to: network.fmspc_tcb_dao,
+            input: call.abi_encode().into(),
+        }
+    }
+
+    pub fn enclave_identity(
+        network: &NetworkProfile,
+        id: EnclaveIdType,
+        version: u32,
+        identity: EnclaveIdentityJsonObj,
+    ) -> Self {
+        let call = IEnclaveIdentityDao::upsertEnclaveIdentityCall {
+            id: id.to_uint256(),
+            version: U256::from(version),
+            enclaveIdentityObj: identity,
+        };
+        UpsertCall {
+            kind: UpsertKind::EnclaveIdentity(id, version),
+            to: network.enclave_id_dao,
+            input: call.abi_encode().into(),
+        }
+    }
+
+    pub fn to_request(&self) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_to(self.to)
+            .with_input(self.input.clone())
+    }
+}
+
+pub fn signer_from_private_key(private_key: &str) -> Result<PrivateKeySigner> {
+    Ok(private_key.trim().parse()?)
+}
+
+pub fn signer_from_keystore(path: impl AsRef<Path>, password: &str) -> Result<PrivateKeySigner> {
+    Ok(PrivateKeySigner::decrypt_keystore(path, password)?)
+}
+
+impl PccsReader {
+    /// Fetches every missing collateral from `source` and encodes its upserts,
+    /// ordered so that issuers are written before what they sign.
+    pub async fn upsert_calls<S: CollateralSource + ?Sized>(
+        &self,
+        missing: &[MissingCollateral],
+        source: &S,
+    ) -> Result<Vec<UpsertCall>> {
+        let network = self.network();
+        let mut calls: Vec<UpsertCall> = Vec::new();
+
+        for missing in missing {
+            match missing {
+                MissingCollateral::None => {}
+                MissingCollateral::PCS(ca, cert_missing, crl_missing) => {
+                    let (cert, crl) = source.certificate(*ca).await?;
+                    if *cert_missing {
+                        if cert.is_empty() {
+                            return Err(anyhow::anyhow!(
+                                "{:?} CA certificate not found in source",
+                                ca
+                            ));
+                        }
+                        calls.push(UpsertCall::pcs_certificate(network, *ca, cert));
+                    }
+                    // the TCB Signing CA has no CRL
+                    if *crl_missing && *ca != CA::SIGNING {
+                        if crl.is_empty() {
+                            return Err(anyhow::anyhow!("{:?} CA CRL not found in source", ca));
+                        }
+                        calls.push(UpsertCall::pcs_crl(network, *ca, crl));
+                    }
+                }
+                MissingCollateral::FMSPCTCB(tcb_type, fmspc, version) => {
+                    let tcb_info = source.tcb_info(*tcb_type, fmspc, *version).await?;
+                    calls.push(UpsertCall::fmspc_tcb(network, *tcb_type, fmspc, *version, tcb_info));
+                }
+                MissingCollateral::QEIdentity(id, version) => {
+                    let identity = source.enclave_identity(*id, *version).await?;
+                    calls.push(UpsertCall::enclave_identity(network, *id, *version, identity));
+                }
+            }
+        }
+
+        // the same collateral can be missing for several quotes
+        let mut seen = HashSet::new();
+        calls.retain(|call| seen.insert(call.kind.clone()));
+        calls.sort_by_key(|call| call.kind.priority());
+        Ok(calls)
+    }
+
+    /// Upserts every missing collateral from `source`, one transaction at a time,
+    /// and returns the hash of each once it has been mined. The transactions are sent
+    /// over the transport of the reader, with its timeouts, retries and fallback URLs.
+    pub async fn fill_missing<S: CollateralSource + ?Sized>(
+        &self,
+        missing: &[MissingCollateral],
+        source: &S,
+        signer: PrivateKeySigner,
+    ) -> Result<Vec<(UpsertCall, TxHash)>> {
+        let calls = self.upsert_calls(missing, source).await?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer))
+            .on_provider(self.provider().clone());
+
+        let mut submitted = Vec::with_capacity(calls.len());
+        for call in calls {
+            let receipt = provider
+                .send_transaction(call.to_request())
+                .await?
+                .get_receipt()
+                .await?;
+            if !receipt.status() {
+                return Err(anyhow::anyhow!(
+                    "{} upsert reverted in {}",
+                    call.kind,
+                    receipt.transaction_hash
+                ));
+            }
+            submitted.push((call, receipt.transaction_hash));
+        }
+
+        Ok(submitted)
+    }
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use alloy::primitives::{Address, Bytes};
+use alloy::sol_types::{Revert, SolCall, SolError};
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use common::{
+    certificate_returns, dao_contracts, identity_returns, quote, reader, LocalChain,
+    DCAP_ATTESTATION, ENCLAVE_ID_DAO, PCS_DAO,
+};
+use pccs_reader_rs::attestation::{IDcapAttestation, TcbStatus};
+use pccs_reader_rs::quote::parse_quote;
+use pccs_reader_rs::MissingCollateral;
+
+fn attestation_returns(success: bool, output: Vec<u8>) -> Vec<u8> {
+    IDcapAttestation::verifyAndAttestOnChainCall::abi_encode_returns(&(success, Bytes::from(output)))
+}
+
+// A node storing all the collateral, answering every call to the DCAP attestation with `error`
+async fn failing_node(error: Value) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = Router::new()
+        .route("/", post(failing_rpc))
+        .with_state(Arc::new(error));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    url
+}
+
+async fn failing_rpc(State(error): State<Arc<Value>>, Json(request): Json<Value>) -> Json<Value> {
+    let params = &request["params"];
+    let result = match request["method"].as_str() {
+        Some("eth_blockNumber") => json!("0x1"),
+        Some("eth_call") => {
+            let to: Option<Address> = serde_json::from_value(params[0]["to"].clone()).ok();
+            if to == Some(DCAP_ATTESTATION) {
+                return Json(json!({ "jsonrpc": "2.0", "id": request["id"], "error": *error }));
+            }
+            let contracts = dao_contracts(2, "00606a000000");
+            json!(Bytes::from(to.and_then(|to| contracts.get(&to).cloned()).unwrap_or_default()))
+        }
+        _ => Value::Null,
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+#[tokio::test]
+async fn test_simulates_verification() {
+    let raw_quote = quote("v3_sgx_quote.hex");
+    // abi.encodePacked(quoteVersion, tee, tcbStatus, fmspcBytes, quoteBody)
+    let mut output = vec![0x00, 0x03];
+    output.extend_from_slice(&raw_quote[4..8]);
+    output.push(4);
+    output.extend_from_slice(&[0x00, 0x60, 0x6a, 0x00, 0x00, 0x00]);
+    output.extend_from_slice(&raw_quote[48..48 + 384]);
+
+    let chain = LocalChain::start(HashMap::from([(
+        DCAP_ATTESTATION,
+        attestation_returns(true, output),
+    )]))
+    .await;
+    let verification = reader(chain.url())
+        .simulate_onchain_verification(&raw_quote)
+        .await
+        .unwrap();
+
+    assert!(verification.verified);
+    assert_eq!(verification.missing, MissingCollateral::None);
+    let output = verification.output.unwrap();
+    assert_eq!((output.quote_version, output.tee_type), (3, 0));
+    assert_eq!(output.tcb_status, TcbStatus::OutOfDate);
+    assert_eq!(hex::encode(output.fmspc), "00606a000000");
+    assert_eq!(output.quote_body.to_bytes(), raw_quote[48..48 + 384]);
+}
+
+#[tokio::test]
+async fn test_reports_missing_collateral_on_failure() {
+    // no TCB Info on chain
+    let chain = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (ENCLAVE_ID_DAO, identity_returns()),
+        (
+            DCAP_ATTESTATION,
+            attestation_returns(false, b"TCB Info not found".to_vec()),
+        ),
+    ]))
+    .await;
+
+    let verification = reader(chain.url())
+        .simulate_onchain_verification(&quote("v3_sgx_quote.hex"))
+        .await
+        .unwrap();
+    assert!(!verification.verified);
+    assert_eq!(verification.output, None);
+    assert_eq!(verification.error.as_deref(), Some("TCB Info not found"));
+    assert!(matches!(verification.missing, MissingCollateral::FMSPCTCB(0, _, 2)));
+}
+
+#[tokio::test]
+async fn test_fails_on_malformed_certification_data() {
+    // a well-formed quote whose PCK certificate is not base64
+    let mut raw_quote = quote("v3_sgx_quote.hex");
+    let pem = raw_quote
+        .windows(27)
+        .position(|window| window == b"-----BEGIN CERTIFICATE-----")
+        .unwrap();
+    raw_quote[pem + 28..pem + 92].fill(b'!');
+    assert!(parse_quote(&raw_quote).is_ok());
+
+    let chain = LocalChain::start(HashMap::from([(
+        DCAP_ATTESTATION,
+        attestation_returns(false, b"Invalid PCK certificate".to_vec()),
+    )]))
+    .await;
+    let verification = reader(chain.url()).simulate_onchain_verification(&raw_quote).await;
+    assert!(verification.is_err());
+}
+
+#[tokio::test]
+async fn test_reports_the_revert_reason() {
+    let data = Revert::from("TCB Info expired").abi_encode();
+    let url = failing_node(json!({
+        "code": 3,
+        "message": "execution reverted: TCB Info expired",
+        "data": Bytes::from(data),
+    }))
+    .await;
+
+    let verification = reader(&url)
+        .simulate_onchain_verification(&quote("v3_sgx_quote.hex"))
+        .await
+        .unwrap();
+    assert!(!verification.verified);
+    assert_eq!(verification.error.as_deref(), Some("TCB Info expired"));
+    assert_eq!(verification.missing, MissingCollateral::None);
+}
+
+#[tokio::test]
+async fn test_fails_on_node_errors() {
+    // not a revert, the quote was not verified at all
+    let url = failing_node(json!({ "code": -32000, "message": "missing trie node" })).await;
+    let verification = reader(&url)
+        .simulate_onchain_verification(&quote("v3_sgx_quote.hex"))
+        .await;
+    assert!(verification.unwrap_err().to_string().contains("missing trie node"));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::eips::{BlockId, BlockNumberOrTag};
+
+use common::{
+    certificate_returns, identity_returns, no_tcb_info_returns, quote, reader, tcb_info_returns,
+    LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+};
+use pccs_reader_rs::{MissingCollateral, CA};
+
+#[tokio::test]
+async fn test_scan_deduplicates_lookups() {
+    // every certificate and identity is on chain, no TCB Info is
+    let chain = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (ENCLAVE_ID_DAO, identity_returns()),
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+    ]))
+    .await;
+    let reader = reader(chain.url());
+
+    let v3 = quote("v3_sgx_quote.hex");
+    let v4 = quote("v4_tdx_quote.hex");
+    let quotes = vec![v3.clone(), v4.clone(), v3.clone(), v4.clone(), vec![0x03, 0x00]];
+
+    let report = reader.find_missing_collaterals_from_quotes(&quotes, 2).await.unwrap();
+
+    // ROOT, SIGNING, PLATFORM, QE v3, TDQE v4 and one TCB Info per FMSPC
+    assert_eq!(report.lookups, 7);
+    assert_eq!(
+        report.missing,
+        vec![
+            MissingCollateral::FMSPCTCB(0, "00606a000000".to_string(), 2),
+            MissingCollateral::FMSPCTCB(1, "90c06f000000".to_string(), 3),
+        ]
+    );
+    assert!(!report.is_complete());
+
+    assert_eq!(report.quotes.len(), 5);
+    assert_eq!(report.quotes[0].pck_ca, Some(CA::PLATFORM));
+    assert_eq!(report.quotes[2].missing, report.missing[0]);
+    assert_eq!(report.quotes[3].missing, report.missing[1]);
+    assert!(report.quotes[4].error.is_some());
+    // the whole batch is read at the block the latest tag resolved to
+    assert!(matches!(report.block, BlockId::Number(BlockNumberOrTag::Number(_))));
+
+    // a single quote gets the same answer as the one by one check
+    let single = reader.find_missing_collaterals_from_quotes(&[&v4], 1).await.unwrap();
+    assert_eq!(single.quotes[0].missing, reader.find_missing_collaterals_from_quote(&v4).await);
+
+    // one check is pinned to a single block, which it reports
+    let check = reader.check_quote_collaterals(&v4).await.unwrap();
+    assert_eq!(check.missing, single.quotes[0].missing);
+    assert!(matches!(check.block, BlockId::Number(BlockNumberOrTag::Number(_))));
+
+    let pinned = reader.at_block(1).check_quote_collaterals(&v4).await.unwrap();
+    assert_eq!(pinned.block, BlockId::number(1));
+}
+
+#[tokio::test]
+async fn test_scan_reports_failed_reads() {
+    // the Enclave ID DAO answers nothing that decodes, which is a failed read, not a missing identity
+    let chain = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (FMSPC_TCB_DAO, tcb_info_returns(2, "00606a000000")),
+    ]))
+    .await;
+    let v3 = quote("v3_sgx_quote.hex");
+
+    let report = reader(chain.url())
+        .find_missing_collaterals_from_quotes(&[&v3], 1)
+        .await
+        .unwrap();
+    assert!(report.missing.is_empty());
+    assert_eq!(report.quotes[0].missing, MissingCollateral::None);
+    assert!(report.quotes[0].error.as_ref().unwrap().contains("QeIdentity"));
+    assert!(!report.is_complete());
+
+    // a block that cannot be resolved fails the whole scan
+    drop(chain);
+    let unreachable = reader("http://127.0.0.1:1");
+    assert!(unreachable.find_missing_collaterals_from_quotes(&[&v3], 1).await.is_err());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common::{full_chain, quote, LocalChain};
+use pccs_reader_rs::cache::{CacheConfig, CollateralCache};
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::{MissingCollateral, PccsReader, CA};
+
+fn reader(url: &str, cache: &Arc<CollateralCache>) -> PccsReader {
+    common::reader(url).with_cache(cache.clone())
+}
+
+#[tokio::test]
+async fn test_serves_reads_from_the_cache() {
+    let full = full_chain().await;
+    let empty = LocalChain::start(HashMap::new()).await;
+    let cache = Arc::new(CollateralCache::default());
+    let v3 = quote("v3_sgx_quote.hex");
+
+    let reader = reader(full.url(), &cache);
+    assert!(reader.get_certificate_by_id(CA::ROOT).await.is_ok());
+    assert!(reader.get_tcb_info(0, "00606a000000", 2).await.is_ok());
+    assert!(reader.get_enclave_identity(EnclaveIdType::QE, 3).await.is_ok());
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (0, 3, 3));
+
+    // the same DAO addresses on a chain without the collateral, only the cache has it
+    let cached = self::reader(empty.url(), &cache);
+    assert!(cached.get_certificate_by_id(CA::ROOT).await.is_ok());
+    assert!(cached.get_tcb_info(0, "00606a000000", 2).await.is_ok());
+    assert!(cached.get_enclave_identity(EnclaveIdType::QE, 3).await.is_ok());
+    assert_eq!(cache.stats().hits, 3);
+
+    // explicit blocks are read from chain
+    let historical = cached.at_block(1);
+    assert!(historical.get_enclave_identity(EnclaveIdType::QE, 3).await.is_err());
+    assert_eq!(cache.stats().hits, 3);
+
+    // a check is pinned to a block, it reads the entries a pinned read stored at or before it
+    assert_eq!(reader.find_missing_collaterals_from_quote(&v3).await, MissingCollateral::None);
+    let hits = cache.stats().hits;
+    assert_eq!(cached.find_missing_collaterals_from_quote(&v3).await, MissingCollateral::None);
+    assert!(cache.stats().hits > hits);
+
+    assert!(cached.invalidate_enclave_identity(EnclaveIdType::QE, 3));
+    assert!(cached.get_enclave_identity(EnclaveIdType::QE, 3).await.is_err());
+    assert_eq!(cache.stats().invalidations, 1);
+}
+
+#[tokio::test]
+async fn test_persists_the_cache() {
+    let full = full_chain().await;
+    let empty = LocalChain::start(HashMap::new()).await;
+    let path = std::env::temp_dir().join(format!("pccs-cache-{}.json", std::process::id()));
+    let config = CacheConfig {
+        path: Some(path.clone()),
+        ..Default::default()
+    };
+
+    let cache = Arc::new(CollateralCache::new(config.clone()).unwrap());
+    let root = reader(full.url(), &cache).get_certificate_by_id(CA::ROOT).await.unwrap();
+    let tcb_info = reader(full.url(), &cache)
+        .get_tcb_info(0, "00606a000000", 2)
+        .await
+        .unwrap();
+
+    // a new process picks the entries up from disk
+    let reloaded = Arc::new(CollateralCache::new(config).unwrap());
+    assert_eq!(reloaded.stats().entries, 2);
+    let reader = reader(empty.url(), &reloaded);
+    assert_eq!(reader.get_certificate_by_id(CA::ROOT).await.unwrap(), root);
+    assert_eq!(reader.get_tcb_info(0, "00606a000000", 2).await.unwrap(), tcb_info);
+
+    std::fs::remove_file(path).unwrap();
+}
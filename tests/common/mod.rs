@@ -0,0 +1,354 @@
+//! Local chains for the integration tests: an anvil node when it is installed,
+//! or a minimal JSON-RPC server that answers `eth_call` the same way otherwise.
+//! The mock also accepts raw transactions and reports them as mined right away.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::{keccak256, Address, Bytes, B256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::{Block, Header, Log};
+use alloy::sol_types::SolCall;
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use pccs_reader_rs::pccs::enclave_id::{EnclaveIdentityJsonObj, IEnclaveIdentityDao};
+use pccs_reader_rs::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use pccs_reader_rs::pccs::pcs::IPCSDao;
+use pccs_reader_rs::{NetworkProfile, PccsReader};
+
+// Where the tests deploy the DAOs and the DCAP attestation entrypoint
+pub const PCS_DAO: Address = Address::repeat_byte(0x11);
+pub const FMSPC_TCB_DAO: Address = Address::repeat_byte(0x22);
+pub const ENCLAVE_ID_DAO: Address = Address::repeat_byte(0x33);
+pub const DCAP_ATTESTATION: Address = Address::repeat_byte(0x55);
+
+// The first anvil account
+pub const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+// What the mock PCS DAO stores for every CA
+pub const CERT: [u8; 4] = [0x30, 0x82, 0x01, 0x0a];
+pub const CRL: [u8; 3] = [0x30, 0x81, 0x99];
+
+/// A file of `tests/data`.
+pub fn data(name: &str) -> Vec<u8> {
+    std::fs::read(format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+/// A hex encoded quote of `tests/data`.
+pub fn quote(name: &str) -> Vec<u8> {
+    hex::decode(String::from_utf8(data(name)).unwrap().trim()).unwrap()
+}
+
+/// The default network profile, on `url` and with the DAOs at the test addresses.
+pub fn network(url: &str) -> NetworkProfile {
+    let mut network = NetworkProfile::default().with_rpc_url(url);
+    network.pcs_dao = PCS_DAO;
+    network.fmspc_tcb_dao = FMSPC_TCB_DAO;
+    network.enclave_id_dao = ENCLAVE_ID_DAO;
+    network.dcap_attestation = DCAP_ATTESTATION;
+    network
+}
+
+pub fn reader(url: &str) -> PccsReader {
+    PccsReader::new(network(url)).unwrap()
+}
+
+pub fn certificate_returns() -> Vec<u8> {
+    IPCSDao::getCertificateByIdCall::abi_encode_returns(&(
+        Bytes::from(CERT.to_vec()),
+        Bytes::from(CRL.to_vec()),
+    ))
+}
+
+pub fn tcb_info_returns(version: u32, fmspc: &str) -> Vec<u8> {
+    IFmspcTcbDao::getTcbInfoCall::abi_encode_returns(&(TcbInfoJsonObj {
+        tcbInfoStr: format!(r#"{{"id":"SGX","version":{},"fmspc":"{}"}}"#, version, fmspc),
+        signature: vec![0xaa; 64].into(),
+    },))
+}
+
+/// What the FMSPC TCB DAO returns for a TCB Info it does not store.
+pub fn no_tcb_info_returns() -> Vec<u8> {
+    IFmspcTcbDao::getTcbInfoCall::abi_encode_returns(&(TcbInfoJsonObj {
+        tcbInfoStr: String::new(),
+        signature: Bytes::new(),
+    },))
+}
+
+pub fn identity_returns() -> Vec<u8> {
+    IEnclaveIdentityDao::getEnclaveIdentityCall::abi_encode_returns(&(EnclaveIdentityJsonObj {
+        identityStr: r#"{"id":"QE","version":2}"#.to_string(),
+        signature: vec![0xbb; 64].into(),
+    },))
+}
+
+/// What the Enclave Identity DAO returns for an identity it does not store.
+pub fn no_identity_returns() -> Vec<u8> {
+    IEnclaveIdentityDao::getEnclaveIdentityCall::abi_encode_returns(&(EnclaveIdentityJsonObj {
+        identityStr: String::new(),
+        signature: Bytes::new(),
+    },))
+}
+
+/// DAOs storing a certificate and CRL for every CA, the given TCB Info and a QE identity.
+pub fn dao_contracts(tcb_version: u32, fmspc: &str) -> HashMap<Address, Vec<u8>> {
+    HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (FMSPC_TCB_DAO, tcb_info_returns(tcb_version, fmspc)),
+        (ENCLAVE_ID_DAO, identity_returns()),
+    ])
+}
+
+/// A chain with all the collateral of the v3 SGX quote of `tests/data`.
+pub async fn full_chain() -> LocalChain {
+    LocalChain::start(dao_contracts(2, "00606a000000")).await
+}
+
+/// Runtime code that returns `data` for every call, whatever the calldata.
+pub fn constant_return_code(data: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(data.len()).expect("return data too large");
+    let [len_hi, len_lo] = len.to_be_bytes();
+    let mut code = vec![
+        0x61, len_hi, len_lo, // PUSH2 len
+        0x80, // DUP1
+        0x61, 0x00, 0x0d, // PUSH2 13, where the data starts
+        0x60, 0x00, // PUSH1 0
+        0x39, // CODECOPY
+        0x60, 0x00, // PUSH1 0
+        0xf3, // RETURN
+    ];
+    code.extend_from_slice(data);
+    code
+}
+
+pub enum LocalChain {
+    Anvil { node: Child, url: String },
+    Mock {
+        url: String,
+        logs: Arc<Mutex<Vec<Log>>>,
+    },
+}
+
+impl LocalChain {
+    /// Starts a chain where every address in `contracts` answers calls with its return data.
+    pub async fn start(contracts: HashMap<Address, Vec<u8>>) -> LocalChain {
+        match start_anvil(&contracts).await {
+            Some(chain) => chain,
+            None => LocalChain::mock_with_logs(contracts, Vec::new()).await,
+        }
+    }
+
+    /// Starts an anvil node, for the tests that must run against a real chain.
+    pub async fn anvil(contracts: HashMap<Address, Vec<u8>>) -> LocalChain {
+        start_anvil(&contracts).await.expect("anvil is required")
+    }
+
+    /// Starts the mock chain, whose `eth_getLogs` serves the given logs. Its head is the
+    /// block of the last log.
+    pub async fn mock_with_logs(contracts: HashMap<Address, Vec<u8>>, logs: Vec<Log>) -> LocalChain {
+        let logs = Arc::new(Mutex::new(logs));
+        LocalChain::Mock {
+            url: start_mock_rpc(contracts, logs.clone()).await,
+            logs,
+        }
+    }
+
+    /// Replaces the logs of the mock chain, e.g. to add blocks or to reorg it.
+    pub fn set_logs(&self, new_logs: Vec<Log>) {
+        if let LocalChain::Mock { logs, .. } = self {
+            *logs.lock().unwrap() = new_logs;
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        match self {
+            LocalChain::Anvil { url, .. } => url,
+            LocalChain::Mock { url, .. } => url,
+        }
+    }
+}
+
+impl Drop for LocalChain {
+    fn drop(&mut self) {
+        if let LocalChain::Anvil { node, .. } = self {
+            let _ = node.kill();
+        }
+    }
+}
+
+async fn start_anvil(contracts: &HashMap<Address, Vec<u8>>) -> Option<LocalChain> {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .ok()?
+        .local_addr()
+        .ok()?
+        .port();
+    let node = Command::new("anvil")
+        .args(["--port", &port.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let url = format!("http://127.0.0.1:{}", port);
+    let chain = LocalChain::Anvil { node, url: url.clone() };
+
+    let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+    for _ in 0..50 {
+        if provider.get_chain_id().await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    for (address, data) in contracts {
+        let code = Bytes::from(constant_return_code(data));
+        provider
+            .raw_request::<_, ()>("anvil_setCode".into(), (address, code))
+            .await
+            .ok()?;
+    }
+
+    Some(chain)
+}
+
+struct MockState {
+    contracts: HashMap<Address, Vec<u8>>,
+    // raw transactions by hash
+    transactions: Mutex<HashMap<B256, Bytes>>,
+    logs: Arc<Mutex<Vec<Log>>>,
+}
+
+async fn start_mock_rpc(contracts: HashMap<Address, Vec<u8>>, logs: Arc<Mutex<Vec<Log>>>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let state = MockState {
+        contracts,
+        transactions: Mutex::new(HashMap::new()),
+        logs,
+    };
+    let app = Router::new()
+        .route("/", post(mock_rpc))
+        .with_state(Arc::new(state));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    url
+}
+
+async fn mock_rpc(State(state): State<Arc<MockState>>, Json(request): Json<Value>) -> Json<Value> {
+    let contracts = &state.contracts;
+    let params = &request["params"];
+    let result = match request["method"].as_str() {
+        Some("eth_chainId") => json!("0x7a69"),
+        Some("eth_call") => {
+            let to: Option<Address> = serde_json::from_value(params[0]["to"].clone()).ok();
+            let data = to.and_then(|to| contracts.get(&to)).cloned().unwrap_or_default();
+            json!(Bytes::from(data))
+        }
+        Some("eth_getCode") => {
+            let address: Option<Address> = serde_json::from_value(params[0].clone()).ok();
+            let code = address
+                .and_then(|address| contracts.get(&address))
+                .map(|data| constant_return_code(data))
+                .unwrap_or_default();
+            json!(Bytes::from(code))
+        }
+        Some("eth_blockNumber") => json!(format!("{:#x}", head(&state.logs.lock().unwrap()))),
+        Some("eth_getLogs") => json!(matching_logs(&state.logs.lock().unwrap(), &params[0])),
+        Some("eth_getBlockByNumber") | Some("eth_getBlockByHash") => mock_block(),
+        Some("eth_gasPrice") => json!("0x3b9aca00"),
+        Some("eth_estimateGas") => json!("0x100000"),
+        Some("eth_getTransactionCount") => {
+            json!(format!("{:#x}", state.transactions.lock().unwrap().len()))
+        }
+        Some("eth_feeHistory") => json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]],
+        }),
+        Some("eth_sendRawTransaction") => {
+            let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap_or_default();
+            let hash = keccak256(&raw);
+            state.transactions.lock().unwrap().insert(hash, raw);
+            json!(hash)
+        }
+        Some("eth_getTransactionReceipt") => {
+            let hash: Option<B256> = serde_json::from_value(params[0].clone()).ok();
+            match hash.filter(|hash| state.transactions.lock().unwrap().contains_key(hash)) {
+                Some(hash) => mined_receipt(hash),
+                None => Value::Null,
+            }
+        }
+        _ => {
+            return Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": "method not found" },
+            }))
+        }
+    };
+
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+fn head(logs: &[Log]) -> u64 {
+    logs.iter().filter_map(|log| log.block_number).max().unwrap_or(1)
+}
+
+// Logs in the block range of the filter, from any of its addresses
+fn matching_logs(logs: &[Log], filter: &Value) -> Vec<Log> {
+    let block = |key: &str, default: u64| {
+        filter[key]
+            .as_str()
+            .and_then(|block| u64::from_str_radix(block.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(default)
+    };
+    let (from, to) = (block("fromBlock", 0), block("toBlock", head(logs)));
+    let addresses: Vec<Address> = match &filter["address"] {
+        Value::Array(_) => serde_json::from_value(filter["address"].clone()).unwrap_or_default(),
+        Value::Null => Vec::new(),
+        address => serde_json::from_value(address.clone()).into_iter().collect(),
+    };
+    logs.iter()
+        .filter(|log| log.block_number.is_some_and(|number| (from..=to).contains(&number)))
+        .filter(|log| addresses.is_empty() || addresses.contains(&log.address()))
+        .cloned()
+        .collect()
+}
+
+/// The hash of the only block of the mock chain, the one transactions are mined in.
+pub const MOCK_BLOCK_HASH: B256 = B256::repeat_byte(0x01);
+
+fn mock_block() -> Value {
+    let block: Block = Block {
+        header: Header {
+            hash: Some(MOCK_BLOCK_HASH),
+            number: Some(1),
+            state_root: B256::repeat_byte(0x02),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    json!(block)
+}
+
+fn mined_receipt(hash: B256) -> Value {
+    json!({
+        "type": "0x2",
+        "status": "0x1",
+        "transactionHash": hash,
+        "transactionIndex": "0x0",
+        "blockHash": MOCK_BLOCK_HASH,
+        "blockNumber": "0x1",
+        "from": Address::ZERO,
+        "to": Address::ZERO,
+        "contractAddress": null,
+        "cumulativeGasUsed": "0x5208",
+        "gasUsed": "0x5208",
+        "effectiveGasPrice": "0x3b9aca00",
+        "logs": [],
+        "logsBloom": Bytes::from(vec![0u8; 256]),
+    })
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::{Address, B256, U256};
+use alloy::sol_types::SolValue;
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use common::{network, quote, reader, LocalChain, DCAP_ATTESTATION};
+use pccs_reader_rs::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use pccs_reader_rs::pccs::fmspc_tcb::TcbInfoJsonObj;
+use pccs_reader_rs::proof::{bytes_storage, DaoStorageLayout, StorageLayout};
+use pccs_reader_rs::source::CollateralSource;
+use pccs_reader_rs::{MissingCollateral, PccsReader, CA};
+
+const DAO_STORAGE: Address = Address::repeat_byte(0x44);
+
+struct StaticSource;
+
+#[async_trait]
+impl CollateralSource for StaticSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let crl = match ca {
+            CA::SIGNING => vec![],
+            _ => vec![0x30, 0x81, ca as u8],
+        };
+        Ok((vec![0x30, 0x82, ca as u8], crl))
+    }
+
+    async fn tcb_info(&self, _tcb_type: u8, fmspc: &str, _version: u32) -> Result<TcbInfoJsonObj> {
+        Ok(TcbInfoJsonObj {
+            tcbInfoStr: format!(r#"{{"id":"SGX","version":2,"fmspc":"{}"}}"#, fmspc),
+            signature: vec![0xaa; 64].into(),
+        })
+    }
+
+    async fn enclave_identity(
+        &self,
+        _id: EnclaveIdType,
+        _version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        Ok(EnclaveIdentityJsonObj {
+            identityStr: r#"{"id":"QE","version":2}"#.to_string(),
+            signature: vec![0xbb; 64].into(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_quote_cost() {
+    // nothing on chain, every collateral of the quote is upserted
+    let chain = LocalChain::mock_with_logs(HashMap::new(), Vec::new()).await;
+    let report = reader(chain.url())
+        .quote_cost(&quote("v3_sgx_quote.hex"), &StaticSource)
+        .await
+        .unwrap();
+
+    let actions: Vec<&str> = report.estimates.iter().map(|estimate| estimate.action.as_str()).collect();
+    assert_eq!(actions.len(), 8);
+    assert_eq!(actions[0], "ROOT CA certificate");
+    assert!(actions.contains(&"QE identity (version 3)"));
+    assert_eq!(actions[7], "verifyAndAttestOnChain");
+    assert!(report.unestimated.is_empty());
+
+    assert!(report.max_fee_per_gas > 0);
+    assert_eq!(report.total_gas, report.estimates.iter().map(|estimate| estimate.gas).sum::<u64>());
+    assert_eq!(report.total_max_cost_wei, report.total_gas as u128 * report.max_fee_per_gas);
+}
+
+#[tokio::test]
+async fn test_missing_collateral_cost() {
+    let chain = LocalChain::mock_with_logs(HashMap::new(), Vec::new()).await;
+    let missing = [
+        MissingCollateral::QEIdentity(EnclaveIdType::QE, 4),
+        MissingCollateral::PCS(CA::PLATFORM, false, true),
+        MissingCollateral::None,
+    ];
+    let report = reader(chain.url())
+        .missing_collateral_cost(&missing, &StaticSource)
+        .await
+        .unwrap();
+
+    let actions: Vec<&str> = report.estimates.iter().map(|estimate| estimate.action.as_str()).collect();
+    assert_eq!(actions, ["PLATFORM CA CRL", "QE identity (version 4)"]);
+    assert_eq!(report.estimates[0].max_cost_wei, report.estimates[0].gas as u128 * report.max_fee_per_gas);
+}
+
+// A node with nothing stored, on which the verification reverts unless it is estimated
+// over state overrides, which it keeps
+async fn start_node() -> (String, Arc<Mutex<Vec<Value>>>) {
+    let overrides = Arc::new(Mutex::new(Vec::new()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = Router::new().route("/", post(rpc)).with_state(overrides.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (url, overrides)
+}
+
+async fn rpc(
+    State(overrides): State<Arc<Mutex<Vec<Value>>>>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let params = &request["params"];
+    let result = match request["method"].as_str() {
+        Some("eth_chainId") => json!("0x7a69"),
+        Some("eth_blockNumber") => json!("0x1"),
+        Some("eth_call") => json!("0x"),
+        Some("eth_estimateGas") if params[0]["to"] == json!(DCAP_ATTESTATION) => {
+            overrides.lock().unwrap().push(params[2].clone());
+            if params[2].is_null() {
+                return Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": { "code": 3, "message": "execution reverted", "data": "0x" },
+                }));
+            }
+            json!("0x200000")
+        }
+        Some("eth_estimateGas") => json!("0x100000"),
+        Some("eth_feeHistory") => json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]],
+        }),
+        _ => Value::Null,
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+#[tokio::test]
+async fn test_estimates_the_verification_over_the_upserts() {
+    let (url, overrides) = start_node().await;
+    let layout = DaoStorageLayout::new(DAO_STORAGE, U256::from(3));
+    let mut network = network(&url);
+    network.storage = Some(layout);
+    let report = PccsReader::new(network)
+        .unwrap()
+        .quote_cost(&quote("v3_sgx_quote.hex"), &StaticSource)
+        .await
+        .unwrap();
+
+    assert!(report.unestimated.is_empty(), "{:?}", report.unestimated);
+    let verification = report.estimates.last().unwrap();
+    assert_eq!(verification.action, "verifyAndAttestOnChain");
+    assert_eq!(verification.gas, 0x200000);
+
+    // the TCB Info the quote lacks was where the layout keeps it
+    let tcb_info = StaticSource.tcb_info(0, "00606a000000", 2).await.unwrap();
+    let slot = layout.tcb_info(0, [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00], 2).slot;
+    let overrides = overrides.lock().unwrap();
+    assert_eq!(overrides.len(), 1);
+    let state_diff: HashMap<B256, B256> =
+        serde_json::from_value(overrides[0][DAO_STORAGE.to_string()]["stateDiff"].clone()).unwrap();
+    for (slot, word) in bytes_storage(slot, &tcb_info.abi_encode()) {
+        assert_eq!(state_diff.get(&slot), Some(&word));
+    }
+}
+
+#[tokio::test]
+async fn test_leaves_the_verification_unestimated_without_a_layout() {
+    let (url, overrides) = start_node().await;
+    let mut network = network(&url);
+    network.storage = None;
+    let report = PccsReader::new(network)
+        .unwrap()
+        .quote_cost(&quote("v3_sgx_quote.hex"), &StaticSource)
+        .await
+        .unwrap();
+
+    assert_eq!(report.estimates.len(), 7);
+    assert_eq!(report.unestimated.len(), 1);
+    assert_eq!(report.unestimated[0].0, "verifyAndAttestOnChain");
+    assert_eq!(*overrides.lock().unwrap(), [Value::Null]);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Bytes;
+use alloy::sol_types::SolCall;
+
+use common::{
+    certificate_returns, network, no_tcb_info_returns, tcb_info_returns, LocalChain,
+    ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO, PRIVATE_KEY,
+};
+use pccs_reader_rs::diff::{DiffStatus, Side};
+use pccs_reader_rs::pccs::enclave_id::{
+    EnclaveIdType, EnclaveIdentityJsonObj, IEnclaveIdentityDao,
+};
+use pccs_reader_rs::writer::{signer_from_private_key, UpsertKind};
+use pccs_reader_rs::{MissingCollateral, PccsReader, CA};
+
+fn reader(name: &str, url: &str) -> PccsReader {
+    let mut network = network(url);
+    network.name = name.to_string();
+    PccsReader::new(network).unwrap()
+}
+
+fn identity(tcb_evaluation_data_number: u64) -> Vec<u8> {
+    IEnclaveIdentityDao::getEnclaveIdentityCall::abi_encode_returns(&(EnclaveIdentityJsonObj {
+        identityStr: format!(
+            r#"{{"id":"QE","version":2,"issueDate":"2024-06-01T00:00:00Z","tcbEvaluationDataNumber":{}}}"#,
+            tcb_evaluation_data_number
+        ),
+        signature: vec![0xbb; 64].into(),
+    },))
+}
+
+// Both store the same certificates, A a newer QE identity and the only TCB Info
+async fn chains() -> (LocalChain, LocalChain) {
+    let certificate = certificate_returns();
+    let tcb_info = tcb_info_returns(3, "00606a000000");
+
+    let a = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate.clone()),
+        (FMSPC_TCB_DAO, tcb_info),
+        (ENCLAVE_ID_DAO, identity(17)),
+    ]))
+    .await;
+    let b = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate),
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+        (ENCLAVE_ID_DAO, identity(16)),
+    ]))
+    .await;
+    (a, b)
+}
+
+#[tokio::test]
+async fn test_diffs_networks() {
+    let (a, b) = chains().await;
+    let fmspcs = ["00606A000000".to_string()];
+
+    let diff = reader("mainnet", a.url())
+        .diff(&reader("sepolia", b.url()), &fmspcs)
+        .await
+        .unwrap();
+    assert_eq!(
+        (diff.network_a.as_str(), diff.network_b.as_str()),
+        ("mainnet", "sepolia")
+    );
+    assert!(!diff.is_in_sync());
+
+    let status = |collateral: &UpsertKind| {
+        diff.collaterals
+            .iter()
+            .find(|diff| &diff.collateral == collateral)
+            .map(|diff| diff.status)
+    };
+    assert_eq!(
+        status(&UpsertKind::PcsCertificate(CA::ROOT)),
+        Some(DiffStatus::Identical)
+    );
+    assert_eq!(
+        status(&UpsertKind::PcsCrl(CA::PLATFORM)),
+        Some(DiffStatus::Identical)
+    );
+    // the TCB Signing CA has no CRL
+    assert_eq!(status(&UpsertKind::PcsCrl(CA::SIGNING)), None);
+    assert_eq!(
+        status(&UpsertKind::EnclaveIdentity(EnclaveIdType::QE, 4)),
+        Some(DiffStatus::Newer(Side::A))
+    );
+    assert_eq!(
+        status(&UpsertKind::FmspcTcb(0, "00606a000000".to_string(), 3)),
+        Some(DiffStatus::OnlyOn(Side::A))
+    );
+
+    assert!(diff.missing_on(Side::A).is_empty());
+    let missing = diff.missing_on(Side::B);
+    assert_eq!(missing.len(), 8);
+    assert!(missing.contains(&MissingCollateral::QEIdentity(EnclaveIdType::TDQE, 4)));
+    assert!(missing.contains(&MissingCollateral::FMSPCTCB(
+        1,
+        "00606a000000".to_string(),
+        3
+    )));
+}
+
+#[tokio::test]
+async fn test_syncs_newer_collateral() {
+    let (a, b) = chains().await;
+    let (from, to) = (reader("mainnet", a.url()), reader("sepolia", b.url()));
+    let fmspcs = ["00606a000000".to_string()];
+
+    let calls = to.sync_calls(&from, &fmspcs).await.unwrap();
+    assert_eq!(calls.len(), 8);
+    // identities go before TCB Infos
+    assert!(matches!(calls[0].kind, UpsertKind::EnclaveIdentity(..)));
+    assert!(matches!(calls[7].kind, UpsertKind::FmspcTcb(..)));
+
+    // the upsert carries the identity exactly as signed on the other network
+    let call =
+        IEnclaveIdentityDao::upsertEnclaveIdentityCall::abi_decode(&calls[0].input, true).unwrap();
+    assert!(call
+        .enclaveIdentityObj
+        .identityStr
+        .contains(r#""tcbEvaluationDataNumber":17"#));
+    assert_eq!(
+        call.enclaveIdentityObj.signature,
+        Bytes::from(vec![0xbb; 64])
+    );
+
+    let signer = signer_from_private_key(PRIVATE_KEY).unwrap();
+    let submitted = to.sync_from(&from, &fmspcs, signer).await.unwrap();
+    assert_eq!(
+        submitted
+            .into_iter()
+            .map(|(call, _)| call)
+            .collect::<Vec<_>>(),
+        calls
+    );
+}
+
+#[tokio::test]
+async fn test_diff_fails_on_read_errors() {
+    let (a, _) = chains().await;
+    // an identity DAO whose answer does not decode is not a DAO without identities
+    let broken = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+        (ENCLAVE_ID_DAO, vec![0x01]),
+    ]))
+    .await;
+
+    let fmspcs = ["00606A000000".to_string()];
+    let diff = reader("mainnet", a.url())
+        .diff(&reader("sepolia", broken.url()), &fmspcs)
+        .await;
+    assert!(diff.is_err());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::{keccak256, Address, B256};
+
+use common::{
+    constant_return_code, LocalChain, DCAP_ATTESTATION, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+};
+use pccs_reader_rs::network::CodeHashes;
+use pccs_reader_rs::{NetworkProfile, PccsReader};
+
+const PCK_DAO: Address = Address::repeat_byte(0x44);
+const PCCS_ROUTER: Address = Address::repeat_byte(0x77);
+
+fn network(url: &str) -> NetworkProfile {
+    let mut network = common::network(url);
+    network.chain_id = 31337;
+    network.pck_dao = PCK_DAO;
+    network.pccs_router = PCCS_ROUTER;
+    network
+}
+
+fn deployed() -> HashMap<Address, Vec<u8>> {
+    [ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO, PCK_DAO, DCAP_ATTESTATION, PCCS_ROUTER]
+        .into_iter()
+        .map(|address| (address, vec![0x01]))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_self_check_healthy() {
+    let chain = LocalChain::mock_with_logs(deployed(), Vec::new()).await;
+    let mut network = network(chain.url());
+    network.code_hashes = Some(CodeHashes {
+        pcs_dao: Some(keccak256(constant_return_code(&[0x01]))),
+        ..Default::default()
+    });
+    let report = PccsReader::new(network).unwrap().self_check().await.unwrap();
+
+    assert!(report.chain_id_matches());
+    assert_eq!(report.contracts.len(), 6);
+    assert!(report.contracts.iter().all(|contract| contract.has_code()));
+    assert_eq!(report.contracts[2].name, "PCS_DAO");
+    assert_eq!(report.contracts[2].code_hash_matches(), Some(true));
+    assert_eq!(report.contracts[3].code_hash_matches(), None);
+    assert!(report.is_healthy(), "{:?}", report.problems());
+}
+
+#[tokio::test]
+async fn test_self_check_wrong_chain() {
+    let mut contracts = deployed();
+    contracts.remove(&PCK_DAO);
+    let chain = LocalChain::mock_with_logs(contracts, Vec::new()).await;
+    let mut network = network(chain.url());
+    network.chain_id = 65536;
+    network.code_hashes = Some(CodeHashes {
+        pcs_dao: Some(B256::repeat_byte(0xaa)),
+        ..Default::default()
+    });
+    let report = PccsReader::new(network).unwrap().self_check().await.unwrap();
+
+    assert_eq!(report.chain_id, Some(31337));
+    let problems = report.problems();
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems[0].contains("serves chain 31337"));
+    assert!(problems[1].starts_with("PCS_DAO"));
+    assert!(problems[2].starts_with("PCK_DAO has no code"));
+}
+
+#[tokio::test]
+async fn test_self_check_unreachable() {
+    // nothing listens on the port of a dropped listener
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut network = network(&format!("http://127.0.0.1:{}", port));
+    network.fallback_rpc_urls.clear();
+    let report = PccsReader::new(network).unwrap().self_check().await.unwrap();
+
+    assert_eq!(report.chain_id, None);
+    assert!(report.rpc_error.is_some());
+    assert!(report.contracts.is_empty());
+    assert!(!report.is_healthy());
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::collections::{BTreeSet, HashMap};
+
+use alloy::primitives::{Address, FixedBytes, LogData, B256, U256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+
+use common::{network, LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO};
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::pccs::events::{
+    CollateralItem, EventIndex, EventIndexer, IEnclaveIdentityDaoEvents, IFmspcTcbDaoEvents,
+    IPCSDaoEvents,
+};
+use pccs_reader_rs::{PccsReader, CA};
+
+const PCK_DAO: Address = Address::repeat_byte(0x44);
+
+fn reader(url: &str) -> PccsReader {
+    let mut network = network(url);
+    network.pck_dao = PCK_DAO;
+    PccsReader::new(network).unwrap()
+}
+
+fn log(address: Address, data: LogData, block_number: u64, log_index: u64) -> Log {
+    Log {
+        inner: alloy::primitives::Log { address, data },
+        block_number: Some(block_number),
+        block_hash: Some(B256::with_last_byte(block_number as u8)),
+        transaction_hash: Some(B256::with_last_byte(log_index as u8)),
+        log_index: Some(log_index),
+        ..Default::default()
+    }
+}
+
+fn tcb_info_upsert(fmspc: [u8; 6], version: u32) -> LogData {
+    IFmspcTcbDaoEvents::UpsertedFmspcTcb {
+        tcbType: 0,
+        fmspc: FixedBytes(fmspc),
+        version,
+        attestationId: B256::ZERO,
+    }
+    .encode_log_data()
+}
+
+fn history() -> Vec<Log> {
+    let root_crl = IPCSDaoEvents::UpsertedPcsCrl {
+        ca: 0,
+        attestationId: B256::ZERO,
+    }
+    .encode_log_data();
+    let qe_identity = IEnclaveIdentityDaoEvents::UpsertedEnclaveIdentity {
+        id: U256::from(0),
+        version: U256::from(4),
+        attestationId: B256::ZERO,
+    }
+    .encode_log_data();
+
+    vec![
+        log(PCS_DAO, root_crl, 2, 0),
+        log(FMSPC_TCB_DAO, tcb_info_upsert([0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00], 3), 3, 1),
+        log(ENCLAVE_ID_DAO, qe_identity, 5, 2),
+        log(FMSPC_TCB_DAO, tcb_info_upsert([0x00, 0x60, 0x6a, 0x00, 0x00, 0x00], 2), 7, 3),
+        // not one of the DAOs
+        log(Address::repeat_byte(0x99), tcb_info_upsert([0xff; 6], 3), 8, 4),
+        log(FMSPC_TCB_DAO, tcb_info_upsert([0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00], 3), 9, 5),
+    ]
+}
+
+#[tokio::test]
+async fn test_indexes_upserts() {
+    let chain = LocalChain::mock_with_logs(HashMap::new(), history()).await;
+    let indexer = EventIndexer::new(reader(chain.url())).with_chunk_size(3);
+
+    let mut index = EventIndex::new(0);
+    assert_eq!(indexer.index_to_latest(&mut index).await.unwrap(), 5);
+    assert_eq!(index.checkpoint, Some(9));
+
+    assert_eq!(
+        index.fmspcs_with_tcb_info(),
+        BTreeSet::from(["00606a000000".to_string(), "00906ed50000".to_string()])
+    );
+    let updates = index.tcb_info_history("00906ED50000");
+    assert_eq!(
+        updates.iter().map(|event| event.block_number).collect::<Vec<_>>(),
+        vec![3, 9]
+    );
+    assert_eq!(index.history(&CollateralItem::PcsCrl(CA::ROOT))[0].block_number, 2);
+    assert_eq!(
+        index.items()[2],
+        &CollateralItem::EnclaveIdentity {
+            id: EnclaveIdType::QE,
+            version: 4
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_resumes_from_checkpoint() {
+    let chain = LocalChain::mock_with_logs(HashMap::new(), history()).await;
+    let path = std::env::temp_dir().join(format!("pccs-events-{}.json", std::process::id()));
+    let indexer = EventIndexer::new(reader(chain.url()))
+        .with_chunk_size(2)
+        .with_checkpoint(&path);
+
+    // the first run stops halfway
+    let mut index = indexer.load_index(1).unwrap();
+    assert_eq!(indexer.index_until(&mut index, 5).await.unwrap(), 3);
+
+    // the next one picks the saved index up, without indexing anything twice
+    let mut resumed = indexer.load_index(1).unwrap();
+    assert_eq!(resumed, index);
+    assert_eq!(resumed.next_block(), 6);
+    assert_eq!(indexer.index_to_latest(&mut resumed).await.unwrap(), 2);
+    assert_eq!(resumed.events.len(), 5);
+    assert_eq!(EventIndex::load(&path).unwrap(), resumed);
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Bytes;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use async_trait::async_trait;
+
+use common::{reader, LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO, PRIVATE_KEY};
+use pccs_reader_rs::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj};
+use pccs_reader_rs::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use pccs_reader_rs::pccs::pcs::IPCSDao;
+use pccs_reader_rs::source::CollateralSource;
+use pccs_reader_rs::writer::{signer_from_private_key, UpsertKind};
+use pccs_reader_rs::{MissingCollateral, CA};
+
+struct StaticSource;
+
+#[async_trait]
+impl CollateralSource for StaticSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let crl = match ca {
+            CA::SIGNING => vec![],
+            _ => vec![0x30, 0x81, ca as u8],
+        };
+        Ok((vec![0x30, 0x82, ca as u8], crl))
+    }
+
+    async fn tcb_info(&self, _tcb_type: u8, fmspc: &str, _version: u32) -> Result<TcbInfoJsonObj> {
+        Ok(TcbInfoJsonObj {
+            tcbInfoStr: format!(r#"{{"id":"SGX","version":3,"fmspc":"{}"}}"#, fmspc),
+            signature: vec![0xaa; 64].into(),
+        })
+    }
+
+    async fn enclave_identity(
+        &self,
+        _id: EnclaveIdType,
+        _version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        Ok(EnclaveIdentityJsonObj {
+            identityStr: r#"{"id":"QE","version":2}"#.to_string(),
+            signature: vec![0xbb; 64].into(),
+        })
+    }
+}
+
+// Serves everything but certificates
+struct NoCertificateSource;
+
+#[async_trait]
+impl CollateralSource for NoCertificateSource {
+    async fn certificate(&self, ca: CA) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (_, crl) = StaticSource.certificate(ca).await?;
+        Ok((vec![], crl))
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<TcbInfoJsonObj> {
+        StaticSource.tcb_info(tcb_type, fmspc, version).await
+    }
+
+    async fn enclave_identity(
+        &self,
+        id: EnclaveIdType,
+        version: u32,
+    ) -> Result<EnclaveIdentityJsonObj> {
+        StaticSource.enclave_identity(id, version).await
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn test_fill_missing() {
+    // the upserts only need to succeed, whatever they return
+    let ok = vec![0u8; 32];
+    let chain = LocalChain::anvil(HashMap::from([
+        (PCS_DAO, ok.clone()),
+        (FMSPC_TCB_DAO, ok.clone()),
+        (ENCLAVE_ID_DAO, ok),
+    ]))
+    .await;
+
+    let reader = reader(chain.url());
+
+    let missing = vec![
+        MissingCollateral::FMSPCTCB(0, "00606a000000".to_string(), 3),
+        MissingCollateral::PCS(CA::PLATFORM, true, true),
+        MissingCollateral::PCS(CA::ROOT, false, true),
+        MissingCollateral::QEIdentity(EnclaveIdType::QE, 4),
+        MissingCollateral::PCS(CA::SIGNING, true, false),
+    ];
+
+    let signer: PrivateKeySigner = signer_from_private_key(PRIVATE_KEY).unwrap();
+    let submitted = reader
+        .fill_missing(&missing, &StaticSource, signer)
+        .await
+        .unwrap();
+
+    let kinds: Vec<UpsertKind> = submitted.iter().map(|(call, _)| call.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            UpsertKind::PcsCrl(CA::ROOT),
+            UpsertKind::PcsCertificate(CA::SIGNING),
+            UpsertKind::PcsCertificate(CA::PLATFORM),
+            UpsertKind::PcsCrl(CA::PLATFORM),
+            UpsertKind::EnclaveIdentity(EnclaveIdType::QE, 4),
+            UpsertKind::FmspcTcb(0, "00606a000000".to_string(), 3),
+        ]
+    );
+
+    // the calldata decodes back to what the source served
+    let (root_crl, _) = &submitted[0];
+    let call = IPCSDao::upsertRootCACrlCall::abi_decode(&root_crl.input, true).unwrap();
+    assert_eq!(call.rootcacrl, Bytes::from(vec![0x30, 0x81, CA::ROOT as u8]));
+    let (tcb_info, _) = &submitted[5];
+    assert_eq!(tcb_info.to, FMSPC_TCB_DAO);
+    let call = IFmspcTcbDao::upsertFmspcTcbCall::abi_decode(&tcb_info.input, true).unwrap();
+    assert!(call.tcbInfoObj.tcbInfoStr.contains("00606a000000"));
+
+    let provider = ProviderBuilder::new().on_http(chain.url().parse().unwrap());
+    for (_, tx_hash) in &submitted {
+        let receipt = provider.get_transaction_receipt(*tx_hash).await.unwrap().unwrap();
+        assert!(receipt.status());
+    }
+}
+
+#[tokio::test]
+async fn test_upsert_calls_dedup() {
+    let chain = LocalChain::start(HashMap::new()).await;
+    let reader = reader(chain.url());
+
+    // what two quotes of different PCK CAs miss, one after the other
+    let missing = vec![
+        MissingCollateral::PCS(CA::PLATFORM, true, false),
+        MissingCollateral::PCS(CA::PROCESSOR, true, false),
+        MissingCollateral::PCS(CA::PLATFORM, true, false),
+    ];
+    let calls = reader.upsert_calls(&missing, &StaticSource).await.unwrap();
+    let kinds: Vec<UpsertKind> = calls.into_iter().map(|call| call.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            UpsertKind::PcsCertificate(CA::PLATFORM),
+            UpsertKind::PcsCertificate(CA::PROCESSOR),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_upsert_calls_reject_empty_certificates() {
+    let chain = LocalChain::start(HashMap::new()).await;
+    let reader = reader(chain.url());
+
+    let missing = [MissingCollateral::PCS(CA::PLATFORM, true, true)];
+    assert!(reader.upsert_calls(&missing, &NoCertificateSource).await.is_err());
+    // only the CRL is missing, the certificate is not needed
+    let missing = [MissingCollateral::PCS(CA::PLATFORM, false, true)];
+    let calls = reader.upsert_calls(&missing, &NoCertificateSource).await.unwrap();
+    assert_eq!(calls[0].kind, UpsertKind::PcsCrl(CA::PLATFORM));
+}
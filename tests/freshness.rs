@@ -0,0 +1,141 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Bytes;
+use alloy::sol_types::SolCall;
+
+use common::{
+    data, no_tcb_info_returns, reader, LocalChain, CERT, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+};
+use pccs_reader_rs::freshness::{Freshness, VersionedCollateral};
+use pccs_reader_rs::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj, IEnclaveIdentityDao};
+use pccs_reader_rs::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use pccs_reader_rs::pccs::pcs::IPCSDao;
+use pccs_reader_rs::source::DirectoryCollateralSource;
+use pccs_reader_rs::CA;
+
+fn tcb_info(evaluation_number: u32) -> TcbInfoJsonObj {
+    TcbInfoJsonObj {
+        tcbInfoStr: format!(
+            r#"{{"id":"SGX","version":2,"issueDate":"2024-06-01T00:00:00Z","fmspc":"00606a000000","tcbEvaluationDataNumber":{}}}"#,
+            evaluation_number
+        ),
+        signature: vec![0xaa; 64].into(),
+    }
+}
+
+fn qe_identity() -> EnclaveIdentityJsonObj {
+    EnclaveIdentityJsonObj {
+        identityStr: r#"{"id":"QE","version":2,"issueDate":"2024-06-01T00:00:00Z","tcbEvaluationDataNumber":17}"#.to_string(),
+        signature: vec![0xbb; 64].into(),
+    }
+}
+
+#[tokio::test]
+async fn test_compare_with_source() {
+    // on chain: CRL number 1 everywhere, TCB evaluation 16
+    let chain = LocalChain::start(HashMap::from([
+        (
+            PCS_DAO,
+            IPCSDao::getCertificateByIdCall::abi_encode_returns(&(
+                Bytes::from(CERT.to_vec()),
+                Bytes::from(data("crl_number_1.der")),
+            )),
+        ),
+        (
+            FMSPC_TCB_DAO,
+            IFmspcTcbDao::getTcbInfoCall::abi_encode_returns(&(tcb_info(16),)),
+        ),
+        (
+            ENCLAVE_ID_DAO,
+            IEnclaveIdentityDao::getEnclaveIdentityCall::abi_encode_returns(&(qe_identity(),)),
+        ),
+    ]))
+    .await;
+
+    let reader = reader(chain.url());
+
+    // source: a newer Root CA CRL and TCB evaluation 17
+    let dir = std::env::temp_dir().join(format!("pccs-reader-freshness-{}", std::process::id()));
+    let source = DirectoryCollateralSource::new(&dir);
+    source
+        .write_certificate(CA::ROOT, &[0x30], &data("crl_number_2.der"))
+        .unwrap();
+    source
+        .write_certificate(CA::PLATFORM, &[0x30], &data("crl_number_1.der"))
+        .unwrap();
+    source
+        .write_tcb_info(0, "00606a000000", 2, &tcb_info(17))
+        .unwrap();
+    source
+        .write_enclave_identity(EnclaveIdType::QE, 3, &qe_identity())
+        .unwrap();
+
+    let quote_hex = String::from_utf8(data("v3_sgx_quote.hex")).unwrap();
+    let raw_quote = hex::decode(quote_hex.trim()).unwrap();
+    let reports = reader
+        .compare_quote_with_source(&raw_quote, &source)
+        .await
+        .unwrap();
+
+    let freshness: Vec<(VersionedCollateral, Freshness)> = reports
+        .iter()
+        .map(|report| (report.collateral.clone(), report.freshness))
+        .collect();
+    assert_eq!(
+        freshness,
+        vec![
+            (VersionedCollateral::Crl(CA::ROOT), Freshness::Behind),
+            (VersionedCollateral::Crl(CA::PLATFORM), Freshness::UpToDate),
+            (
+                VersionedCollateral::TcbInfo(0, "00606a000000".to_string(), 2),
+                Freshness::Behind
+            ),
+            (
+                VersionedCollateral::EnclaveIdentity(EnclaveIdType::QE, 3),
+                Freshness::UpToDate
+            ),
+        ]
+    );
+    assert_eq!(reports[0].onchain.as_ref().unwrap().number, Some(1));
+    assert_eq!(reports[0].source.number, Some(2));
+
+    // a source without the collateral cannot be compared against
+    let empty = DirectoryCollateralSource::new(dir.join("empty"));
+    assert!(reader.compare_quote_with_source(&raw_quote, &empty).await.is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_read_error_is_not_missing() {
+    // on chain: no TCB Info, and an identity DAO whose answer does not decode
+    let chain = LocalChain::start(HashMap::from([
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+        (ENCLAVE_ID_DAO, vec![0x01]),
+    ]))
+    .await;
+    let reader = reader(chain.url());
+
+    let dir = std::env::temp_dir().join(format!("pccs-reader-read-error-{}", std::process::id()));
+    let source = DirectoryCollateralSource::new(&dir);
+    source
+        .write_tcb_info(0, "00606a000000", 2, &tcb_info(17))
+        .unwrap();
+    source
+        .write_enclave_identity(EnclaveIdType::QE, 3, &qe_identity())
+        .unwrap();
+
+    let tcb_info = VersionedCollateral::TcbInfo(0, "00606a000000".to_string(), 2);
+    let reports = reader
+        .compare_with_source(std::slice::from_ref(&tcb_info), &source)
+        .await
+        .unwrap();
+    assert_eq!(reports[0].freshness, Freshness::MissingOnChain);
+
+    let identity = VersionedCollateral::EnclaveIdentity(EnclaveIdType::QE, 3);
+    assert!(reader.compare_with_source(&[identity], &source).await.is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::sol_types::SolCall;
+use x509_parser::pem::Pem;
+
+use common::LocalChain;
+use pccs_reader_rs::network::HelperAddresses;
+use pccs_reader_rs::pccs::helpers::{IX509CRLHelper, IX509Helper, ParsedCertificate, ParsedCrl};
+use pccs_reader_rs::{NetworkProfile, PccsReader};
+
+const X509_HELPER: Address = Address::repeat_byte(0x66);
+const X509_CRL_HELPER: Address = Address::repeat_byte(0x77);
+
+fn reader(url: &str) -> PccsReader {
+    let mut network = NetworkProfile::default().with_rpc_url(url);
+    network.helpers = Some(HelperAddresses {
+        enclave_identity: Address::repeat_byte(0x88),
+        fmspc_tcb: Address::repeat_byte(0x99),
+        x509: X509_HELPER,
+        x509_crl: X509_CRL_HELPER,
+    });
+    PccsReader::new(network).unwrap()
+}
+
+// The Root CA certificate at the end of the PCK chain of the quote
+fn root_ca() -> Vec<u8> {
+    let path = format!("{}/tests/data/v3_sgx_quote.hex", env!("CARGO_MANIFEST_DIR"));
+    let raw_quote = hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap();
+    let start = raw_quote.windows(10).position(|w| w == b"-----BEGIN").unwrap();
+    Pem::iter_from_buffer(&raw_quote[start..])
+        .map_while(|pem| pem.ok())
+        .last()
+        .unwrap()
+        .contents
+}
+
+#[tokio::test]
+async fn test_flags_parse_discrepancies() {
+    let der = root_ca();
+    let parsed = ParsedCertificate::from_der(&der).unwrap();
+    // the helper reads another issuer
+    let cert = IX509Helper::X509CertObj {
+        serialNumber: parsed.serial_number,
+        issuerCommonName: "Intel SGX Root".to_string(),
+        validityNotBefore: U256::from(parsed.not_before),
+        validityNotAfter: U256::from(parsed.not_after),
+        subjectCommonName: parsed.subject_common_name.clone(),
+        subjectPublicKey: parsed.subject_public_key.clone().into(),
+        extensionPtr: U256::ZERO,
+        authorityKeyIdentifier: parsed.authority_key_identifier.clone().into(),
+        subjectKeyIdentifier: parsed.subject_key_identifier.clone().into(),
+        signature: Bytes::new(),
+        tbs: Bytes::new(),
+    };
+
+    let crl_der = std::fs::read(format!("{}/tests/data/crl_number_2.der", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let parsed_crl = ParsedCrl::from_der(&crl_der).unwrap();
+    let crl = IX509CRLHelper::X509CRLObj {
+        serialNumber: parsed_crl.serial_number,
+        issuerCommonName: parsed_crl.issuer_common_name.clone(),
+        validityNotBefore: U256::from(parsed_crl.this_update),
+        validityNotAfter: U256::from(parsed_crl.next_update),
+        serialNumbersRevoked: parsed_crl.revoked_serial_numbers.clone(),
+        authorityKeyIdentifier: Bytes::new(),
+        signature: Bytes::new(),
+        tbs: Bytes::new(),
+    };
+
+    let chain = LocalChain::start(HashMap::from([
+        (X509_HELPER, IX509Helper::parseX509DERCall::abi_encode_returns(&(cert,))),
+        (X509_CRL_HELPER, IX509CRLHelper::parseCRLDERCall::abi_encode_returns(&(crl,))),
+    ]))
+    .await;
+    let reader = reader(chain.url());
+
+    let report = reader.check_certificate_parse(&der).await.unwrap();
+    assert!(!report.is_consistent());
+    assert_eq!(report.discrepancies.len(), 1);
+    assert_eq!(report.discrepancies[0].field, "issuer_common_name");
+    assert_eq!(report.discrepancies[0].onchain, "Intel SGX Root");
+    assert_eq!(report.discrepancies[0].offchain, "Intel SGX Root CA");
+
+    let report = reader.check_crl_parse(&crl_der).await.unwrap();
+    assert!(report.is_consistent());
+}
+
+#[tokio::test]
+async fn test_needs_helper_addresses() {
+    let reader = PccsReader::new(NetworkProfile::holesky()).unwrap();
+    let error = reader.parse_crl_onchain(&[0x30]).await.unwrap_err();
+    assert_eq!(error.to_string(), "No helper contracts known for holesky");
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy::sol_types::{SolCall, SolValue};
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use common::{
+    certificate_returns, identity_returns, network, no_tcb_info_returns, quote, DCAP_ATTESTATION,
+    ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+};
+use pccs_reader_rs::attestation::IDcapAttestation;
+use pccs_reader_rs::hypothetical::HypotheticalState;
+use pccs_reader_rs::pccs::fmspc_tcb::{IFmspcTcbDao, TcbInfoJsonObj};
+use pccs_reader_rs::proof::{bytes_storage, DaoStorageLayout, StorageLayout};
+use pccs_reader_rs::writer::UpsertCall;
+use pccs_reader_rs::{MissingCollateral, PccsReader};
+
+const DAO_STORAGE: Address = Address::repeat_byte(0x44);
+const FMSPC: [u8; 6] = [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00];
+
+fn layout() -> DaoStorageLayout {
+    DaoStorageLayout::new(DAO_STORAGE, U256::from(3))
+}
+
+fn tcb_info() -> TcbInfoJsonObj {
+    TcbInfoJsonObj {
+        tcbInfoStr: r#"{"id":"SGX","version":2,"fmspc":"00606a000000"}"#.to_string(),
+        signature: vec![0xaa; 64].into(),
+    }
+}
+
+// A node whose FMSPC TCB DAO reads the TCB Info from the storage overrides of each
+// `eth_call`, and whose DCAP attestation only verifies the quote when it finds it there
+struct OverrideNode {
+    verifies: bool,
+    // every `eth_call` reverts instead
+    reverts: bool,
+    // the state overrides of every `eth_call`, in order
+    overrides: Mutex<Vec<Value>>,
+}
+
+async fn start_node(verifies: bool, reverts: bool) -> (String, Arc<OverrideNode>) {
+    let node = Arc::new(OverrideNode {
+        verifies,
+        reverts,
+        overrides: Mutex::new(Vec::new()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = Router::new().route("/", post(rpc)).with_state(node.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (url, node)
+}
+
+async fn rpc(State(node): State<Arc<OverrideNode>>, Json(request): Json<Value>) -> Json<Value> {
+    let params = &request["params"];
+    let result = match request["method"].as_str() {
+        Some("eth_chainId") => json!("0x7a69"),
+        Some("eth_blockNumber") => json!("0x1"),
+        Some("eth_call") if node.reverts => {
+            return Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": 3, "message": "execution reverted", "data": "0x" },
+            }))
+        }
+        Some("eth_call") => {
+            node.overrides.lock().unwrap().push(params[2].clone());
+            let to: Option<Address> = serde_json::from_value(params[0]["to"].clone()).ok();
+            let stored = stored_tcb_info(&params[2]);
+            let data = match to {
+                Some(PCS_DAO) => certificate_returns(),
+                Some(ENCLAVE_ID_DAO) => identity_returns(),
+                Some(FMSPC_TCB_DAO) => match &stored {
+                    Some(tcb_info) => {
+                        IFmspcTcbDao::getTcbInfoCall::abi_encode_returns(&(tcb_info.clone(),))
+                    }
+                    None => no_tcb_info_returns(),
+                },
+                Some(DCAP_ATTESTATION) => verification(stored.is_some() && node.verifies),
+                _ => vec![],
+            };
+            json!(Bytes::from(data))
+        }
+        _ => Value::Null,
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+// The TCB Info written where the layout keeps it, read back as a Solidity `bytes`
+fn stored_tcb_info(overrides: &Value) -> Option<TcbInfoJsonObj> {
+    let state_diff: HashMap<B256, B256> =
+        serde_json::from_value(overrides[DAO_STORAGE.to_string()]["stateDiff"].clone()).ok()?;
+    let slot = layout().tcb_info(0, FMSPC, 2).slot;
+    let head = U256::from_be_bytes(state_diff.get(&slot)?.0);
+    let len = (head >> 1usize).to::<usize>();
+    let start = U256::from_be_bytes(keccak256(slot).0);
+    let mut value: Vec<u8> = (0..len.div_ceil(32))
+        .flat_map(|i| state_diff[&B256::from(start + U256::from(i))].0)
+        .collect();
+    value.truncate(len);
+    TcbInfoJsonObj::abi_decode(&value, true).ok()
+}
+
+fn verification(verified: bool) -> Vec<u8> {
+    let raw_quote = quote("v3_sgx_quote.hex");
+    let output = match verified {
+        true => {
+            let mut output = vec![0x00, 0x03];
+            output.extend_from_slice(&raw_quote[4..8]);
+            output.push(0);
+            output.extend_from_slice(&FMSPC);
+            output.extend_from_slice(&raw_quote[48..48 + 384]);
+            output
+        }
+        false => b"TCBInfo not found".to_vec(),
+    };
+    IDcapAttestation::verifyAndAttestOnChainCall::abi_encode_returns(&(verified, Bytes::from(output)))
+}
+
+#[tokio::test]
+async fn test_simulates_over_state_overrides() {
+    let raw_quote = quote("v3_sgx_quote.hex");
+    let (url, node) = start_node(true, false).await;
+    let network = network(&url);
+    let reader = PccsReader::new(network.clone()).unwrap();
+
+    let calls = [UpsertCall::fmspc_tcb(&network, 0, "00606a000000", 2, tcb_info())];
+    let hypothetical = reader
+        .simulate_with_collateral(&raw_quote, &calls, &layout())
+        .await
+        .unwrap();
+
+    assert_eq!(hypothetical.state, HypotheticalState::StateOverrides);
+    assert_eq!(hypothetical.applied, ["TCB info for fmspc 00606a000000 (tcb type 0, version 2)"]);
+    assert!(hypothetical.verification.verified);
+    // the reader itself is left reading the chain
+    assert!(reader.state_overrides().is_none());
+
+    // every call carried the TCB Info, word for word where the layout keeps it
+    let slot = layout().tcb_info(0, FMSPC, 2).slot;
+    let expected: HashMap<B256, B256> = bytes_storage(slot, &tcb_info().abi_encode())
+        .into_iter()
+        .collect();
+    let overrides = node.overrides.lock().unwrap();
+    assert!(!overrides.is_empty());
+    for sent in overrides.iter() {
+        let state_diff: HashMap<B256, B256> =
+            serde_json::from_value(sent[DAO_STORAGE.to_string()]["stateDiff"].clone()).unwrap();
+        assert_eq!(state_diff, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_checks_missing_collateral_over_state_overrides() {
+    let raw_quote = quote("v3_sgx_quote.hex");
+    // the quote fails for another reason, so the missing collateral is looked up
+    let (url, _node) = start_node(false, false).await;
+    let network = network(&url);
+    let reader = PccsReader::new(network.clone()).unwrap();
+
+    let calls = [UpsertCall::fmspc_tcb(&network, 0, "00606a000000", 2, tcb_info())];
+    let hypothetical = reader
+        .simulate_with_collateral(&raw_quote, &calls, &layout())
+        .await
+        .unwrap();
+    assert!(!hypothetical.verification.verified);
+    assert_eq!(hypothetical.verification.missing, MissingCollateral::None);
+
+    // without the upsert, the same check reports the TCB Info
+    let hypothetical = reader
+        .simulate_with_collateral(&raw_quote, &[], &layout())
+        .await
+        .unwrap();
+    assert_eq!(
+        hypothetical.verification.missing,
+        MissingCollateral::FMSPCTCB(0, "00606a000000".to_string(), 2)
+    );
+}
+
+#[tokio::test]
+async fn test_does_not_fork_on_a_revert() {
+    let raw_quote = quote("v3_sgx_quote.hex");
+    let (url, _node) = start_node(true, true).await;
+    let network = network(&url);
+    let reader = PccsReader::new(network.clone()).unwrap();
+
+    // the node took the overrides and reverted, anvil would not answer differently
+    let calls = [UpsertCall::fmspc_tcb(&network, 0, "00606a000000", 2, tcb_info())];
+    let error = reader
+        .simulate_with_collateral(&raw_quote, &calls, &layout())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("execution reverted"), "{}", error);
+}
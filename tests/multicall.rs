@@ -0,0 +1,97 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use alloy::sol_types::SolCall;
+
+use common::{
+    certificate_returns, identity_returns, network, quote, tcb_info_returns, LocalChain, CRL,
+    ENCLAVE_ID_DAO, PCS_DAO,
+};
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::pccs::multicall::{Call3Result, IMulticall3};
+use pccs_reader_rs::{MissingCollateral, PccsReader};
+
+const MULTICALL: Address = Address::repeat_byte(0x44);
+
+fn reader(url: &str) -> PccsReader {
+    let mut network = network(url);
+    network.multicall = MULTICALL;
+    PccsReader::new(network).unwrap()
+}
+
+// The aggregate3 answer for root, QE identity, TCB Info, signing and PCK CA, in that order
+fn aggregate3(results: [Option<Vec<u8>>; 5]) -> Vec<u8> {
+    let results: Vec<Call3Result> = results
+        .into_iter()
+        .map(|result| Call3Result {
+            success: result.is_some(),
+            returnData: result.unwrap_or_default().into(),
+        })
+        .collect();
+    IMulticall3::aggregate3Call::abi_encode_returns(&(results,))
+}
+
+#[tokio::test]
+async fn test_check_reads_through_multicall() {
+    // only Multicall3 answers, so every read has to go through it
+    let chain = LocalChain::start(HashMap::from([(
+        MULTICALL,
+        aggregate3([
+            Some(certificate_returns()),
+            Some(identity_returns()),
+            None,
+            Some(certificate_returns()),
+            Some(certificate_returns()),
+        ]),
+    )]))
+    .await;
+    let v3 = quote("v3_sgx_quote.hex");
+
+    let missing = reader(chain.url()).find_missing_collaterals_from_quote(&v3).await;
+    assert_eq!(missing, MissingCollateral::FMSPCTCB(0, "00606a000000".to_string(), 2));
+    assert!(reader(chain.url()).get_quote_collateral(&v3).await.is_err());
+}
+
+#[tokio::test]
+async fn test_collateral_read_through_multicall() {
+    let chain = LocalChain::start(HashMap::from([(
+        MULTICALL,
+        aggregate3([
+            Some(certificate_returns()),
+            Some(identity_returns()),
+            Some(tcb_info_returns(2, "00606a000000")),
+            Some(certificate_returns()),
+            Some(certificate_returns()),
+        ]),
+    )]))
+    .await;
+    let v3 = quote("v3_sgx_quote.hex");
+
+    let reader = reader(chain.url());
+    assert_eq!(reader.find_missing_collaterals_from_quote(&v3).await, MissingCollateral::None);
+
+    let collateral = reader.get_quote_collateral(&v3).await.unwrap();
+    assert_eq!(collateral.pck_crl, CRL);
+    assert!(collateral.tcb_info.tcbInfoStr.contains("00606a000000"));
+}
+
+#[tokio::test]
+async fn test_falls_back_without_multicall() {
+    // nothing deployed at the Multicall3 address, the DAOs answer directly
+    let chain = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (ENCLAVE_ID_DAO, identity_returns()),
+    ]))
+    .await;
+    let v3 = quote("v3_sgx_quote.hex");
+
+    let reader = reader(chain.url());
+    assert!(reader.aggregate3(vec![]).await.is_err());
+    assert_eq!(
+        reader.find_missing_collaterals_from_quote(&v3).await,
+        MissingCollateral::FMSPCTCB(0, "00606a000000".to_string(), 2)
+    );
+    assert!(reader.get_enclave_identity(EnclaveIdType::QE, 3).await.is_ok());
+}
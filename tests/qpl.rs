@@ -0,0 +1,72 @@
+mod common;
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use common::{dao_contracts, LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO};
+use pccs_reader_rs::qpl::QPL_CONF_ENV;
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs anvil"]
+async fn test_qpl_from_c() {
+    let chain = LocalChain::anvil(dao_contracts(3, "00906ed50000")).await;
+
+    // the cdylib is built next to the test binary
+    let lib_dir = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let work_dir = std::env::temp_dir().join(format!("pccs-reader-qpl-{}", std::process::id()));
+    std::fs::create_dir_all(&work_dir).unwrap();
+
+    let config = work_dir.join("pccs_reader_qpl.conf");
+    std::fs::write(
+        &config,
+        format!(
+            r#"{{
+                // local chain
+                "rpc_url": "{}",
+                "pcs_dao": "{}",
+                "fmspc_tcb_dao": "{}",
+                "enclave_id_dao": "{}"
+            }}"#,
+            chain.url(),
+            PCS_DAO,
+            FMSPC_TCB_DAO,
+            ENCLAVE_ID_DAO
+        ),
+    )
+    .unwrap();
+
+    let harness = work_dir.join("harness");
+    let source = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/qpl/harness.c");
+    let status = Command::new("cc")
+        .arg(&source)
+        .arg("-o")
+        .arg(&harness)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lpccs_reader_rs")
+        .status()
+        .expect("a C compiler is required");
+    assert!(status.success());
+
+    // the QPL blocks on its own runtime, so keep it off the test runtime
+    let output = tokio::task::spawn_blocking(move || {
+        Command::new(&harness)
+            .env(QPL_CONF_ENV, &config)
+            .output()
+            .unwrap()
+    })
+    .await
+    .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains(r#"sgx tcb_info: {"tcbInfo": {"id":"SGX","version":3,"fmspc":"00906ed50000"}, "signature": ""#));
+    assert!(stdout.contains(r#"tdx qe_identity: {"enclaveIdentity": {"id":"QE","version":2}"#));
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+}
@@ -0,0 +1,115 @@
+// Calls the QPL entry points the way the DCAP Quote Verification Library does.
+
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+typedef uint32_t quote3_error_t;
+
+#define SGX_QL_SUCCESS 0x0000
+#define SGX_QL_ERROR_INVALID_PARAMETER 0xE002
+
+typedef struct _sgx_ql_qve_collateral_t {
+    union {
+        uint32_t version;
+        struct {
+            uint16_t major_version;
+            uint16_t minor_version;
+        };
+    };
+    uint32_t tee_type;
+    char *pck_crl_issuer_chain;
+    uint32_t pck_crl_issuer_chain_size;
+    char *root_ca_crl;
+    uint32_t root_ca_crl_size;
+    char *pck_crl;
+    uint32_t pck_crl_size;
+    char *tcb_info_issuer_chain;
+    uint32_t tcb_info_issuer_chain_size;
+    char *tcb_info;
+    uint32_t tcb_info_size;
+    char *qe_identity_issuer_chain;
+    uint32_t qe_identity_issuer_chain_size;
+    char *qe_identity;
+    uint32_t qe_identity_size;
+} sgx_ql_qve_collateral_t;
+
+typedef sgx_ql_qve_collateral_t tdx_ql_qve_collateral_t;
+
+quote3_error_t sgx_ql_get_quote_verification_collateral(const uint8_t *fmspc, uint16_t fmspc_size,
+                                                        const char *pck_ca,
+                                                        sgx_ql_qve_collateral_t **pp_quote_collateral);
+quote3_error_t sgx_ql_free_quote_verification_collateral(const sgx_ql_qve_collateral_t *p_quote_collateral);
+quote3_error_t tdx_ql_get_quote_verification_collateral(const uint8_t *fmspc, uint16_t fmspc_size,
+                                                        const char *pck_ca,
+                                                        tdx_ql_qve_collateral_t **pp_quote_collateral);
+quote3_error_t tdx_ql_free_quote_verification_collateral(const tdx_ql_qve_collateral_t *p_quote_collateral);
+
+static int check_string(const char *name, const char *s, uint32_t size, const char *prefix) {
+    if (s == NULL || size != strlen(s) + 1 || strncmp(s, prefix, strlen(prefix)) != 0) {
+        fprintf(stderr, "invalid %s\n", name);
+        return 1;
+    }
+    return 0;
+}
+
+static int check_collateral(const sgx_ql_qve_collateral_t *c, uint16_t major_version, uint32_t tee_type) {
+    int failed = 0;
+    if (c->major_version != major_version || c->tee_type != tee_type) {
+        fprintf(stderr, "invalid version %u.%u or tee type %u\n", c->major_version, c->minor_version, c->tee_type);
+        failed = 1;
+    }
+    failed |= check_string("pck_crl_issuer_chain", c->pck_crl_issuer_chain, c->pck_crl_issuer_chain_size,
+                           "-----BEGIN CERTIFICATE-----");
+    failed |= check_string("root_ca_crl", c->root_ca_crl, c->root_ca_crl_size, "-----BEGIN X509 CRL-----");
+    failed |= check_string("pck_crl", c->pck_crl, c->pck_crl_size, "-----BEGIN X509 CRL-----");
+    failed |= check_string("tcb_info_issuer_chain", c->tcb_info_issuer_chain, c->tcb_info_issuer_chain_size,
+                           "-----BEGIN CERTIFICATE-----");
+    failed |= check_string("tcb_info", c->tcb_info, c->tcb_info_size, "{\"tcbInfo\": ");
+    failed |= check_string("qe_identity_issuer_chain", c->qe_identity_issuer_chain,
+                           c->qe_identity_issuer_chain_size, "-----BEGIN CERTIFICATE-----");
+    failed |= check_string("qe_identity", c->qe_identity, c->qe_identity_size, "{\"enclaveIdentity\": ");
+    return failed;
+}
+
+int main(void) {
+    const uint8_t fmspc[6] = {0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00};
+    sgx_ql_qve_collateral_t *sgx = NULL;
+    tdx_ql_qve_collateral_t *tdx = NULL;
+    quote3_error_t ret;
+
+    if (sgx_ql_get_quote_verification_collateral(fmspc, 5, "platform", &sgx) != SGX_QL_ERROR_INVALID_PARAMETER ||
+        sgx_ql_get_quote_verification_collateral(fmspc, 6, "root", &sgx) != SGX_QL_ERROR_INVALID_PARAMETER) {
+        fprintf(stderr, "invalid parameters accepted\n");
+        return 1;
+    }
+
+    ret = sgx_ql_get_quote_verification_collateral(fmspc, sizeof(fmspc), "platform", &sgx);
+    if (ret != SGX_QL_SUCCESS) {
+        fprintf(stderr, "sgx_ql_get_quote_verification_collateral: 0x%x\n", ret);
+        return 1;
+    }
+    if (check_collateral(sgx, 3, 0x00000000)) {
+        return 1;
+    }
+    printf("sgx tcb_info: %s\n", sgx->tcb_info);
+    if (sgx_ql_free_quote_verification_collateral(sgx) != SGX_QL_SUCCESS) {
+        return 1;
+    }
+
+    ret = tdx_ql_get_quote_verification_collateral(fmspc, sizeof(fmspc), "processor", &tdx);
+    if (ret != SGX_QL_SUCCESS) {
+        fprintf(stderr, "tdx_ql_get_quote_verification_collateral: 0x%x\n", ret);
+        return 1;
+    }
+    if (check_collateral(tdx, 4, 0x00000081)) {
+        return 1;
+    }
+    printf("tdx qe_identity: %s\n", tdx->qe_identity);
+    if (tdx_ql_free_quote_verification_collateral(tdx) != SGX_QL_SUCCESS) {
+        return 1;
+    }
+
+    printf("OK\n");
+    return 0;
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Bytes;
+use alloy::sol_types::SolCall;
+
+use common::{
+    data, no_tcb_info_returns, LocalChain, CERT, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+    PRIVATE_KEY,
+};
+use pccs_reader_rs::config::NetworkConfig;
+use pccs_reader_rs::pccs::enclave_id::{EnclaveIdType, EnclaveIdentityJsonObj, IEnclaveIdentityDao};
+use pccs_reader_rs::pccs::fmspc_tcb::TcbInfoJsonObj;
+use pccs_reader_rs::pccs::pcs::IPCSDao;
+use pccs_reader_rs::relayer::{Relayer, RelayerConfig, RelayerState, SubmissionStatus, WatchedFmspc};
+use pccs_reader_rs::source::DirectoryCollateralSource;
+use pccs_reader_rs::writer::{signer_from_private_key, UpsertKind};
+use pccs_reader_rs::CA;
+
+fn qe_identity() -> EnclaveIdentityJsonObj {
+    EnclaveIdentityJsonObj {
+        identityStr: r#"{"id":"QE","version":2,"issueDate":"2024-06-01T00:00:00Z","tcbEvaluationDataNumber":17}"#.to_string(),
+        signature: vec![0xbb; 64].into(),
+    }
+}
+
+#[tokio::test]
+async fn test_relayer_rounds() {
+    // on chain: every certificate, CRL number 1 and the identities, no TCB Info
+    let chain = LocalChain::start(HashMap::from([
+        (
+            PCS_DAO,
+            IPCSDao::getCertificateByIdCall::abi_encode_returns(&(
+                Bytes::from(CERT.to_vec()),
+                Bytes::from(data("crl_number_1.der")),
+            )),
+        ),
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+        (
+            ENCLAVE_ID_DAO,
+            IEnclaveIdentityDao::getEnclaveIdentityCall::abi_encode_returns(&(qe_identity(),)),
+        ),
+    ]))
+    .await;
+
+    // source: a newer Root CA CRL and the watched TCB Info
+    let dir = std::env::temp_dir().join(format!("pccs-relayer-{}", std::process::id()));
+    let source = DirectoryCollateralSource::new(dir.join("source"));
+    source.write_certificate(CA::ROOT, &[0x30], &data("crl_number_2.der")).unwrap();
+    for ca in [CA::PROCESSOR, CA::PLATFORM] {
+        source.write_certificate(ca, &[0x30], &data("crl_number_1.der")).unwrap();
+    }
+    for (id, version) in [(EnclaveIdType::QE, 3), (EnclaveIdType::QE, 4), (EnclaveIdType::TDQE, 4)] {
+        source.write_enclave_identity(id, version, &qe_identity()).unwrap();
+    }
+    let tcb_info = TcbInfoJsonObj {
+        tcbInfoStr: r#"{"id":"SGX","version":3,"fmspc":"00606a000000","tcbEvaluationDataNumber":17}"#.to_string(),
+        signature: vec![0xaa; 64].into(),
+    };
+    source.write_tcb_info(0, "00606a000000", 3, &tcb_info).unwrap();
+
+    let config = || RelayerConfig {
+        network: NetworkConfig {
+            rpc_url: Some(chain.url().to_string()),
+            pcs_dao: Some(PCS_DAO),
+            fmspc_tcb_dao: Some(FMSPC_TCB_DAO),
+            enclave_id_dao: Some(ENCLAVE_ID_DAO),
+            ..Default::default()
+        },
+        source_dir: Some(source.root().to_path_buf()),
+        watch: vec![WatchedFmspc {
+            fmspc: "00606a000000".to_string(),
+            tcb_type: 0,
+            version: 3,
+        }],
+        state_file: dir.join("state.json"),
+        ..Default::default()
+    };
+    let expected = vec![
+        UpsertKind::PcsCrl(CA::ROOT),
+        UpsertKind::FmspcTcb(0, "00606a000000".to_string(), 3),
+    ];
+
+    // a dry run sends nothing and keeps no state
+    let mut dry_run = Relayer::new(config(), config().source(), None).unwrap();
+    let summary = dry_run.run_once().await.unwrap();
+    let kinds: Vec<UpsertKind> = summary.upserts.iter().map(|call| call.kind.clone()).collect();
+    assert_eq!(kinds, expected);
+    assert!(summary.submitted.is_empty());
+    assert!(!dir.join("state.json").exists());
+
+    let signer = signer_from_private_key(PRIVATE_KEY).unwrap();
+    let mut relayer = Relayer::new(config(), config().source(), Some(signer.clone())).unwrap();
+    let summary = relayer.run_once().await.unwrap();
+    assert_eq!(summary.submitted.len(), 2);
+    assert!(summary.errors.is_empty());
+
+    let state = RelayerState::load(&dir.join("state.json")).unwrap();
+    let nonces: Vec<u64> = state.submissions.values().map(|s| s.nonce).collect();
+    assert_eq!(state.submissions.len(), 2);
+    assert!(nonces.contains(&0) && nonces.contains(&1));
+
+    // a restarted relayer does not send the same upserts again, even though
+    // the mock chain never reflects them
+    let mut restarted = Relayer::new(config(), config().source(), Some(signer)).unwrap();
+    let summary = restarted.run_once().await.unwrap();
+    assert_eq!(summary.upserts.len(), 2);
+    assert!(summary.submitted.is_empty());
+    assert_eq!(summary.skipped, 2);
+    assert!(restarted
+        .state()
+        .submissions
+        .values()
+        .all(|submission| submission.status == SubmissionStatus::Mined));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use alloy::sol_types::SolValue;
+
+use common::{LocalChain, DCAP_ATTESTATION};
+use pccs_reader_rs::{NetworkProfile, PccsReader};
+
+const QUOTE_VERIFIER: Address = Address::repeat_byte(0x66);
+const PCCS_ROUTER: Address = Address::repeat_byte(0x77);
+// the mock router gives the same address whatever is asked
+const DAO: Address = Address::repeat_byte(0x88);
+
+fn chain_contracts() -> HashMap<Address, Vec<u8>> {
+    HashMap::from([
+        (DCAP_ATTESTATION, QUOTE_VERIFIER.abi_encode()),
+        (QUOTE_VERIFIER, PCCS_ROUTER.abi_encode()),
+        (PCCS_ROUTER, DAO.abi_encode()),
+    ])
+}
+
+#[tokio::test]
+async fn test_resolve_from_router() {
+    let chain = LocalChain::start(chain_contracts()).await;
+    let network = NetworkProfile::default().with_rpc_url(chain.url());
+    let reader = PccsReader::from_router(network, PCCS_ROUTER).await.unwrap();
+
+    let resolved = reader.resolved_addresses().unwrap();
+    assert_eq!(resolved.pccs_router, PCCS_ROUTER);
+    assert_eq!(resolved.pcs_dao, DAO);
+    assert_eq!(reader.network().enclave_id_dao, DAO);
+    assert_eq!(reader.network().fmspc_tcb_dao, DAO);
+    assert_eq!(reader.network().pcs_dao, DAO);
+    assert_eq!(reader.network().pck_dao, DAO);
+    assert!(resolved.to_string().contains(&format!("PCS_DAO: {}", DAO)));
+
+    // the clones keep the addresses
+    assert_eq!(reader.clone().resolved_addresses(), Some(resolved));
+}
+
+#[tokio::test]
+async fn test_resolve_from_dcap_attestation() {
+    let chain = LocalChain::start(chain_contracts()).await;
+    let network = NetworkProfile::default().with_rpc_url(chain.url());
+    let reader = PccsReader::from_dcap_attestation(network, DCAP_ATTESTATION)
+        .await
+        .unwrap();
+
+    assert_eq!(reader.network().dcap_attestation, DCAP_ATTESTATION);
+    assert_eq!(reader.network().pccs_router, PCCS_ROUTER);
+    assert_eq!(reader.resolved_addresses().unwrap().fmspc_tcb_dao, DAO);
+}
+
+#[tokio::test]
+async fn test_resolve_unset_router() {
+    let chain = LocalChain::start(HashMap::from([(PCCS_ROUTER, Address::ZERO.abi_encode())])).await;
+    let network = NetworkProfile::default().with_rpc_url(chain.url());
+    let err = PccsReader::from_router(network, PCCS_ROUTER).await.unwrap_err();
+    assert!(err.to_string().contains("has no Enclave ID DAO"));
+}
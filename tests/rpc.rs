@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy::providers::Provider;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use pccs_reader_rs::rpc::RpcPolicy;
+use pccs_reader_rs::{NetworkProfile, PccsReader};
+
+#[derive(Debug, Clone, Copy)]
+enum Behavior {
+    // answers every request with this chain id
+    Answer(u64),
+    // HTTP 429 for the first requests, then answers
+    RateLimited(usize, u64),
+    Slow(Duration, u64),
+    Reverts,
+}
+
+struct Endpoint {
+    url: String,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Endpoint {
+    async fn start(behavior: Behavior) -> Endpoint {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/", post(respond))
+            .with_state((behavior, hits.clone()));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Endpoint { url, hits }
+    }
+
+    fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+}
+
+async fn respond(
+    State((behavior, hits)): State<(Behavior, Arc<AtomicUsize>)>,
+    Json(request): Json<Value>,
+) -> axum::response::Response {
+    let hit = hits.fetch_add(1, Ordering::SeqCst) + 1;
+    let answer = |chain_id: u64| {
+        Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": format!("{:#x}", chain_id) }))
+            .into_response()
+    };
+    match behavior {
+        Behavior::Answer(chain_id) => answer(chain_id),
+        Behavior::RateLimited(times, _) if hit <= times => {
+            (StatusCode::TOO_MANY_REQUESTS, "rate limited").into_response()
+        }
+        Behavior::RateLimited(_, chain_id) => answer(chain_id),
+        Behavior::Slow(delay, chain_id) => {
+            tokio::time::sleep(delay).await;
+            answer(chain_id)
+        }
+        Behavior::Reverts => Json(json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": { "code": 3, "message": "execution reverted" },
+        }))
+        .into_response(),
+    }
+}
+
+fn reader(endpoints: &[&Endpoint], quorum: usize) -> PccsReader {
+    let mut network = NetworkProfile::default().with_rpc_url(&endpoints[0].url);
+    network.fallback_rpc_urls = endpoints[1..].iter().map(|e| e.url.clone()).collect();
+    network.rpc = RpcPolicy {
+        timeout: Duration::from_millis(300),
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        quorum,
+    };
+    PccsReader::new(network).unwrap()
+}
+
+#[tokio::test]
+async fn test_retries_rate_limited_requests() {
+    let endpoint = Endpoint::start(Behavior::RateLimited(2, 7)).await;
+
+    let chain_id = reader(&[&endpoint], 1).provider().get_chain_id().await.unwrap();
+    assert_eq!(chain_id, 7);
+    assert_eq!(endpoint.hits(), 3);
+
+    // more rate limits than retries
+    let endpoint = Endpoint::start(Behavior::RateLimited(10, 7)).await;
+    assert!(reader(&[&endpoint], 1).provider().get_chain_id().await.is_err());
+    assert_eq!(endpoint.hits(), 4);
+}
+
+#[tokio::test]
+async fn test_fails_over_to_fallback_urls() {
+    let slow = Endpoint::start(Behavior::Slow(Duration::from_secs(5), 1)).await;
+    let limited = Endpoint::start(Behavior::RateLimited(usize::MAX, 2)).await;
+    let healthy = Endpoint::start(Behavior::Answer(3)).await;
+
+    let started = Instant::now();
+    let chain_id = reader(&[&slow, &limited, &healthy], 1)
+        .provider()
+        .get_chain_id()
+        .await
+        .unwrap();
+    assert_eq!(chain_id, 3);
+    // the slow endpoint was given up on after the timeout
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!((slow.hits(), limited.hits(), healthy.hits()), (1, 1, 1));
+}
+
+#[tokio::test]
+async fn test_does_not_retry_call_errors() {
+    let reverts = Endpoint::start(Behavior::Reverts).await;
+    let healthy = Endpoint::start(Behavior::Answer(3)).await;
+
+    // a revert is an answer, not a failure of the endpoint
+    assert!(reader(&[&reverts, &healthy], 1).provider().get_chain_id().await.is_err());
+    assert_eq!((reverts.hits(), healthy.hits()), (1, 0));
+}
+
+#[tokio::test]
+async fn test_requires_a_quorum() {
+    let first = Endpoint::start(Behavior::Answer(1)).await;
+    let second = Endpoint::start(Behavior::Answer(1)).await;
+    let lying = Endpoint::start(Behavior::Answer(2)).await;
+
+    let chain_id = reader(&[&lying, &first, &second], 2)
+        .provider()
+        .get_chain_id()
+        .await
+        .unwrap();
+    assert_eq!(chain_id, 1);
+
+    // every endpoint must agree, which never happens
+    assert!(reader(&[&lying, &first, &second], 3).provider().get_chain_id().await.is_err());
+    // the first round plus the retries
+    assert_eq!(lying.hits(), 1 + 4);
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::body::Body;
+use axum::http::{Request, Response, StatusCode};
+use tower::ServiceExt;
+
+use common::{
+    certificate_returns, dao_contracts, no_identity_returns, no_tcb_info_returns, reader,
+    LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO, PCS_DAO,
+};
+use pccs_reader_rs::server::router;
+
+// The signing CA and the Root CA of the mock chain, both `common::CERT`
+const ISSUER_CHAIN: &str = "-----BEGIN CERTIFICATE-----\nMIIBCg==\n-----END CERTIFICATE-----\n\
+                            -----BEGIN CERTIFICATE-----\nMIIBCg==\n-----END CERTIFICATE-----\n";
+
+async fn get(chain: &LocalChain, uri: &str) -> Response<Body> {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    router(reader(chain.url())).oneshot(request).await.unwrap()
+}
+
+async fn body(response: Response<Body>) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+fn issuer_chain(response: &Response<Body>, name: &str) -> Option<String> {
+    let value = response.headers().get(name)?.to_str().unwrap();
+    Some(
+        percent_encoding::percent_decode_str(value)
+            .decode_utf8()
+            .unwrap()
+            .into_owned(),
+    )
+}
+
+#[tokio::test]
+async fn test_serves_tcb_info() {
+    let chain = LocalChain::start(dao_contracts(3, "00906ed50000")).await;
+
+    let response = get(&chain, "/sgx/certification/v4/tcb?fmspc=00906ED50000").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(
+        issuer_chain(&response, "TCB-Info-Issuer-Chain").as_deref(),
+        Some(ISSUER_CHAIN)
+    );
+    assert_eq!(issuer_chain(&response, "SGX-TCB-Info-Issuer-Chain"), None);
+    assert_eq!(
+        body(response).await,
+        format!(
+            r#"{{"tcbInfo": {{"id":"SGX","version":3,"fmspc":"00906ed50000"}}, "signature": "{}"}}"#,
+            "aa".repeat(64)
+        )
+    );
+
+    // the v3 API names the header after SGX
+    let response = get(&chain, "/sgx/certification/v3/tcb?fmspc=00906ed50000").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        issuer_chain(&response, "SGX-TCB-Info-Issuer-Chain").as_deref(),
+        Some(ISSUER_CHAIN)
+    );
+    assert_eq!(issuer_chain(&response, "TCB-Info-Issuer-Chain"), None);
+}
+
+#[tokio::test]
+async fn test_serves_qe_identity() {
+    let chain = LocalChain::start(dao_contracts(3, "00906ed50000")).await;
+
+    for version in ["v3", "v4"] {
+        let response = get(
+            &chain,
+            &format!("/sgx/certification/{}/qe/identity", version),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            issuer_chain(&response, "SGX-Enclave-Identity-Issuer-Chain").as_deref(),
+            Some(ISSUER_CHAIN)
+        );
+        assert_eq!(
+            body(response).await,
+            format!(
+                r#"{{"enclaveIdentity": {{"id":"QE","version":2}}, "signature": "{}"}}"#,
+                "bb".repeat(64)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_not_stored_is_not_found() {
+    let chain = LocalChain::start(HashMap::from([
+        (PCS_DAO, certificate_returns()),
+        (FMSPC_TCB_DAO, no_tcb_info_returns()),
+        (ENCLAVE_ID_DAO, no_identity_returns()),
+    ]))
+    .await;
+
+    for uri in [
+        "/sgx/certification/v4/tcb?fmspc=00906ed50000",
+        "/sgx/certification/v3/tcb?fmspc=00906ed50000",
+        "/sgx/certification/v4/qe/identity",
+        "/tdx/certification/v4/qe/identity",
+    ] {
+        assert_eq!(
+            get(&chain, uri).await.status(),
+            StatusCode::NOT_FOUND,
+            "{}",
+            uri
+        );
+    }
+}
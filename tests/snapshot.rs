@@ -0,0 +1,76 @@
+mod common;
+
+use common::{
+    dao_contracts, full_chain, quote, reader, LocalChain, CRL, ENCLAVE_ID_DAO, MOCK_BLOCK_HASH,
+};
+use pccs_reader_rs::collateral::get_quote_collateral_from_source;
+use pccs_reader_rs::pccs::is_not_stored;
+use pccs_reader_rs::source::{CollateralSource, SnapshotCollateralSource};
+use pccs_reader_rs::{find_missing_collaterals_from_source, MissingCollateral};
+
+#[tokio::test]
+async fn test_checks_quotes_offline() {
+    let chain = full_chain().await;
+    let path = std::env::temp_dir().join(format!("pccs-snapshot-{}.json", std::process::id()));
+
+    let snapshot = reader(chain.url())
+        .export_snapshot(&["00606A000000".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(snapshot.chain_id, 31337);
+    if let LocalChain::Mock { .. } = chain {
+        assert_eq!((snapshot.block_number, snapshot.block_hash), (1, MOCK_BLOCK_HASH));
+    }
+    assert_eq!(snapshot.certificates.len(), 4);
+    assert_eq!(snapshot.enclave_identities.len(), 5);
+    assert_eq!(snapshot.tcb_infos.len(), 3);
+    snapshot.save(&path).unwrap();
+    drop(chain);
+
+    let source = SnapshotCollateralSource::load(&path).unwrap();
+    let v3 = quote("v3_sgx_quote.hex");
+    assert_eq!(find_missing_collaterals_from_source(&source, &v3).await, MissingCollateral::None);
+    let collateral = get_quote_collateral_from_source(&source, &v3).await.unwrap();
+    assert_eq!(collateral.pck_crl, CRL);
+
+    // the TDX FMSPC was not exported
+    let v4 = quote("v4_tdx_quote.hex");
+    assert!(matches!(
+        find_missing_collaterals_from_source(&source, &v4).await,
+        MissingCollateral::FMSPCTCB(1, _, 3)
+    ));
+    assert!(is_not_stored(&source.tcb_info(1, "90c06f000000", 3).await.unwrap_err()));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_rejects_tampered_snapshots() {
+    let chain = full_chain().await;
+    let snapshot = reader(chain.url()).export_snapshot(&[]).await.unwrap();
+    assert!(snapshot.verify().is_ok());
+
+    let mut tampered = snapshot.clone();
+    tampered.certificates[0].crl = vec![0x30, 0x00];
+    assert!(tampered.verify().is_err());
+
+    let mut tampered = snapshot.clone();
+    tampered.enclave_identities[0].identity = tampered.enclave_identities[0]
+        .identity
+        .replace(r#""version":2"#, r#""version":3"#);
+    assert!(tampered.verify().is_err());
+
+    let mut newer = snapshot;
+    newer.format_version += 1;
+    assert!(newer.verify().is_err());
+}
+
+#[tokio::test]
+async fn test_export_fails_on_read_errors() {
+    // an identity DAO whose answer does not decode is not a DAO without identities
+    let mut contracts = dao_contracts(2, "00606a000000");
+    contracts.insert(ENCLAVE_ID_DAO, vec![0x01]);
+    let chain = LocalChain::start(contracts).await;
+
+    assert!(reader(chain.url()).export_snapshot(&[]).await.is_err());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use alloy::primitives::Bytes;
+use alloy::sol_types::SolCall;
+
+use common::{dao_contracts, reader, LocalChain, CERT, CRL, PCS_DAO};
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::pccs::is_not_stored;
+use pccs_reader_rs::pccs::pcs::IPCSDao;
+use pccs_reader_rs::source::{CollateralSource, IntelPcsCollateralSource};
+use pccs_reader_rs::CA;
+
+// pccs-reader-server speaks the Intel PCS API, so it stands in for Intel here
+#[tokio::test]
+async fn test_intel_pcs_source() {
+    let chain = LocalChain::start(dao_contracts(3, "00906ed50000")).await;
+    let reader = reader(chain.url());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}/", listener.local_addr().unwrap());
+    let app = pccs_reader_rs::server::router(reader.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let pcs = IntelPcsCollateralSource::new(&base_url);
+
+    let tcb_info = pcs.get_tcb_info(0, "00906ed50000", 3).await.unwrap();
+    assert_eq!(tcb_info.body, reader.get_tcb_info_obj(0, "00906ed50000", 3).await.unwrap());
+    assert_eq!(tcb_info.issuer_chain, vec![CERT.to_vec(), CERT.to_vec()]);
+
+    let identity = pcs.get_enclave_identity(EnclaveIdType::TDQE, 4).await.unwrap();
+    assert_eq!(
+        identity.body,
+        reader.get_enclave_identity_obj(EnclaveIdType::TDQE, 4).await.unwrap()
+    );
+
+    let pck_crl = pcs.get_pck_crl(CA::PLATFORM).await.unwrap();
+    assert_eq!(pck_crl.body, CRL.to_vec());
+    assert_eq!(pck_crl.issuer_chain.len(), 2);
+
+    assert_eq!(pcs.certificate(CA::ROOT).await.unwrap(), (CERT.to_vec(), CRL.to_vec()));
+    assert_eq!(pcs.certificate(CA::SIGNING).await.unwrap(), (CERT.to_vec(), vec![]));
+    assert_eq!(pcs.certificate(CA::PROCESSOR).await.unwrap(), (CERT.to_vec(), CRL.to_vec()));
+
+    // the v3 API does not serve TDX collateral
+    assert!(pcs.get_tcb_info(1, "00906ed50000", 2).await.is_err());
+    assert!(pcs.get_pck_crl(CA::ROOT).await.is_err());
+}
+
+#[tokio::test]
+async fn test_onchain_source() {
+    let chain = LocalChain::start(dao_contracts(3, "00906ed50000")).await;
+    let reader = reader(chain.url());
+    let source: &dyn CollateralSource = &reader;
+
+    assert_eq!(source.certificate(CA::ROOT).await.unwrap(), (CERT.to_vec(), CRL.to_vec()));
+    assert_eq!(
+        source.tcb_info(0, "00906ed50000", 3).await.unwrap().signature,
+        Bytes::from(vec![0xaa; 64])
+    );
+    assert!(source.enclave_identity(EnclaveIdType::QE, 4).await.is_ok());
+
+    // a PCS DAO without the certificate, which is not a failed read
+    let empty = IPCSDao::getCertificateByIdCall::abi_encode_returns(&(Bytes::new(), Bytes::new()));
+    let chain = LocalChain::start([(PCS_DAO, empty)].into()).await;
+    let reader = common::reader(chain.url());
+    let missing = CollateralSource::certificate(&reader, CA::ROOT).await.unwrap_err();
+    assert!(is_not_stored(&missing));
+}
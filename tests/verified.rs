@@ -0,0 +1,57 @@
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::providers::Provider;
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::proof::TrustedState;
+use pccs_reader_rs::{NetworkProfile, PccsReader, CA};
+
+// The layout shipped with the profile must locate and decode what the DAOs return
+#[tokio::test]
+#[ignore = "reads the Automata testnet"]
+async fn test_verifies_the_deployed_storage() {
+    let network = NetworkProfile::automata_testnet();
+    let layout = network.storage.unwrap();
+    let reader = PccsReader::new(network).unwrap().pin_block().await.unwrap();
+    let BlockId::Number(number) = reader.block() else {
+        panic!("expected a block number");
+    };
+    let block = reader
+        .provider()
+        .get_block_by_number(number, false)
+        .await
+        .unwrap()
+        .unwrap();
+    let BlockNumberOrTag::Number(number) = number else {
+        panic!("expected a block number");
+    };
+    let verified = reader.verified(TrustedState::new(block.header.state_root, number), layout);
+
+    for ca in [CA::ROOT, CA::PLATFORM, CA::PROCESSOR, CA::SIGNING] {
+        assert_eq!(
+            verified.get_certificate_by_id(ca).await.unwrap(),
+            reader.get_certificate_by_id(ca).await.unwrap()
+        );
+    }
+
+    let tcb_info = verified
+        .get_tcb_info_obj(0, "00606a000000", 3)
+        .await
+        .unwrap();
+    let called = reader.get_tcb_info_obj(0, "00606a000000", 3).await.unwrap();
+    assert_eq!(
+        (tcb_info.tcbInfoStr, tcb_info.signature),
+        (called.tcbInfoStr, called.signature)
+    );
+
+    let identity = verified
+        .get_enclave_identity_obj(EnclaveIdType::QE, 3)
+        .await
+        .unwrap();
+    let called = reader
+        .get_enclave_identity_obj(EnclaveIdType::QE, 3)
+        .await
+        .unwrap();
+    assert_eq!(
+        (identity.identityStr, identity.signature),
+        (called.identityStr, called.signature)
+    );
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use alloy::primitives::{Address, FixedBytes, B256, U256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use futures::stream::{BoxStream, StreamExt};
+use futures::SinkExt;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+
+use common::{reader, LocalChain, ENCLAVE_ID_DAO, FMSPC_TCB_DAO};
+use pccs_reader_rs::pccs::enclave_id::EnclaveIdType;
+use pccs_reader_rs::pccs::events::{CollateralItem, IEnclaveIdentityDaoEvents, IFmspcTcbDaoEvents};
+use pccs_reader_rs::watch::{CollateralChange, WatchOptions};
+
+// An upsert in a block whose hash tells the fork it belongs to apart
+fn tcb_info_upsert(block_number: u64, fork: u8) -> Log {
+    let data = IFmspcTcbDaoEvents::UpsertedFmspcTcb {
+        tcbType: 0,
+        fmspc: FixedBytes([0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]),
+        version: 3,
+        attestationId: B256::ZERO,
+    }
+    .encode_log_data();
+    log(FMSPC_TCB_DAO, data, block_number, fork)
+}
+
+fn identity_upsert(block_number: u64, fork: u8) -> Log {
+    let data = IEnclaveIdentityDaoEvents::UpsertedEnclaveIdentity {
+        id: U256::from(2),
+        version: U256::from(4),
+        attestationId: B256::ZERO,
+    }
+    .encode_log_data();
+    log(ENCLAVE_ID_DAO, data, block_number, fork)
+}
+
+fn log(address: Address, data: alloy::primitives::LogData, block_number: u64, fork: u8) -> Log {
+    Log {
+        inner: alloy::primitives::Log { address, data },
+        block_number: Some(block_number),
+        block_hash: Some(B256::from([fork; 32])),
+        transaction_hash: Some(B256::with_last_byte(block_number as u8)),
+        log_index: Some(0),
+        ..Default::default()
+    }
+}
+
+fn options(from_block: Option<u64>) -> WatchOptions {
+    WatchOptions {
+        from_block,
+        poll_interval: Duration::from_millis(20),
+        reorg_depth: 10,
+        ws_url: None,
+    }
+}
+
+async fn next(changes: &mut BoxStream<'static, anyhow::Result<CollateralChange>>) -> CollateralChange {
+    tokio::time::timeout(Duration::from_secs(5), changes.next())
+        .await
+        .expect("no change in time")
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_streams_upserts_and_reorgs() {
+    let chain = LocalChain::mock_with_logs(
+        HashMap::new(),
+        vec![tcb_info_upsert(2, 0xa0), identity_upsert(3, 0xa0)],
+    )
+    .await;
+    let mut changes = reader(chain.url()).watch_collateral(options(Some(1)));
+
+    // replayed from the first block
+    let first = next(&mut changes).await;
+    assert!(matches!(&first, CollateralChange::Upserted(event) if event.block_number == 2));
+    let second = next(&mut changes).await;
+    assert_eq!(
+        second.item(),
+        &CollateralItem::EnclaveIdentity {
+            id: EnclaveIdType::TDQE,
+            version: 4
+        }
+    );
+
+    // block 3 is replaced by another fork, which carries on to block 4
+    chain.set_logs(vec![
+        tcb_info_upsert(2, 0xa0),
+        identity_upsert(3, 0xb0),
+        tcb_info_upsert(4, 0xb0),
+    ]);
+    assert_eq!(next(&mut changes).await, CollateralChange::Reverted(match second {
+        CollateralChange::Upserted(event) => event,
+        other => panic!("unexpected {:?}", other),
+    }));
+    let reupserted = next(&mut changes).await;
+    assert!(matches!(&reupserted, CollateralChange::Upserted(event)
+        if event.block_number == 3 && event.block_hash == B256::from([0xb0; 32])));
+    let new_block = next(&mut changes).await;
+    assert!(matches!(&new_block, CollateralChange::Upserted(event) if event.block_number == 4));
+}
+
+#[tokio::test]
+async fn test_streams_only_new_upserts_by_default() {
+    let chain = LocalChain::mock_with_logs(HashMap::new(), vec![tcb_info_upsert(2, 0xa0)]).await;
+    let mut changes = reader(chain.url()).watch_collateral(options(None));
+
+    // let the first poll see the chain as it is
+    assert!(tokio::time::timeout(Duration::from_millis(100), changes.next()).await.is_err());
+
+    chain.set_logs(vec![tcb_info_upsert(2, 0xa0), identity_upsert(5, 0xa0)]);
+    let change = next(&mut changes).await;
+    assert!(matches!(&change, CollateralChange::Upserted(event) if event.block_number == 5));
+}
+
+// A WebSocket node that pushes the given logs to the subscription of each connection in
+// turn, and drops every connection but the last once it has pushed them
+async fn start_ws_node(connections: Vec<Vec<Log>>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let last = connections.len() - 1;
+        for (i, logs) in connections.into_iter().enumerate() {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let connection = async move {
+                while let Some(Ok(Message::Text(text))) = socket.next().await {
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    let subscribed = request["method"] == "eth_subscribe";
+                    let result = if subscribed { json!("0x1") } else { Value::Null };
+                    let response =
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                    socket.send(Message::Text(response.to_string())).await.unwrap();
+                    if !subscribed {
+                        continue;
+                    }
+                    for log in &logs {
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": { "subscription": "0x1", "result": log },
+                        });
+                        socket.send(Message::Text(notification.to_string())).await.unwrap();
+                    }
+                    if i < last {
+                        // once the subscription has taken the logs
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        return;
+                    }
+                }
+            };
+            if i < last {
+                connection.await;
+            } else {
+                tokio::spawn(connection);
+            }
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn test_resubscribes_over_a_dropped_websocket() {
+    let chain = LocalChain::mock_with_logs(HashMap::new(), vec![tcb_info_upsert(2, 0xa0)]).await;
+    let ws_url = start_ws_node(vec![
+        vec![identity_upsert(3, 0xa0)],
+        // pushed again although replayed, then a new block
+        vec![tcb_info_upsert(4, 0xa0), identity_upsert(5, 0xa0)],
+    ])
+    .await;
+    let mut changes = reader(chain.url()).watch_collateral(WatchOptions {
+        ws_url: Some(ws_url),
+        ..options(None)
+    });
+
+    let live = next(&mut changes).await;
+    assert!(matches!(&live, CollateralChange::Upserted(event) if event.block_number == 3));
+
+    // the connection drops, and block 4 is upserted before it is back
+    let dropped = tokio::time::timeout(Duration::from_secs(5), changes.next())
+        .await
+        .expect("no change in time")
+        .unwrap()
+        .unwrap_err();
+    assert!(dropped.to_string().contains("ended, reconnecting"), "{}", dropped);
+    chain.set_logs(vec![
+        tcb_info_upsert(2, 0xa0),
+        identity_upsert(3, 0xa0),
+        tcb_info_upsert(4, 0xa0),
+    ]);
+
+    // resumed from block 3 without reporting it again
+    let replayed = next(&mut changes).await;
+    assert!(matches!(&replayed, CollateralChange::Upserted(event) if event.block_number == 4));
+    let new_block = next(&mut changes).await;
+    assert!(matches!(&new_block, CollateralChange::Upserted(event) if event.block_number == 5));
+}